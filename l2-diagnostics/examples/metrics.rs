@@ -0,0 +1,44 @@
+#![allow(unused_crate_dependencies, reason = "used in library")]
+
+//! Receives packets from the lidar and exposes metrics about them for Prometheus.
+//!
+//! Usage: `cargo run --example metrics --features prometheus -- [192.168.1.62:6101] [127.0.0.1:9100] [192.168.1.2:6201]`
+//!
+//! The metrics can then be scraped with `curl http://127.0.0.1:9100/metrics`.
+
+use std::{env, net::SocketAddr};
+
+use anyhow::Result;
+use futures::StreamExt;
+use l2_diagnostics::{Metrics, serve};
+use l2_protocol::{DEFAULT_LIDAR_ADDR, DEFAULT_USER_ADDR, UdpSource};
+use tokio::net::TcpListener;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut args = env::args().skip(1);
+    let lidar_addr = args
+        .next()
+        .map_or(Ok(DEFAULT_LIDAR_ADDR), |arg| arg.parse::<SocketAddr>())?;
+    let listen_addr = args.next().map_or_else(
+        || Ok(SocketAddr::from(([127, 0, 0, 1], 9100))),
+        |arg| arg.parse(),
+    )?;
+    let user_addr = args
+        .next()
+        .map_or(Ok(DEFAULT_USER_ADDR), |arg| arg.parse::<SocketAddr>())?;
+
+    let mut metrics = Metrics::new()?;
+    let listener = TcpListener::bind(listen_addr).await?;
+    tokio::spawn(serve(listener, metrics.registry().clone()));
+    println!("serving metrics on http://{listen_addr}/metrics");
+
+    let mut source = UdpSource::bind(user_addr, lidar_addr).await?;
+    while let Some(packet) = source.next().await {
+        metrics.observe(&packet?);
+        metrics.record_codec_stats(source.stats());
+    }
+
+    println!("LIDAR disconnected");
+    Ok(())
+}
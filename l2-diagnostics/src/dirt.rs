@@ -0,0 +1,492 @@
+use std::{f32::consts::PI, ops::Range, time::Duration};
+
+use l2_processing::{BeamModel, Calibration};
+use l2_protocol::{LidarCalibParam, LidarInsideState, LidarPointData, LidarPointDataRef};
+
+/// Settings of a [`DirtMonitor`]
+#[derive(Debug, Clone)]
+pub struct DirtConfig {
+    /// smoothed dirty index above which the window is considered soiled; `None` to rely on the
+    /// sectors alone
+    pub max_dirty_index: Option<f32>,
+    /// time constant of the exponential smoothing of the dirty index
+    pub smoothing: Duration,
+    /// number of sectors the azimuth of the beams is divided into
+    pub azimuth_sectors: usize,
+    /// number of sectors the elevation of the beams between -90° and 90° is divided into
+    pub elevation_sectors: usize,
+    /// weight of the latest revolution of the horizontal motor in the smoothed statistics of a
+    /// sector
+    pub revolution_weight: f32,
+    /// time after which a revolution is closed even if the horizontal motor hasn't turned a full
+    /// circle, e.g. because it has stalled
+    pub revolution_timeout: Duration,
+    /// number of revolutions the baseline of a sector is averaged over; sectors are only judged
+    /// once that many have been seen
+    pub baseline_revolutions: u32,
+    /// relative drop of the valid return ratio below the baseline at which a sector is affected
+    pub valid_ratio_drop: f32,
+    /// relative drop of the mean intensity below the baseline at which a sector is affected
+    pub intensity_drop: f32,
+    /// sectors with a lower valid return ratio in their baseline, e.g. facing the sky, are not
+    /// judged
+    pub min_valid_ratio: f32,
+    /// revolutions with fewer beams in a sector don't update it
+    pub min_beams: u32,
+    /// number of affected sectors needed to raise an advisory
+    pub min_affected_sectors: usize,
+    /// number of consecutive revolutions an advisory needs to be warranted or not before it is
+    /// raised or cleared
+    pub debounce: u32,
+}
+
+impl Default for DirtConfig {
+    fn default() -> Self {
+        Self {
+            max_dirty_index: None,
+            smoothing: Duration::from_secs(10),
+            azimuth_sectors: 12,
+            elevation_sectors: 6,
+            revolution_weight: 0.3,
+            revolution_timeout: Duration::from_secs(2),
+            baseline_revolutions: 20,
+            valid_ratio_drop: 0.2,
+            intensity_drop: 0.3,
+            min_valid_ratio: 0.2,
+            min_beams: 20,
+            min_affected_sectors: 1,
+            debounce: 3,
+        }
+    }
+}
+
+/// Statistics of a part of the field of view
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirtSector {
+    /// [rad]
+    pub azimuth: Range<f32>,
+    /// [rad]
+    pub elevation: Range<f32>,
+    /// smoothed share of beams with a return
+    pub valid_ratio: f32,
+    pub baseline_valid_ratio: f32,
+    /// smoothed mean intensity of the returns
+    pub intensity: f32,
+    pub baseline_intensity: f32,
+    /// whether the returns have dropped enough below the baseline to suspect dirt
+    pub affected: bool,
+}
+
+/// Recommendation to clean the optical window
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirtAdvisory {
+    /// timestamp of the end of the revolution the advisory is based on
+    pub stamp: Duration,
+    /// smoothed dirty index
+    pub dirty_index: f32,
+    /// sectors in which the window is likely soiled
+    pub sectors: Vec<DirtSector>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DirtEvent {
+    /// the window has appeared soiled for the configured number of revolutions
+    Raised(DirtAdvisory),
+    /// the affected sectors of a raised advisory have changed
+    Updated(DirtAdvisory),
+    /// a raised advisory hasn't been warranted for the configured number of revolutions
+    Cleared { stamp: Duration, dirty_index: f32 },
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct SectorState {
+    /// counters of the current revolution
+    beams: u32,
+    returns: u32,
+    intensity_sum: u32,
+    /// smoothed statistics, `None` before the first revolution with enough beams
+    valid_ratio: Option<f32>,
+    intensity: Option<f32>,
+    baseline_valid_ratio: Option<f32>,
+    baseline_intensity: Option<f32>,
+    baseline_revolutions: u32,
+    affected: bool,
+}
+
+impl SectorState {
+    fn close_revolution(&mut self, config: &DirtConfig) {
+        let beams = self.beams;
+        let returns = self.returns;
+        let intensity_sum = self.intensity_sum;
+        (self.beams, self.returns, self.intensity_sum) = (0, 0, 0);
+        if beams < config.min_beams.max(1) {
+            return;
+        }
+
+        #[expect(clippy::cast_precision_loss, reason = "only a ratio is needed")]
+        let valid_ratio = returns as f32 / beams as f32;
+        #[expect(clippy::cast_precision_loss, reason = "only an average is needed")]
+        let intensity = (returns > 0).then(|| intensity_sum as f32 / returns as f32);
+
+        let weight = config.revolution_weight.clamp(0.0, 1.0);
+        self.valid_ratio = Some(smooth(self.valid_ratio, valid_ratio, weight));
+        if let Some(intensity) = intensity {
+            self.intensity = Some(smooth(self.intensity, intensity, weight));
+        }
+
+        let baseline_ready = self.baseline_revolutions >= config.baseline_revolutions;
+        self.affected = baseline_ready && self.is_degraded(config);
+        if !self.affected {
+            // the baseline must not absorb the dirt it is meant to reveal
+            #[expect(clippy::cast_precision_loss, reason = "only a weight is needed")]
+            let baseline_weight =
+                1.0 / (self.baseline_revolutions.min(config.baseline_revolutions) + 1) as f32;
+            self.baseline_valid_ratio = Some(smooth(
+                self.baseline_valid_ratio,
+                valid_ratio,
+                baseline_weight,
+            ));
+            if let Some(intensity) = intensity {
+                self.baseline_intensity =
+                    Some(smooth(self.baseline_intensity, intensity, baseline_weight));
+            }
+            self.baseline_revolutions = self.baseline_revolutions.saturating_add(1);
+        }
+    }
+
+    fn is_degraded(&self, config: &DirtConfig) -> bool {
+        let (Some(valid_ratio), Some(baseline_valid_ratio)) =
+            (self.valid_ratio, self.baseline_valid_ratio)
+        else {
+            return false;
+        };
+        if baseline_valid_ratio < config.min_valid_ratio {
+            return false;
+        }
+        let valid_ratio_dropped =
+            valid_ratio < baseline_valid_ratio * (1.0 - config.valid_ratio_drop);
+        let intensity_dropped =
+            self.intensity
+                .zip(self.baseline_intensity)
+                .is_some_and(|(intensity, baseline)| {
+                    intensity < baseline * (1.0 - config.intensity_drop)
+                });
+        valid_ratio_dropped || intensity_dropped
+    }
+}
+
+fn smooth(previous: Option<f32>, value: f32, weight: f32) -> f32 {
+    previous.map_or(value, |previous| previous + (value - previous) * weight)
+}
+
+/// The line info of a point data packet needed to locate its beams
+#[derive(Debug, Clone, Copy)]
+struct Line {
+    stamp: Duration,
+    state: LidarInsideState,
+    param: LidarCalibParam,
+    range_limits: (f32, f32),
+    angle_min: f32,
+    angle_increment: f32,
+    horizontal_angle_start: f32,
+    horizontal_angle_step: f32,
+}
+
+/// Watches for a soiled optical window.
+///
+/// The `dirty_index` in [`LidarInsideState`] is smoothed over time and correlated with the returns
+/// in every sector of the field of view over each revolution of the horizontal motor: a sector is
+/// affected if its share of beams with a return or the mean intensity of its returns drops below
+/// its own baseline. Advisories name the affected
+/// sectors, telling which part of the window to clean. Changes in the surroundings, e.g. when the
+/// lidar is moved, look like dirt in the sectors, so the dirty index confirms them if
+/// [`DirtConfig::max_dirty_index`] is set.
+#[derive(Debug)]
+pub struct DirtMonitor {
+    config: DirtConfig,
+    dirty_index: Option<f32>,
+    last_stamp: Option<Duration>,
+    /// horizontal angle covered by the current revolution [rad]
+    swept: f32,
+    /// timestamp of the first packet of the current revolution
+    revolution_start: Option<Duration>,
+    sectors: Vec<SectorState>,
+    advisory: Option<DirtAdvisory>,
+    /// number of consecutive revolutions contradicting whether an advisory is raised
+    streak: u32,
+}
+
+impl DirtMonitor {
+    #[must_use]
+    pub fn new(config: DirtConfig) -> Self {
+        let sector_count = config.azimuth_sectors.max(1) * config.elevation_sectors.max(1);
+        Self {
+            config,
+            dirty_index: None,
+            last_stamp: None,
+            swept: 0.0,
+            revolution_start: None,
+            sectors: vec![SectorState::default(); sector_count],
+            advisory: None,
+            streak: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn config(&self) -> &DirtConfig {
+        &self.config
+    }
+
+    /// Adds a packet and returns the event caused by the revolution it completes, if any.
+    pub fn update_point_data(&mut self, data: &LidarPointData) -> Option<DirtEvent> {
+        let line = Line {
+            stamp: data.stamp(),
+            state: data.state(),
+            param: data.param(),
+            range_limits: (data.range_min(), data.range_max()),
+            angle_min: data.angle_min(),
+            angle_increment: data.angle_increment(),
+            horizontal_angle_start: data.com_horizontal_angle_start(),
+            horizontal_angle_step: data.com_horizontal_angle_step(),
+        };
+        let ranges = data.ranges().iter().copied();
+        self.update(&line, ranges.zip(data.intensities().iter().copied()))
+    }
+
+    /// Adds a packet and returns the event caused by the revolution it completes, if any.
+    pub fn update_point_data_ref(&mut self, data: &LidarPointDataRef<'_>) -> Option<DirtEvent> {
+        let line = Line {
+            stamp: data.stamp(),
+            state: data.state(),
+            param: data.param(),
+            range_limits: (data.range_min(), data.range_max()),
+            angle_min: data.angle_min(),
+            angle_increment: data.angle_increment(),
+            horizontal_angle_start: data.com_horizontal_angle_start(),
+            horizontal_angle_step: data.com_horizontal_angle_step(),
+        };
+        self.update(&line, data.ranges().zip(data.intensities().iter().copied()))
+    }
+
+    fn update(&mut self, line: &Line, beams: impl Iterator<Item = (u16, u8)>) -> Option<DirtEvent> {
+        let stamp = line.stamp;
+        if self.last_stamp.is_some_and(|last| stamp < last) {
+            // the lidar has been restarted or its clock has been set
+            self.dirty_index = None;
+            self.swept = 0.0;
+            self.revolution_start = None;
+            for sector in &mut self.sectors {
+                (sector.beams, sector.returns, sector.intensity_sum) = (0, 0, 0);
+            }
+        }
+        let elapsed = self
+            .last_stamp
+            .map_or(Duration::ZERO, |last| stamp.saturating_sub(last));
+        self.last_stamp = Some(stamp);
+
+        let weight = if self.config.smoothing.is_zero() {
+            1.0
+        } else {
+            1.0 - (-elapsed.as_secs_f32() / self.config.smoothing.as_secs_f32()).exp()
+        };
+        self.dirty_index = Some(smooth(self.dirty_index, line.state.dirty_index(), weight));
+
+        let model = BeamModel::new(Calibration::from(line.param));
+        let (range_min, range_max) = line.range_limits;
+        let mut alpha = line.angle_min;
+        let mut theta = line.horizontal_angle_start;
+        for (range, intensity) in beams {
+            let direction = model.direction(alpha, theta);
+            let azimuth = direction.y.atan2(direction.x);
+            let elevation = direction.z.atan2(direction.x.hypot(direction.y));
+            let index = self.sector_index(azimuth, elevation);
+            let range = model.range(range);
+            if let Some(sector) = self.sectors.get_mut(index) {
+                sector.beams = sector.beams.saturating_add(1);
+                if (range_min..=range_max).contains(&range) {
+                    sector.returns = sector.returns.saturating_add(1);
+                    sector.intensity_sum =
+                        sector.intensity_sum.saturating_add(u32::from(intensity));
+                }
+            }
+
+            alpha += line.angle_increment;
+            theta += line.horizontal_angle_step;
+        }
+
+        self.swept += (theta - line.horizontal_angle_start).abs();
+        let revolution_start = *self.revolution_start.get_or_insert(stamp);
+        if self.swept < 2.0 * PI
+            && stamp.saturating_sub(revolution_start) < self.config.revolution_timeout
+        {
+            return None;
+        }
+        self.swept = 0.0;
+        self.revolution_start = None;
+        self.close_revolution(stamp)
+    }
+
+    fn close_revolution(&mut self, stamp: Duration) -> Option<DirtEvent> {
+        for sector in &mut self.sectors {
+            sector.close_revolution(&self.config);
+        }
+
+        let dirty_index = self.dirty_index.unwrap_or_default();
+        let sectors = self
+            .sectors()
+            .filter(|sector| sector.affected)
+            .collect::<Vec<_>>();
+        let warranted = sectors.len() >= self.config.min_affected_sectors.max(1)
+            && self
+                .config
+                .max_dirty_index
+                .is_none_or(|limit| dirty_index > limit);
+
+        if warranted != self.advisory.is_some() {
+            self.streak += 1;
+            if self.streak < self.config.debounce.max(1) {
+                return None;
+            }
+            self.streak = 0;
+            return if warranted {
+                let advisory = DirtAdvisory {
+                    stamp,
+                    dirty_index,
+                    sectors,
+                };
+                self.advisory = Some(advisory.clone());
+                Some(DirtEvent::Raised(advisory))
+            } else {
+                self.advisory = None;
+                Some(DirtEvent::Cleared { stamp, dirty_index })
+            };
+        }
+
+        self.streak = 0;
+        let advisory = self.advisory.as_mut()?;
+        advisory.stamp = stamp;
+        advisory.dirty_index = dirty_index;
+        let changed = advisory.sectors.len() != sectors.len()
+            || advisory
+                .sectors
+                .iter()
+                .zip(&sectors)
+                .any(|(previous, current)| {
+                    previous.azimuth != current.azimuth || previous.elevation != current.elevation
+                });
+        advisory.sectors = sectors;
+        changed.then(|| DirtEvent::Updated(advisory.clone()))
+    }
+
+    /// Index of the sector containing the direction, sectors of the same elevation being adjacent
+    fn sector_index(&self, azimuth: f32, elevation: f32) -> usize {
+        let azimuth_sectors = self.config.azimuth_sectors.max(1);
+        let elevation_sectors = self.config.elevation_sectors.max(1);
+        #[expect(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            clippy::cast_precision_loss,
+            reason = "the fractions are clamped to [0, 1)"
+        )]
+        let bin = |fraction: f32, count: usize| {
+            ((fraction.clamp(0.0, 1.0) * count as f32) as usize).min(count - 1)
+        };
+        bin((elevation + PI / 2.0) / PI, elevation_sectors) * azimuth_sectors
+            + bin(azimuth.rem_euclid(2.0 * PI) / (2.0 * PI), azimuth_sectors)
+    }
+
+    /// Smoothed dirty index; `None` before the first packet
+    #[must_use]
+    pub fn dirty_index(&self) -> Option<f32> {
+        self.dirty_index
+    }
+
+    /// Statistics of all sectors which have seen a revolution with enough beams
+    pub fn sectors(&self) -> impl Iterator<Item = DirtSector> + '_ {
+        let azimuth_sectors = self.config.azimuth_sectors.max(1);
+        #[expect(clippy::cast_precision_loss, reason = "sector counts are small")]
+        let azimuth_width = 2.0 * PI / azimuth_sectors as f32;
+        #[expect(clippy::cast_precision_loss, reason = "sector counts are small")]
+        let elevation_height = PI / self.config.elevation_sectors.max(1) as f32;
+
+        self.sectors
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, sector)| {
+                #[expect(clippy::cast_precision_loss, reason = "sector counts are small")]
+                let azimuth = (index % azimuth_sectors) as f32 * azimuth_width;
+                #[expect(clippy::cast_precision_loss, reason = "sector counts are small")]
+                let elevation = (index / azimuth_sectors) as f32 * elevation_height - PI / 2.0;
+                Some(DirtSector {
+                    azimuth: azimuth..azimuth + azimuth_width,
+                    elevation: elevation..elevation + elevation_height,
+                    valid_ratio: sector.valid_ratio?,
+                    baseline_valid_ratio: sector.baseline_valid_ratio?,
+                    intensity: sector.intensity.unwrap_or_default(),
+                    baseline_intensity: sector.baseline_intensity.unwrap_or_default(),
+                    affected: sector.affected,
+                })
+            })
+    }
+
+    /// The advisory currently raised
+    #[must_use]
+    pub fn advisory(&self) -> Option<&DirtAdvisory> {
+        self.advisory.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::point_data;
+
+    /// A point data packet of 300 beams from a stalled horizontal motor
+    fn stalled_point_data(sec: u32) -> Box<LidarPointData> {
+        point_data(json!({
+            "info": {"stamp": {"sec": sec}},
+            "ranges": vec![1000_u16; 300],
+            "intensities": vec![100_u8; 300],
+        }))
+    }
+
+    #[test]
+    fn closes_revolutions_of_a_stalled_motor_after_the_timeout() {
+        let mut monitor = DirtMonitor::new(DirtConfig::default());
+        let [first, second, third] = [0, 1, 2].map(stalled_point_data);
+
+        monitor.update_point_data(&first);
+        monitor.update_point_data(&second);
+        assert_eq!(
+            monitor.sectors().count(),
+            0,
+            "the revolution should still be open"
+        );
+
+        monitor.update_point_data(&third);
+        assert_eq!(
+            monitor.sectors().count(),
+            1,
+            "the revolution should be closed, all beams hitting the same sector"
+        );
+    }
+
+    #[test]
+    fn saturates_the_counters_of_a_revolution() {
+        let mut sector = SectorState {
+            beams: u32::MAX,
+            returns: u32::MAX,
+            intensity_sum: u32::MAX,
+            ..SectorState::default()
+        };
+        sector.close_revolution(&DirtConfig::default());
+        assert_eq!(sector.valid_ratio, Some(1.0), "all beams had a return");
+        assert_eq!(
+            (sector.beams, sector.returns, sector.intensity_sum),
+            (0, 0, 0),
+            "the counters should be reset"
+        );
+    }
+}
@@ -0,0 +1,445 @@
+use std::{collections::VecDeque, ops::RangeInclusive, time::Duration};
+
+use l2_protocol::{LidarInsideState, LidarPointData, LidarPointDataRef};
+
+/// A value reported in [`LidarInsideState`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HealthMetric {
+    SysRotationPeriod,
+    ComRotationPeriod,
+    DirtyIndex,
+    PacketLostUp,
+    PacketLostDown,
+    ApdTemperature,
+    ApdVoltage,
+    LaserVoltage,
+    ImuTemperature,
+}
+
+impl HealthMetric {
+    pub const ALL: [Self; 9] = [
+        Self::SysRotationPeriod,
+        Self::ComRotationPeriod,
+        Self::DirtyIndex,
+        Self::PacketLostUp,
+        Self::PacketLostDown,
+        Self::ApdTemperature,
+        Self::ApdVoltage,
+        Self::LaserVoltage,
+        Self::ImuTemperature,
+    ];
+
+    #[must_use]
+    pub fn value(self, state: &LidarInsideState) -> f64 {
+        match self {
+            Self::SysRotationPeriod => state.sys_rotation_period().into(),
+            Self::ComRotationPeriod => state.com_rotation_period().into(),
+            Self::DirtyIndex => state.dirty_index().into(),
+            Self::PacketLostUp => state.packet_lost_up().into(),
+            Self::PacketLostDown => state.packet_lost_down().into(),
+            Self::ApdTemperature => state.apd_temperature().into(),
+            Self::ApdVoltage => state.apd_voltage().into(),
+            Self::LaserVoltage => state.laser_voltage().into(),
+            Self::ImuTemperature => state.imu_temperature().into(),
+        }
+    }
+}
+
+/// Statistics of a [`HealthMetric`] within the monitor's window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HealthStats {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub last: f64,
+    /// change per second between the first and the last sample; `None` if they share a timestamp
+    pub rate: Option<f64>,
+}
+
+/// A problem detected by the [`HealthMonitor`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HealthCondition {
+    /// the optical window is dirty and should be cleaned
+    DirtyWindow,
+    ApdOverheating,
+    ImuOverheating,
+    ApdVoltageOutOfRange,
+    LaserVoltageOutOfRange,
+    /// rotation of the horizontal motor is out of range
+    SysMotorSpeedOutOfRange,
+    /// rotation of the vertical motor is out of range
+    ComMotorSpeedOutOfRange,
+    /// the upper board loses too many packets
+    PacketLossUp,
+    /// the lower board loses too many packets
+    PacketLossDown,
+}
+
+impl HealthCondition {
+    const ALL: [Self; 9] = [
+        Self::DirtyWindow,
+        Self::ApdOverheating,
+        Self::ImuOverheating,
+        Self::ApdVoltageOutOfRange,
+        Self::LaserVoltageOutOfRange,
+        Self::SysMotorSpeedOutOfRange,
+        Self::ComMotorSpeedOutOfRange,
+        Self::PacketLossUp,
+        Self::PacketLossDown,
+    ];
+
+    /// the value this condition is based on
+    #[must_use]
+    pub fn metric(self) -> HealthMetric {
+        match self {
+            Self::DirtyWindow => HealthMetric::DirtyIndex,
+            Self::ApdOverheating => HealthMetric::ApdTemperature,
+            Self::ImuOverheating => HealthMetric::ImuTemperature,
+            Self::ApdVoltageOutOfRange => HealthMetric::ApdVoltage,
+            Self::LaserVoltageOutOfRange => HealthMetric::LaserVoltage,
+            Self::SysMotorSpeedOutOfRange => HealthMetric::SysRotationPeriod,
+            Self::ComMotorSpeedOutOfRange => HealthMetric::ComRotationPeriod,
+            Self::PacketLossUp => HealthMetric::PacketLostUp,
+            Self::PacketLossDown => HealthMetric::PacketLostDown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HealthEvent {
+    /// the condition has been present for the configured number of samples
+    Raised {
+        condition: HealthCondition,
+        value: f64,
+    },
+    /// a raised condition has been absent for the configured number of samples
+    Cleared {
+        condition: HealthCondition,
+        value: f64,
+    },
+}
+
+/// Limits for the values of [`LidarInsideState`].
+///
+/// The limits depend on the unit and its environment, so none are set by default.
+#[derive(Debug, Clone)]
+pub struct HealthThresholds {
+    /// dirty index above which the optical window should be cleaned
+    pub max_dirty_index: Option<f64>,
+    /// [°C]
+    pub max_apd_temperature: Option<f64>,
+    pub max_imu_temperature: Option<f64>,
+    /// [V]
+    pub apd_voltage: Option<RangeInclusive<f64>>,
+    /// [V]
+    pub laser_voltage: Option<RangeInclusive<f64>>,
+    pub sys_rotation_period: Option<RangeInclusive<f64>>,
+    pub com_rotation_period: Option<RangeInclusive<f64>>,
+    /// packet loss rate of either board
+    pub max_packet_loss: Option<f64>,
+    /// number of consecutive samples a condition needs to be present or absent before it is
+    /// raised or cleared
+    pub debounce: u32,
+}
+
+impl Default for HealthThresholds {
+    fn default() -> Self {
+        Self {
+            max_dirty_index: None,
+            max_apd_temperature: None,
+            max_imu_temperature: None,
+            apd_voltage: None,
+            laser_voltage: None,
+            sys_rotation_period: None,
+            com_rotation_period: None,
+            max_packet_loss: None,
+            debounce: 5,
+        }
+    }
+}
+
+impl HealthThresholds {
+    fn is_violated(&self, condition: HealthCondition, value: f64) -> bool {
+        let above = |limit: Option<f64>| limit.is_some_and(|limit| value > limit);
+        let outside = |range: Option<&RangeInclusive<f64>>| {
+            range.is_some_and(|range| !range.contains(&value))
+        };
+
+        match condition {
+            HealthCondition::DirtyWindow => above(self.max_dirty_index),
+            HealthCondition::ApdOverheating => above(self.max_apd_temperature),
+            HealthCondition::ImuOverheating => above(self.max_imu_temperature),
+            HealthCondition::ApdVoltageOutOfRange => outside(self.apd_voltage.as_ref()),
+            HealthCondition::LaserVoltageOutOfRange => outside(self.laser_voltage.as_ref()),
+            HealthCondition::SysMotorSpeedOutOfRange => outside(self.sys_rotation_period.as_ref()),
+            HealthCondition::ComMotorSpeedOutOfRange => outside(self.com_rotation_period.as_ref()),
+            HealthCondition::PacketLossUp | HealthCondition::PacketLossDown => {
+                above(self.max_packet_loss)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ConditionState {
+    pub(crate) raised: bool,
+    /// number of consecutive samples contradicting `raised`
+    streak: u32,
+}
+
+impl ConditionState {
+    /// Counts a sample and returns whether the condition has been raised (`true`) or cleared
+    /// (`false`) by it.
+    pub(crate) fn update(&mut self, violated: bool, debounce: u32) -> Option<bool> {
+        if violated == self.raised {
+            self.streak = 0;
+            return None;
+        }
+
+        self.streak += 1;
+        if self.streak < debounce.max(1) {
+            return None;
+        }
+        *self = Self {
+            raised: violated,
+            streak: 0,
+        };
+        Some(violated)
+    }
+}
+
+/// Tracks the [`LidarInsideState`] embedded in every point data packet.
+///
+/// Statistics are computed over a sliding time window while thresholds are evaluated for every
+/// sample.
+#[derive(Debug)]
+pub struct HealthMonitor {
+    thresholds: HealthThresholds,
+    window: Duration,
+    samples: VecDeque<(Duration, LidarInsideState)>,
+    conditions: [ConditionState; HealthCondition::ALL.len()],
+}
+
+impl HealthMonitor {
+    #[must_use]
+    pub fn new(thresholds: HealthThresholds, window: Duration) -> Self {
+        Self {
+            thresholds,
+            window,
+            samples: VecDeque::new(),
+            conditions: Default::default(),
+        }
+    }
+
+    /// Adds a sample and returns the conditions that have been raised or cleared by it.
+    pub fn update(&mut self, stamp: Duration, state: LidarInsideState) -> Vec<HealthEvent> {
+        if self.samples.back().is_some_and(|(last, _)| stamp < *last) {
+            // the lidar has been restarted or its clock has been set
+            self.samples.clear();
+        }
+        self.samples.push_back((stamp, state));
+        while self
+            .samples
+            .front()
+            .is_some_and(|(first, _)| stamp.saturating_sub(*first) > self.window)
+        {
+            self.samples.pop_front();
+        }
+
+        let mut events = Vec::new();
+        for (condition, condition_state) in
+            HealthCondition::ALL.into_iter().zip(&mut self.conditions)
+        {
+            let value = condition.metric().value(&state);
+            let violated = self.thresholds.is_violated(condition, value);
+            match condition_state.update(violated, self.thresholds.debounce) {
+                Some(true) => events.push(HealthEvent::Raised { condition, value }),
+                Some(false) => events.push(HealthEvent::Cleared { condition, value }),
+                None => {}
+            }
+        }
+
+        events
+    }
+
+    pub fn update_point_data(&mut self, data: &LidarPointData) -> Vec<HealthEvent> {
+        self.update(data.stamp(), data.state())
+    }
+
+    pub fn update_point_data_ref(&mut self, data: &LidarPointDataRef<'_>) -> Vec<HealthEvent> {
+        self.update(data.stamp(), data.state())
+    }
+
+    /// Statistics of the given value within the window; `None` before the first sample
+    #[must_use]
+    pub fn stats(&self, metric: HealthMetric) -> Option<HealthStats> {
+        let (first_stamp, first) = self.samples.front()?;
+        let (last_stamp, last) = self.samples.back()?;
+        let first = metric.value(first);
+        let last = metric.value(last);
+
+        let mut min = f64::INFINITY;
+        let mut max = f64::NEG_INFINITY;
+        let mut sum = 0.0;
+        let mut count = 0.0;
+        for (_, state) in &self.samples {
+            let value = metric.value(state);
+            min = min.min(value);
+            max = max.max(value);
+            sum += value;
+            count += 1.0;
+        }
+
+        let elapsed = last_stamp.saturating_sub(*first_stamp).as_secs_f64();
+        Some(HealthStats {
+            min,
+            max,
+            mean: sum / count,
+            last,
+            rate: (elapsed > 0.0).then(|| (last - first) / elapsed),
+        })
+    }
+
+    /// Conditions that are currently raised
+    pub fn active(&self) -> impl Iterator<Item = HealthCondition> + '_ {
+        HealthCondition::ALL
+            .into_iter()
+            .zip(&self.conditions)
+            .filter_map(|(condition, state)| state.raised.then_some(condition))
+    }
+
+    /// The most recent sample
+    #[must_use]
+    pub fn latest(&self) -> Option<LidarInsideState> {
+        self.samples.back().map(|(_, state)| *state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::inside_state;
+
+    const HOT: f64 = 70.0;
+    const COOL: f64 = 40.0;
+
+    fn monitor() -> HealthMonitor {
+        HealthMonitor::new(
+            HealthThresholds {
+                max_apd_temperature: Some(60.0),
+                debounce: 3,
+                ..HealthThresholds::default()
+            },
+            Duration::from_secs(10),
+        )
+    }
+
+    /// Adds a sample per second with the given APD temperatures, returning the events of each.
+    fn feed(
+        monitor: &mut HealthMonitor,
+        start: u64,
+        temperatures: &[f64],
+    ) -> Vec<Vec<HealthEvent>> {
+        (start..)
+            .zip(temperatures)
+            .map(|(sec, temperature)| {
+                monitor.update(
+                    Duration::from_secs(sec),
+                    inside_state(json!({"apd_temperature": temperature})),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn raises_after_debounce_consecutive_violations() {
+        let mut monitor = monitor();
+
+        let interrupted = feed(&mut monitor, 0, &[HOT, HOT, COOL, HOT, HOT]);
+        assert!(
+            interrupted.iter().all(Vec::is_empty),
+            "interrupted violations shouldn't raise: {interrupted:?}"
+        );
+
+        let raised = feed(&mut monitor, 5, &[HOT]);
+        assert_eq!(
+            raised,
+            [vec![HealthEvent::Raised {
+                condition: HealthCondition::ApdOverheating,
+                value: HOT,
+            }]],
+            "the third consecutive violation should raise"
+        );
+        assert!(
+            monitor.active().eq([HealthCondition::ApdOverheating]),
+            "the condition should be active"
+        );
+    }
+
+    #[test]
+    fn clears_after_debounce_consecutive_passes() {
+        let mut monitor = monitor();
+        feed(&mut monitor, 0, &[HOT, HOT, HOT]);
+
+        let interrupted = feed(&mut monitor, 3, &[COOL, COOL, HOT, COOL, COOL]);
+        assert!(
+            interrupted.iter().all(Vec::is_empty),
+            "interrupted passes shouldn't clear: {interrupted:?}"
+        );
+
+        let cleared = feed(&mut monitor, 8, &[COOL]);
+        assert_eq!(
+            cleared,
+            [vec![HealthEvent::Cleared {
+                condition: HealthCondition::ApdOverheating,
+                value: COOL,
+            }]],
+            "the third consecutive pass should clear"
+        );
+        assert_eq!(monitor.active().count(), 0, "no condition should be active");
+    }
+
+    #[test]
+    fn evicts_samples_outside_the_window() {
+        let mut monitor = monitor();
+        for (sec, temperature) in [(0, 10.0), (8, 20.0), (16, 30.0)] {
+            monitor.update(
+                Duration::from_secs(sec),
+                inside_state(json!({"apd_temperature": temperature})),
+            );
+        }
+
+        let stats = monitor
+            .stats(HealthMetric::ApdTemperature)
+            .expect("there should be samples");
+        assert_eq!(
+            stats,
+            HealthStats {
+                min: 20.0,
+                max: 30.0,
+                mean: 25.0,
+                last: 30.0,
+                rate: Some(1.25),
+            },
+            "only the samples within 10 s of the latest should count"
+        );
+    }
+
+    #[test]
+    fn restarts_the_window_when_the_clock_jumps_back() {
+        let mut monitor = monitor();
+        feed(&mut monitor, 100, &[10.0, 20.0]);
+        feed(&mut monitor, 0, &[30.0]);
+
+        let stats = monitor
+            .stats(HealthMetric::ApdTemperature)
+            .expect("there should be samples");
+        assert_eq!(
+            (stats.min, stats.rate),
+            (30.0, None),
+            "samples from before the restart should be dropped"
+        );
+    }
+}
@@ -0,0 +1,27 @@
+#![allow(unused_crate_dependencies, reason = "used in examples")]
+
+//! Monitoring of the lidar's condition based on the data it reports
+
+mod dirt;
+mod health;
+#[cfg(feature = "prometheus")]
+mod metrics;
+mod motor;
+mod sequence;
+#[cfg(test)]
+mod testing;
+
+pub use dirt::{DirtAdvisory, DirtConfig, DirtEvent, DirtMonitor, DirtSector};
+pub use health::{
+    HealthCondition, HealthEvent, HealthMetric, HealthMonitor, HealthStats, HealthThresholds,
+};
+#[cfg(feature = "prometheus")]
+pub use metrics::{Metrics, serve};
+pub use motor::{
+    Motor, MotorCondition, MotorEvent, MotorMonitor, MotorSpeed, MotorTolerances, RotationEncoding,
+    ScanRates,
+};
+pub use sequence::{
+    PacketLoss, PacketStream, SequenceEvent, SequenceReorderer, SequenceStats, SequenceTracker,
+    StreamMonitor,
+};
@@ -0,0 +1,335 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use l2_protocol::{CodecStats, Packet};
+use prometheus::{
+    Encoder, Gauge, GaugeVec, IntCounter, IntCounterVec, Opts, Registry, TextEncoder,
+    core::Collector,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{HealthMetric, SequenceEvent, SequenceTracker};
+
+/// Interval over which packet rates are averaged
+const RATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Prometheus metrics derived from the packets of a lidar.
+///
+/// Feed it with [`Metrics::observe`] and [`Metrics::record_codec_stats`] and expose the
+/// [`Registry`] through [`serve`].
+#[derive(Debug)]
+pub struct Metrics {
+    registry: Registry,
+    packets: IntCounterVec,
+    crc_errors: IntCounter,
+    invalid_packets: IntCounter,
+    skipped_bytes: IntCounter,
+    sequence_gaps: IntCounterVec,
+    packet_rate: GaugeVec,
+    inside_state: [Gauge; HealthMetric::ALL.len()],
+    codec_stats: CodecStats,
+    point_stream: StreamTracker,
+    imu_stream: StreamTracker,
+}
+
+impl Metrics {
+    /// Creates the metrics and registers them in a new [`Registry`].
+    ///
+    /// # Errors
+    ///
+    /// Errors if a metric cannot be registered.
+    pub fn new() -> Result<Self> {
+        let registry = Registry::new_custom(Some("l2".to_owned()), None)?;
+
+        let packets = register(
+            &registry,
+            IntCounterVec::new(
+                Opts::new("packets_total", "number of decoded packets"),
+                &["type"],
+            )?,
+        )?;
+        let crc_errors = register(
+            &registry,
+            IntCounter::new(
+                "crc_errors_total",
+                "number of frames dropped due to a CRC error",
+            )?,
+        )?;
+        let invalid_packets = register(
+            &registry,
+            IntCounter::new(
+                "invalid_packets_total",
+                "number of frames whose payload could not be decoded",
+            )?,
+        )?;
+        let skipped_bytes = register(
+            &registry,
+            IntCounter::new(
+                "resync_skipped_bytes_total",
+                "number of bytes skipped while searching for a frame header",
+            )?,
+        )?;
+        let sequence_gaps = register(
+            &registry,
+            IntCounterVec::new(
+                Opts::new(
+                    "sequence_gaps_total",
+                    "number of discontinuities in the sequence ids",
+                ),
+                &["stream"],
+            )?,
+        )?;
+        let packet_rate = register(
+            &registry,
+            GaugeVec::new(
+                Opts::new(
+                    "packet_rate_hertz",
+                    "packets per second of the lidar's clock",
+                ),
+                &["stream"],
+            )?,
+        )?;
+
+        let mut inside_state = Vec::with_capacity(HealthMetric::ALL.len());
+        for metric in HealthMetric::ALL {
+            let (name, help) = inside_state_opts(metric);
+            inside_state.push(register(&registry, Gauge::new(name, help)?)?);
+        }
+        let Ok(inside_state) = inside_state.try_into() else {
+            unreachable!("there should be a gauge for each metric");
+        };
+
+        Ok(Self {
+            registry,
+            packets,
+            crc_errors,
+            invalid_packets,
+            skipped_bytes,
+            sequence_gaps,
+            packet_rate,
+            inside_state,
+            codec_stats: CodecStats::default(),
+            point_stream: StreamTracker::default(),
+            imu_stream: StreamTracker::default(),
+        })
+    }
+
+    /// The registry containing all metrics; cloning it is cheap
+    #[must_use]
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    pub fn observe(&mut self, packet: &Packet) {
+        self.packets
+            .with_label_values(&[packet.packet_type().name()])
+            .inc();
+
+        match packet {
+            Packet::LidarPointData(data) => {
+                let stream = "point_data";
+                self.point_stream.update(
+                    data.seq(),
+                    data.stamp(),
+                    &self.sequence_gaps.with_label_values(&[stream]),
+                    &self.packet_rate.with_label_values(&[stream]),
+                );
+
+                let state = data.state();
+                for (metric, gauge) in HealthMetric::ALL.into_iter().zip(&self.inside_state) {
+                    gauge.set(metric.value(&state));
+                }
+            }
+            Packet::LidarImuData(data) => {
+                let stream = "imu";
+                self.imu_stream.update(
+                    data.seq(),
+                    data.stamp(),
+                    &self.sequence_gaps.with_label_values(&[stream]),
+                    &self.packet_rate.with_label_values(&[stream]),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    /// Updates the error counters from the statistics of the codec the packets are decoded with.
+    ///
+    /// The statistics are cumulative, so they should be passed after every packet or periodically.
+    pub fn record_codec_stats(&mut self, stats: &CodecStats) {
+        let increase = |counter: &IntCounter, previous: u64, current: u64| {
+            counter.inc_by(current.saturating_sub(previous));
+        };
+        increase(
+            &self.crc_errors,
+            self.codec_stats.crc_errors,
+            stats.crc_errors,
+        );
+        increase(
+            &self.invalid_packets,
+            self.codec_stats.invalid_packets,
+            stats.invalid_packets,
+        );
+        increase(
+            &self.skipped_bytes,
+            self.codec_stats.skipped_bytes,
+            stats.skipped_bytes,
+        );
+        self.codec_stats = stats.clone();
+    }
+}
+
+fn register<T: Collector + Clone + 'static>(registry: &Registry, collector: T) -> Result<T> {
+    registry.register(Box::new(collector.clone()))?;
+    Ok(collector)
+}
+
+fn inside_state_opts(metric: HealthMetric) -> (&'static str, &'static str) {
+    match metric {
+        HealthMetric::SysRotationPeriod => (
+            "sys_rotation_period",
+            "rotation period of the horizontal motor",
+        ),
+        HealthMetric::ComRotationPeriod => (
+            "com_rotation_period",
+            "rotation period of the vertical motor",
+        ),
+        HealthMetric::DirtyIndex => ("dirty_index", "dirtiness of the optical window"),
+        HealthMetric::PacketLostUp => ("packet_lost_up", "packet loss rate of the upper board"),
+        HealthMetric::PacketLostDown => ("packet_lost_down", "packet loss rate of the lower board"),
+        HealthMetric::ApdTemperature => ("apd_temperature_celsius", "temperature of the APD"),
+        HealthMetric::ApdVoltage => ("apd_voltage_volts", "high voltage of the APD"),
+        HealthMetric::LaserVoltage => ("laser_voltage_volts", "high voltage of the laser"),
+        HealthMetric::ImuTemperature => ("imu_temperature_celsius", "temperature of the IMU"),
+    }
+}
+
+/// Sequence ids and packet rate of a single kind of packet
+#[derive(Debug, Default)]
+struct StreamTracker {
+    sequence: SequenceTracker,
+    /// start of the current rate interval and number of packets since then
+    interval: Option<(Duration, u32)>,
+}
+
+impl StreamTracker {
+    fn update(&mut self, seq: u32, stamp: Duration, gaps: &IntCounter, rate: &Gauge) {
+        if let SequenceEvent::Gap { .. } = self.sequence.track(seq) {
+            gaps.inc();
+        }
+
+        match &mut self.interval {
+            Some((start, count)) if let Some(elapsed) = stamp.checked_sub(*start) => {
+                *count += 1;
+                if elapsed >= RATE_INTERVAL {
+                    rate.set(f64::from(*count) / elapsed.as_secs_f64());
+                    self.interval = Some((stamp, 0));
+                }
+            }
+            // first packet or the lidar's clock went backwards
+            _ => self.interval = Some((stamp, 0)),
+        }
+    }
+}
+
+/// Answers every HTTP request on `listener` with the metrics of `registry` in the Prometheus text
+/// format.
+///
+/// # Errors
+///
+/// Errors if accepting a connection fails. Failing connections are dropped without affecting
+/// others.
+pub async fn serve(listener: TcpListener, registry: Registry) -> Result<()> {
+    loop {
+        let (stream, _) = listener
+            .accept()
+            .await
+            .context("failed to accept a connection")?;
+        let registry = registry.clone();
+        tokio::spawn(async move {
+            // there's no one to report to; the client will notice the closed connection
+            let _ignored: Result<()> = respond(stream, &registry).await;
+        });
+    }
+}
+
+async fn respond(mut stream: TcpStream, registry: &Registry) -> Result<()> {
+    // the request is ignored, but has to be read as some clients wait for it to be consumed
+    let mut request = [0; 4096];
+    let _len = stream.read(&mut request).await?;
+
+    let encoder = TextEncoder::new();
+    let mut body = Vec::new();
+    encoder.encode(&registry.gather(), &mut body)?;
+
+    let header = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        encoder.format_type(),
+        body.len()
+    );
+    stream.write_all(header.as_bytes()).await?;
+    stream.write_all(&body).await?;
+    stream.shutdown().await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::point_data;
+
+    async fn scrape(addr: SocketAddr) -> Result<String> {
+        let mut stream = TcpStream::connect(addr).await?;
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await?;
+        Ok(response)
+    }
+
+    #[tokio::test]
+    async fn serves_metrics_on_localhost() {
+        let mut metrics = Metrics::new().expect("metrics should register");
+        metrics.observe(&Packet::LidarPointData(point_data(json!({
+            "state": {"apd_temperature": 42.5},
+        }))));
+
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("localhost should be bindable");
+        let addr = listener.local_addr().expect("listener should be bound");
+        let server = tokio::spawn(serve(listener, metrics.registry().clone()));
+
+        let response = scrape(addr).await.expect("metrics should be served");
+        server.abort();
+
+        assert!(
+            response.starts_with("HTTP/1.1 200 OK"),
+            "unexpected response: {response}"
+        );
+        assert!(
+            response.contains(r#"l2_packets_total{type="lidar_point_data"} 1"#),
+            "packet counter missing: {response}"
+        );
+        assert!(
+            response.contains("l2_apd_temperature_celsius 42.5"),
+            "inside state missing: {response}"
+        );
+        for metric in HealthMetric::ALL {
+            let (name, _) = inside_state_opts(metric);
+            assert!(
+                response.contains(&format!("l2_{name} ")),
+                "gauge {name} missing: {response}"
+            );
+        }
+    }
+}
@@ -0,0 +1,427 @@
+use std::{collections::VecDeque, f64::consts::TAU, time::Duration};
+
+use l2_protocol::{LidarInsideState, LidarPointData, LidarPointDataRef};
+
+use crate::health::ConditionState;
+
+/// One of the two motors of the lidar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Motor {
+    /// the low-speed motor turning the head around the vertical axis, reported as
+    /// `sys_rotation_period`
+    Horizontal,
+    /// the high-speed motor sweeping the beam vertically, reported as `com_rotation_period`
+    Vertical,
+}
+
+impl Motor {
+    pub const ALL: [Self; 2] = [Self::Horizontal, Self::Vertical];
+
+    /// The raw value reported for this motor
+    #[must_use]
+    pub fn raw(self, state: &LidarInsideState) -> u32 {
+        match self {
+            Self::Horizontal => state.sys_rotation_period(),
+            Self::Vertical => state.com_rotation_period(),
+        }
+    }
+}
+
+/// How the rotation of the motors is encoded in [`LidarInsideState`].
+///
+/// The fields are named periods while the vendor documents them as speeds, so the encoding of a
+/// firmware should be verified against the packet rate, e.g. with
+/// [`MotorCondition::PacketRateMismatch`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotationEncoding {
+    /// duration of a revolution, in multiples of `unit`
+    Period { unit: Duration },
+    /// revolutions per minute
+    Rpm,
+}
+
+impl Default for RotationEncoding {
+    fn default() -> Self {
+        Self::Period {
+            unit: Duration::from_micros(1),
+        }
+    }
+}
+
+impl RotationEncoding {
+    /// Speed in revolutions per minute; 0 for a period of 0
+    #[must_use]
+    pub fn rpm(self, raw: u32) -> f64 {
+        match self {
+            Self::Period { unit } => {
+                let period = unit.as_secs_f64() * f64::from(raw);
+                if period > 0.0 { 60.0 / period } else { 0.0 }
+            }
+            Self::Rpm => raw.into(),
+        }
+    }
+}
+
+/// A problem detected by the [`MotorMonitor`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MotorCondition {
+    /// the motor is accelerating, e.g. after the lidar has been started
+    SpinningUp(Motor),
+    /// the motor has slowed down far below the highest speed seen without recovering
+    Stalled(Motor),
+    /// the speed of the motor varies too much
+    Jitter(Motor),
+    /// the rate of point data packets doesn't match the speed of the vertical motor, e.g. because
+    /// packets are lost or the rotation is misreported
+    PacketRateMismatch,
+}
+
+impl MotorCondition {
+    const ALL: [Self; 7] = [
+        Self::SpinningUp(Motor::Horizontal),
+        Self::SpinningUp(Motor::Vertical),
+        Self::Stalled(Motor::Horizontal),
+        Self::Stalled(Motor::Vertical),
+        Self::Jitter(Motor::Horizontal),
+        Self::Jitter(Motor::Vertical),
+        Self::PacketRateMismatch,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MotorEvent {
+    /// the condition has been present for the configured number of packets
+    Raised {
+        condition: MotorCondition,
+        value: f64,
+    },
+    /// a raised condition has been absent for the configured number of packets
+    Cleared {
+        condition: MotorCondition,
+        value: f64,
+    },
+}
+
+/// Limits of a [`MotorMonitor`]
+#[derive(Debug, Clone)]
+pub struct MotorTolerances {
+    pub encoding: RotationEncoding,
+    /// relative increase of the speed per second above which a motor is spinning up
+    pub spin_up_rate: f64,
+    /// share of the highest speed seen below which a motor that isn't spinning up is stalled
+    pub stall_fraction: f64,
+    /// standard deviation of the speed relative to its mean above which a motor jitters, unless its
+    /// speed changes faster than `spin_up_rate`
+    pub max_jitter: f64,
+    /// relative difference between the observed and the expected packet rate above which they
+    /// disagree
+    pub max_rate_mismatch: f64,
+    /// number of packets the window needs to contain before speeds and rates are judged
+    pub min_samples: usize,
+    /// number of consecutive packets a condition needs to be present or absent before it is
+    /// raised or cleared
+    pub debounce: u32,
+}
+
+impl Default for MotorTolerances {
+    fn default() -> Self {
+        Self {
+            encoding: RotationEncoding::default(),
+            spin_up_rate: 0.05,
+            stall_fraction: 0.5,
+            max_jitter: 0.02,
+            max_rate_mismatch: 0.1,
+            min_samples: 10,
+            debounce: 5,
+        }
+    }
+}
+
+/// Speed of a motor within the monitor's window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MotorSpeed {
+    /// latest speed [r/min]
+    pub rpm: f64,
+    /// mean speed [r/min]
+    pub mean_rpm: f64,
+    /// standard deviation of the speed relative to its mean
+    pub jitter: f64,
+    /// relative change of the speed per second, fit over the window; `None` if all samples share a
+    /// timestamp
+    pub rate: Option<f64>,
+}
+
+impl MotorSpeed {
+    /// latest speed [Hz]
+    #[must_use]
+    pub fn hz(&self) -> f64 {
+        self.rpm / 60.0
+    }
+}
+
+/// Rates derived from the point data packets within the monitor's window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScanRates {
+    /// rate of scans as reported by the lidar's scan period [Hz]
+    pub scan_rate: f64,
+    /// rate of point data packets measured from their timestamps [Hz]
+    pub packet_rate: f64,
+    /// rate of point data packets implied by the speed of the vertical motor [Hz]
+    pub expected_packet_rate: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    stamp: Duration,
+    /// [r/min] per [`Motor`]
+    rpm: [f64; Motor::ALL.len()],
+    /// [s]
+    scan_period: f32,
+    /// number of point data packets per revolution of the vertical motor
+    packets_per_revolution: Option<f64>,
+}
+
+/// Tracks the speed of the motors and the rate of point data packets.
+///
+/// The speeds reported in every point data packet are converted with the configured
+/// [`RotationEncoding`] and evaluated over a sliding time window, like the [`HealthMonitor`]'s
+/// statistics.
+///
+/// [`HealthMonitor`]: crate::HealthMonitor
+#[derive(Debug)]
+pub struct MotorMonitor {
+    tolerances: MotorTolerances,
+    window: Duration,
+    samples: VecDeque<Sample>,
+    /// highest mean speed per [`Motor`] [r/min]
+    peak_rpm: [f64; Motor::ALL.len()],
+    conditions: [ConditionState; MotorCondition::ALL.len()],
+}
+
+impl MotorMonitor {
+    #[must_use]
+    pub fn new(tolerances: MotorTolerances, window: Duration) -> Self {
+        Self {
+            tolerances,
+            window,
+            samples: VecDeque::new(),
+            peak_rpm: [0.0; Motor::ALL.len()],
+            conditions: Default::default(),
+        }
+    }
+
+    pub fn update_point_data(&mut self, data: &LidarPointData) -> Vec<MotorEvent> {
+        self.update(
+            data.stamp(),
+            &data.state(),
+            data.scan_period(),
+            data.angle_increment(),
+            data.ranges().len(),
+        )
+    }
+
+    pub fn update_point_data_ref(&mut self, data: &LidarPointDataRef<'_>) -> Vec<MotorEvent> {
+        self.update(
+            data.stamp(),
+            &data.state(),
+            data.scan_period(),
+            data.angle_increment(),
+            data.len(),
+        )
+    }
+
+    /// Adds the line info of a point data packet and returns the conditions that have been raised
+    /// or cleared by it.
+    fn update(
+        &mut self,
+        stamp: Duration,
+        state: &LidarInsideState,
+        scan_period: f32,
+        angle_increment: f32,
+        points: usize,
+    ) -> Vec<MotorEvent> {
+        if self.samples.back().is_some_and(|last| stamp < last.stamp) {
+            // the lidar has been restarted or its clock has been set
+            self.samples.clear();
+            self.peak_rpm = [0.0; Motor::ALL.len()];
+        }
+        #[expect(clippy::cast_precision_loss, reason = "packets hold 300 points")]
+        let packet_angle = f64::from(angle_increment.abs()) * points as f64;
+        self.samples.push_back(Sample {
+            stamp,
+            rpm: Motor::ALL.map(|motor| self.tolerances.encoding.rpm(motor.raw(state))),
+            scan_period,
+            packets_per_revolution: (packet_angle > 0.0).then(|| TAU / packet_angle),
+        });
+        while self
+            .samples
+            .front()
+            .is_some_and(|first| stamp.saturating_sub(first.stamp) > self.window)
+        {
+            self.samples.pop_front();
+        }
+        if self.samples.len() < self.tolerances.min_samples.max(2) {
+            return Vec::new();
+        }
+
+        for (motor, peak) in Motor::ALL.into_iter().zip(&mut self.peak_rpm) {
+            if let Some(speed) = speed(&self.samples, motor) {
+                *peak = peak.max(speed.mean_rpm);
+            }
+        }
+
+        let mut events = Vec::new();
+        for (condition, condition_state) in
+            MotorCondition::ALL.into_iter().zip(&mut self.conditions)
+        {
+            let Some((violated, value)) =
+                evaluate(&self.samples, &self.tolerances, &self.peak_rpm, condition)
+            else {
+                continue;
+            };
+            match condition_state.update(violated, self.tolerances.debounce) {
+                Some(true) => events.push(MotorEvent::Raised { condition, value }),
+                Some(false) => events.push(MotorEvent::Cleared { condition, value }),
+                None => {}
+            }
+        }
+        events
+    }
+
+    /// Speed of the motor within the window; `None` before the first sample
+    #[must_use]
+    pub fn speed(&self, motor: Motor) -> Option<MotorSpeed> {
+        speed(&self.samples, motor)
+    }
+
+    /// Rates within the window; `None` before two samples with different timestamps
+    #[must_use]
+    pub fn rates(&self) -> Option<ScanRates> {
+        rates(&self.samples)
+    }
+
+    /// Conditions that are currently raised
+    pub fn active(&self) -> impl Iterator<Item = MotorCondition> + '_ {
+        MotorCondition::ALL
+            .into_iter()
+            .zip(&self.conditions)
+            .filter_map(|(condition, state)| state.raised.then_some(condition))
+    }
+}
+
+/// Whether the condition is present and the value it is based on; `None` if it can't be judged
+fn evaluate(
+    samples: &VecDeque<Sample>,
+    tolerances: &MotorTolerances,
+    peak_rpm: &[f64; Motor::ALL.len()],
+    condition: MotorCondition,
+) -> Option<(bool, f64)> {
+    let spinning_up = |speed: &MotorSpeed| {
+        speed
+            .rate
+            .is_some_and(|rate| rate > tolerances.spin_up_rate)
+    };
+    match condition {
+        MotorCondition::SpinningUp(motor) => {
+            let speed = speed(samples, motor)?;
+            Some((spinning_up(&speed), speed.rpm))
+        }
+        MotorCondition::Stalled(motor) => {
+            let speed = speed(samples, motor)?;
+            let peak = peak_rpm.get(motor as usize)?;
+            let stalled = !spinning_up(&speed) && speed.mean_rpm < peak * tolerances.stall_fraction;
+            Some((stalled, speed.rpm))
+        }
+        MotorCondition::Jitter(motor) => {
+            let speed = speed(samples, motor)?;
+            // motors changing their speed on purpose or stalling don't jitter
+            let steady = speed
+                .rate
+                .is_some_and(|rate| rate.abs() <= tolerances.spin_up_rate);
+            let jitter = steady && speed.jitter > tolerances.max_jitter;
+            Some((jitter, speed.jitter))
+        }
+        MotorCondition::PacketRateMismatch => {
+            let rates = rates(samples)?;
+            if rates.expected_packet_rate <= 0.0 {
+                return None;
+            }
+            let mismatch = rates.packet_rate / rates.expected_packet_rate - 1.0;
+            Some((mismatch.abs() > tolerances.max_rate_mismatch, mismatch))
+        }
+    }
+}
+
+fn speed(samples: &VecDeque<Sample>, motor: Motor) -> Option<MotorSpeed> {
+    let first = samples.front()?;
+    let last = samples.back()?;
+    let rpm = |sample: &Sample| sample.rpm.get(motor as usize).copied().unwrap_or_default();
+    let time = |sample: &Sample| sample.stamp.saturating_sub(first.stamp).as_secs_f64();
+
+    #[expect(clippy::cast_precision_loss, reason = "only an average is needed")]
+    let count = samples.len() as f64;
+    let mean_rpm = samples.iter().map(rpm).sum::<f64>() / count;
+    let mean_time = samples.iter().map(time).sum::<f64>() / count;
+    let mut variance = 0.0;
+    let mut covariance = 0.0;
+    let mut time_variance = 0.0;
+    for sample in samples {
+        let deviation = rpm(sample) - mean_rpm;
+        let time_deviation = time(sample) - mean_time;
+        variance += deviation * deviation;
+        covariance += deviation * time_deviation;
+        time_variance += time_deviation * time_deviation;
+    }
+
+    Some(MotorSpeed {
+        rpm: rpm(last),
+        mean_rpm,
+        jitter: if mean_rpm > 0.0 {
+            (variance / count).sqrt() / mean_rpm
+        } else {
+            0.0
+        },
+        // slope of a line fit to the speeds, robust against jitter
+        rate: (time_variance > 0.0 && mean_rpm > 0.0)
+            .then(|| covariance / time_variance / mean_rpm),
+    })
+}
+
+fn rates(samples: &VecDeque<Sample>) -> Option<ScanRates> {
+    let first = samples.front()?;
+    let last = samples.back()?;
+    let elapsed = last.stamp.saturating_sub(first.stamp).as_secs_f64();
+    if elapsed <= 0.0 {
+        return None;
+    }
+
+    #[expect(clippy::cast_precision_loss, reason = "only a rate is needed")]
+    let packet_rate = (samples.len() - 1) as f64 / elapsed;
+    // integrated over time like the packet rate, which lags behind a changing speed
+    let expected_packets = samples
+        .iter()
+        .zip(samples.iter().skip(1))
+        .map(|(previous, sample)| {
+            let vertical_hz = sample
+                .rpm
+                .get(Motor::Vertical as usize)
+                .copied()
+                .unwrap_or_default()
+                / 60.0;
+            let sample_rate = sample
+                .packets_per_revolution
+                .map_or(0.0, |packets| packets * vertical_hz);
+            sample_rate * sample.stamp.saturating_sub(previous.stamp).as_secs_f64()
+        })
+        .sum::<f64>();
+    let scan_period = f64::from(last.scan_period);
+    Some(ScanRates {
+        scan_rate: if scan_period > 0.0 {
+            scan_period.recip()
+        } else {
+            0.0
+        },
+        packet_rate,
+        expected_packet_rate: expected_packets / elapsed,
+    })
+}
@@ -0,0 +1,473 @@
+use std::{collections::BTreeMap, mem};
+
+use l2_protocol::{LidarInsideState, Packet};
+
+/// Number of sequence ids below the highest one which are remembered to tell duplicates from late
+/// packets
+const HISTORY_LEN: u32 = u64::BITS;
+
+/// Jumps ahead by more than this many ids are taken as a restart rather than as lost packets; at
+/// the few hundred packets per second the lidar sends, that's minutes of silence
+const MAX_GAP: u32 = 1 << 16;
+
+/// A kind of packet carrying its own sequence ids
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PacketStream {
+    PointData,
+    Imu,
+}
+
+/// How a sequence id relates to the ones received before
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceEvent {
+    /// the first packet or the expected successor of the previous one
+    InOrder,
+    /// packets between the expected and the received one are missing (so far)
+    Gap { expected: u32, received: u32 },
+    /// a packet that has been received before
+    Duplicate { seq: u32 },
+    /// a packet that arrived after a packet with a higher id; it is no longer counted as lost
+    Reordered { seq: u32 },
+    /// the id jumped back too far to be a late packet or ahead too far to be a gap, e.g. because
+    /// the lidar has been restarted
+    Restart { seq: u32 },
+}
+
+/// Counters of a [`SequenceTracker`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SequenceStats {
+    /// number of distinct packets received
+    pub received: u64,
+    /// number of packets which have been skipped and haven't arrived late
+    pub lost: u64,
+    pub duplicates: u64,
+    pub reordered: u64,
+    /// number of times the sequence id wrapped around from `u32::MAX` to `0`
+    pub wraps: u64,
+    pub restarts: u64,
+}
+
+impl SequenceStats {
+    /// Share of packets lost, between `0.0` and `1.0`; `None` before any packet has been expected
+    #[must_use]
+    #[expect(
+        clippy::cast_precision_loss,
+        reason = "the ratio doesn't need to be exact"
+    )]
+    pub fn loss_ratio(&self) -> Option<f64> {
+        let expected = self.received + self.lost;
+        (expected > 0).then(|| self.lost as f64 / expected as f64)
+    }
+}
+
+/// Checks that the sequence ids of a single stream are consecutive.
+#[derive(Debug, Default, Clone)]
+pub struct SequenceTracker {
+    /// highest id received so far
+    highest: Option<u32>,
+    /// bit `n` is set if `highest - n - 1` has been received
+    history: u64,
+    stats: SequenceStats,
+}
+
+impl SequenceTracker {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn track(&mut self, seq: u32) -> SequenceEvent {
+        let Some(highest) = self.highest else {
+            self.highest = Some(seq);
+            self.stats.received += 1;
+            return SequenceEvent::InOrder;
+        };
+
+        let ahead = seq.wrapping_sub(highest);
+        if ahead == 0 {
+            self.stats.duplicates += 1;
+            return SequenceEvent::Duplicate { seq };
+        }
+
+        if ahead <= MAX_GAP {
+            if seq < highest {
+                self.stats.wraps += 1;
+            }
+            self.highest = Some(seq);
+            self.stats.received += 1;
+            self.stats.lost += u64::from(ahead - 1);
+            // the previous highest id becomes bit `ahead - 1`
+            self.history = self.history.checked_shl(ahead).unwrap_or_default()
+                | 1_u64.checked_shl(ahead - 1).unwrap_or_default();
+
+            let expected = highest.wrapping_add(1);
+            return if ahead == 1 {
+                SequenceEvent::InOrder
+            } else {
+                SequenceEvent::Gap {
+                    expected,
+                    received: seq,
+                }
+            };
+        }
+
+        let behind = highest.wrapping_sub(seq);
+        if ahead <= u32::MAX / 2 || behind > HISTORY_LEN {
+            *self = Self {
+                highest: Some(seq),
+                history: 0,
+                stats: SequenceStats {
+                    received: self.stats.received + 1,
+                    restarts: self.stats.restarts + 1,
+                    ..self.stats
+                },
+            };
+            return SequenceEvent::Restart { seq };
+        }
+
+        let bit = 1 << (behind - 1);
+        if self.history & bit != 0 {
+            self.stats.duplicates += 1;
+            return SequenceEvent::Duplicate { seq };
+        }
+        self.history |= bit;
+        self.stats.received += 1;
+        self.stats.reordered += 1;
+        self.stats.lost = self.stats.lost.saturating_sub(1);
+        SequenceEvent::Reordered { seq }
+    }
+
+    #[must_use]
+    pub fn stats(&self) -> &SequenceStats {
+        &self.stats
+    }
+}
+
+/// Packet loss as observed by the receiver and as reported by the lidar
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PacketLoss {
+    pub point_data: SequenceStats,
+    pub imu: SequenceStats,
+    /// lidar's own packet loss rate of the upper board; `None` before the first point data
+    pub lidar_lost_up: Option<f32>,
+    /// lidar's own packet loss rate of the lower board; `None` before the first point data
+    pub lidar_lost_down: Option<f32>,
+}
+
+/// Tracks the sequence ids of all packet streams.
+#[derive(Debug, Default, Clone)]
+pub struct StreamMonitor {
+    point_data: SequenceTracker,
+    imu: SequenceTracker,
+    state: Option<LidarInsideState>,
+}
+
+impl StreamMonitor {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tracks the sequence id of the packet; `None` for packets without one
+    pub fn observe(&mut self, packet: &Packet) -> Option<(PacketStream, SequenceEvent)> {
+        match packet {
+            Packet::LidarPointData(data) => {
+                self.state = Some(data.state());
+                Some((PacketStream::PointData, self.point_data.track(data.seq())))
+            }
+            Packet::LidarImuData(data) => Some((PacketStream::Imu, self.imu.track(data.seq()))),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn tracker(&self, stream: PacketStream) -> &SequenceTracker {
+        match stream {
+            PacketStream::PointData => &self.point_data,
+            PacketStream::Imu => &self.imu,
+        }
+    }
+
+    #[must_use]
+    pub fn loss(&self) -> PacketLoss {
+        PacketLoss {
+            point_data: *self.point_data.stats(),
+            imu: *self.imu.stats(),
+            lidar_lost_up: self.state.as_ref().map(LidarInsideState::packet_lost_up),
+            lidar_lost_down: self.state.as_ref().map(LidarInsideState::packet_lost_down),
+        }
+    }
+}
+
+/// Restores the order of packets which have been shuffled in transit, e.g. over UDP.
+///
+/// Up to `window` packets are held back while waiting for a missing one. Packets arriving after
+/// their successors have been released are dropped, unless the id jumped back further than a
+/// [`SequenceTracker`] or the window reaches, e.g. because the lidar has been restarted: then the
+/// packets held back are released and the order starts over.
+#[derive(Debug, Clone)]
+pub struct SequenceReorderer<T> {
+    window: usize,
+    /// sequence id of the next packet to release, extended to 64 bits to survive wrap-arounds
+    next: Option<u64>,
+    pending: BTreeMap<u64, T>,
+}
+
+impl<T> SequenceReorderer<T> {
+    #[must_use]
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            next: None,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a packet and returns the packets which can be released in order.
+    pub fn push(&mut self, seq: u32, item: T) -> Vec<T> {
+        let next = *self.next.get_or_insert(seq.into());
+
+        #[expect(
+            clippy::cast_possible_truncation,
+            reason = "only the distance between the ids is of interest"
+        )]
+        let next_seq = next as u32;
+        let ahead = seq.wrapping_sub(next_seq);
+        let behind = next_seq.wrapping_sub(seq);
+        let mut released = Vec::new();
+        let key = if ahead <= u32::MAX / 2 {
+            next + u64::from(ahead)
+        } else if behind > self.restart_distance() {
+            // the lidar has been restarted
+            released = self.flush();
+            self.next = Some(seq.into());
+            seq.into()
+        } else {
+            // late or duplicate
+            return released;
+        };
+        self.pending.entry(key).or_insert(item);
+
+        self.release(&mut released);
+        while self.pending.len() > self.window {
+            // give up waiting for the missing packet
+            let Some((skipped_to, first)) = self.pending.pop_first() else {
+                unreachable!("pending packets should exceed the window");
+            };
+            released.push(first);
+            self.next = Some(skipped_to + 1);
+            self.release(&mut released);
+        }
+        released
+    }
+
+    /// Distance an id may jump back before it is taken as a restart rather than a late packet
+    fn restart_distance(&self) -> u32 {
+        u32::try_from(self.window)
+            .unwrap_or(u32::MAX)
+            .max(HISTORY_LEN)
+    }
+
+    /// Releases all packets held back, skipping the missing ones.
+    pub fn flush(&mut self) -> Vec<T> {
+        if let Some(&last) = self.pending.keys().next_back() {
+            self.next = Some(last + 1);
+        }
+        mem::take(&mut self.pending).into_values().collect()
+    }
+
+    fn release(&mut self, released: &mut Vec<T>) {
+        while let Some(next) = self.next
+            && let Some(item) = self.pending.remove(&next)
+        {
+            released.push(item);
+            self.next = Some(next + 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(seqs: &[u32]) -> (Vec<SequenceEvent>, SequenceStats) {
+        let mut tracker = SequenceTracker::new();
+        let events = seqs.iter().map(|&seq| tracker.track(seq)).collect();
+        (events, *tracker.stats())
+    }
+
+    #[test]
+    fn tracker_counts_gaps_as_lost() {
+        let (events, stats) = track(&[1, 2, 5]);
+        assert_eq!(
+            events,
+            [
+                SequenceEvent::InOrder,
+                SequenceEvent::InOrder,
+                SequenceEvent::Gap {
+                    expected: 3,
+                    received: 5,
+                },
+            ],
+            "5 should skip 3 and 4"
+        );
+        assert_eq!(
+            (stats.received, stats.lost),
+            (3, 2),
+            "the skipped ids should be lost"
+        );
+    }
+
+    #[test]
+    fn tracker_detects_duplicates() {
+        let (events, stats) = track(&[1, 2, 2, 3, 1]);
+        assert_eq!(
+            events,
+            [
+                SequenceEvent::InOrder,
+                SequenceEvent::InOrder,
+                SequenceEvent::Duplicate { seq: 2 },
+                SequenceEvent::InOrder,
+                SequenceEvent::Duplicate { seq: 1 },
+            ],
+            "repeated ids should be duplicates, whether latest or older"
+        );
+        assert_eq!(
+            (stats.received, stats.duplicates, stats.lost),
+            (3, 2, 0),
+            "duplicates shouldn't count as received"
+        );
+    }
+
+    #[test]
+    fn tracker_recovers_reordered_packets() {
+        let (events, stats) = track(&[1, 3, 4, 2]);
+        assert_eq!(
+            events.last(),
+            Some(&SequenceEvent::Reordered { seq: 2 }),
+            "2 should arrive late"
+        );
+        assert_eq!(
+            (stats.received, stats.reordered, stats.lost),
+            (4, 1, 0),
+            "the late packet should no longer be lost"
+        );
+    }
+
+    #[test]
+    fn tracker_follows_wrap_arounds() {
+        let (events, stats) = track(&[u32::MAX - 1, u32::MAX, 0, 1]);
+        assert!(
+            events.iter().all(|event| *event == SequenceEvent::InOrder),
+            "the ids should be consecutive across the wrap-around: {events:?}"
+        );
+        assert_eq!(
+            (stats.wraps, stats.lost, stats.restarts),
+            (1, 0, 0),
+            "the wrap-around should be counted"
+        );
+    }
+
+    #[test]
+    fn tracker_detects_restarts() {
+        let (events, stats) = track(&[1000, 1001, 3, 4]);
+        assert_eq!(
+            events,
+            [
+                SequenceEvent::InOrder,
+                SequenceEvent::InOrder,
+                SequenceEvent::Restart { seq: 3 },
+                SequenceEvent::InOrder,
+            ],
+            "jumping back further than the history should restart the sequence"
+        );
+        assert_eq!(
+            (stats.received, stats.restarts, stats.lost),
+            (4, 1, 0),
+            "a restart shouldn't lose packets"
+        );
+    }
+
+    #[test]
+    fn tracker_takes_huge_jumps_ahead_as_restarts() {
+        let (events, stats) = track(&[1, 2, 2_000_000_000, 2_000_000_001]);
+        assert_eq!(
+            events.get(2),
+            Some(&SequenceEvent::Restart { seq: 2_000_000_000 }),
+            "a jump far beyond any outage should restart the sequence"
+        );
+        assert_eq!(
+            (stats.restarts, stats.lost),
+            (1, 0),
+            "the jump shouldn't count as loss"
+        );
+    }
+
+    fn reorder(window: usize, seqs: &[u32]) -> (Vec<u32>, SequenceReorderer<u32>) {
+        let mut reorderer = SequenceReorderer::new(window);
+        let released = seqs
+            .iter()
+            .flat_map(|&seq| reorderer.push(seq, seq))
+            .collect();
+        (released, reorderer)
+    }
+
+    #[test]
+    fn reorderer_restores_the_order_within_the_window() {
+        let (released, _) = reorder(4, &[1, 3, 4, 2, 5]);
+        assert_eq!(
+            released,
+            [1, 2, 3, 4, 5],
+            "2 should be waited for and released in order"
+        );
+    }
+
+    #[test]
+    fn reorderer_skips_gaps_beyond_the_window() {
+        let (released, mut reorderer) = reorder(2, &[1, 3, 4, 5, 6]);
+        assert_eq!(
+            released,
+            [1, 3, 4, 5, 6],
+            "2 shouldn't be waited for once more than 2 packets are held back"
+        );
+        assert!(
+            reorderer.push(2, 2).is_empty(),
+            "2 should be dropped once its successors have been released"
+        );
+    }
+
+    #[test]
+    fn reorderer_drops_duplicates() {
+        let (released, _) = reorder(4, &[1, 2, 2, 4, 4, 3]);
+        assert_eq!(
+            released,
+            [1, 2, 3, 4],
+            "every packet should be released once"
+        );
+    }
+
+    #[test]
+    fn reorderer_follows_wrap_arounds() {
+        let (released, _) = reorder(4, &[u32::MAX - 1, 0, u32::MAX, 1]);
+        assert_eq!(
+            released,
+            [u32::MAX - 1, u32::MAX, 0, 1],
+            "the order should continue across the wrap-around"
+        );
+    }
+
+    #[test]
+    fn reorderer_starts_over_after_a_restart() {
+        let (released, mut reorderer) = reorder(4, &[1000, 1002, 3, 4]);
+        assert_eq!(
+            released,
+            [1000, 1002, 3, 4],
+            "the packets held back should be released before those after the restart"
+        );
+        assert_eq!(
+            reorderer.push(5, 5),
+            [5],
+            "the stream should continue after the restart"
+        );
+    }
+}
@@ -0,0 +1,69 @@
+//! Packets for the tests, built from their serialized form so they don't depend on the wire format
+
+use l2_protocol::{LidarInsideState, LidarPointData};
+use serde_json::{Value, json};
+
+/// Overwrites the fields of `base` with those of `values`, descending into objects.
+fn merge(base: &mut Value, values: Value) {
+    match (base, values) {
+        (Value::Object(base), Value::Object(values)) => {
+            for (key, value) in values {
+                merge(base.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (base, value) => *base = value,
+    }
+}
+
+fn zero_inside_state() -> Value {
+    json!({
+        "sys_rotation_period": 0,
+        "com_rotation_period": 0,
+        "dirty_index": 0.0,
+        "packet_lost_up": 0.0,
+        "packet_lost_down": 0.0,
+        "apd_temperature": 0.0,
+        "apd_voltage": 0.0,
+        "laser_voltage": 0.0,
+        "imu_temperature": 0.0,
+    })
+}
+
+/// An inside state with all values zero except the given ones, e.g.
+/// `json!({"apd_temperature": 42.5})`
+pub(crate) fn inside_state(values: Value) -> LidarInsideState {
+    let mut state = zero_inside_state();
+    merge(&mut state, values);
+    serde_json::from_value(state).expect("inside state should be valid")
+}
+
+/// A point data packet with all values zero except the given ones, e.g.
+/// `json!({"info": {"seq": 1}, "ranges": [1000], "intensities": [200]})`
+pub(crate) fn point_data(values: Value) -> Box<LidarPointData> {
+    let mut data = json!({
+        "info": {"seq": 0, "payload_size": 0, "stamp": {"sec": 0, "nsec": 0}},
+        "state": zero_inside_state(),
+        "param": {
+            "a_axis_dist": 0.0,
+            "b_axis_dist": 0.0,
+            "theta_angle_bias": 0.0,
+            "alpha_angle_bias": 0.0,
+            "beta_angle": 0.0,
+            "xi_angle": 0.0,
+            "range_bias": 0.0,
+            "range_scale": 0.0,
+        },
+        "com_horizontal_angle_start": 0.0,
+        "com_horizontal_angle_step": 0.0,
+        "scan_period": 0.0,
+        "range_min": 0.0,
+        "range_max": 0.0,
+        "angle_min": 0.0,
+        "angle_increment": 0.0,
+        "time_increment": 0.0,
+        "ranges": [],
+        "intensities": [],
+    });
+    merge(&mut data, values);
+    Box::new(serde_json::from_value(data).expect("point data should be valid"))
+}
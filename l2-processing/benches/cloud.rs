@@ -0,0 +1,84 @@
+#![allow(unused_crate_dependencies, reason = "used in library")]
+
+//! Point cloud reduction on inputs the size of an assembled scan.
+
+use std::hint::black_box;
+
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use l2_processing::{Point, RadiusOutlierFilter, StatisticalOutlierFilter, VoxelGrid, VoxelMode};
+use nalgebra::Point3;
+
+/// About one full scan of the lidar
+const POINT_COUNT: u32 = 64_000;
+
+/// Builds a room-like cloud of walls and floor with some scattered outliers
+fn scan() -> Vec<Point> {
+    // deterministic linear congruential generator
+    let mut state = 0x2545_f491_u32;
+    let mut random = move || {
+        state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        f32::from(u16::try_from(state >> 16).unwrap_or_default()) / f32::from(u16::MAX)
+    };
+
+    (0..POINT_COUNT)
+        .map(|index| {
+            let (first, second) = (random() * 8.0 - 4.0, random() * 3.0);
+            let position = match index % 50 {
+                0 => Point3::new(first, random() * 8.0 - 4.0, second),
+                1..=15 => Point3::new(first, 4.0, second),
+                16..=30 => Point3::new(4.0, first, second),
+                _ => Point3::new(first, second * 2.0 - 3.0, 0.0),
+            };
+            Point {
+                position,
+                range: position.coords.norm(),
+                returned: true,
+                intensity: u8::try_from(index % 256).unwrap_or_default(),
+                time: 0.0,
+            }
+        })
+        .collect()
+}
+
+fn cloud(criterion: &mut Criterion) {
+    let points = scan();
+
+    let mut group = criterion.benchmark_group("cloud");
+    for (name, mode) in [
+        ("centroid", VoxelMode::Centroid),
+        ("first", VoxelMode::First),
+    ] {
+        let grid = VoxelGrid::new(0.05).with_mode(mode);
+        group.bench_function(format!("voxel/{name}"), |bencher| {
+            bencher.iter(|| grid.downsample(black_box(&points)));
+        });
+    }
+
+    let radius = RadiusOutlierFilter {
+        radius: 0.1,
+        min_neighbors: 3,
+    };
+    group.bench_function("outlier/radius", |bencher| {
+        bencher.iter_batched_ref(
+            || points.clone(),
+            |points| radius.apply(points),
+            BatchSize::LargeInput,
+        );
+    });
+
+    let statistical = StatisticalOutlierFilter {
+        neighbors: 8,
+        std_ratio: 1.0,
+    };
+    group.bench_function("outlier/statistical", |bencher| {
+        bencher.iter_batched_ref(
+            || points.clone(),
+            |points| statistical.apply(points),
+            BatchSize::LargeInput,
+        );
+    });
+    group.finish();
+}
+
+criterion_group!(benches, cloud);
+criterion_main!(benches);
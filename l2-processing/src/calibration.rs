@@ -0,0 +1,515 @@
+use l2_protocol::{LidarCalibParam, LidarPointData};
+use nalgebra::{DMatrix, DVector, Point3, Vector3};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Plane, RansacPlaneFit};
+
+/// Intrinsic calibration of the lidar, describing how ranges and angles map to positions.
+///
+/// Every point data packet carries the calibration stored on the lidar, see [`LidarCalibParam`].
+/// A user supplied one can replace it with [`PointConverter::with_calibration`], e.g. one refined
+/// by a [`CalibrationEstimator`].
+///
+/// [`PointConverter::with_calibration`]: crate::PointConverter::with_calibration
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calibration {
+    /// vertical offset of the measurement center [m]
+    pub a_axis_dist: f32,
+    /// horizontal offset of the measurement center from the rotation axis [m]
+    pub b_axis_dist: f32,
+    /// offset of the horizontal angle [rad]
+    pub theta_angle_bias: f32,
+    /// offset of the vertical angle [rad]
+    pub alpha_angle_bias: f32,
+    /// [rad]
+    pub beta_angle: f32,
+    /// [rad]
+    pub xi_angle: f32,
+    /// added to the raw ranges [mm]
+    pub range_bias: f32,
+    /// factor applied to the biased ranges
+    pub range_scale: f32,
+}
+
+impl Default for Calibration {
+    /// An ideal lidar without offsets
+    fn default() -> Self {
+        Self {
+            a_axis_dist: 0.0,
+            b_axis_dist: 0.0,
+            theta_angle_bias: 0.0,
+            alpha_angle_bias: 0.0,
+            beta_angle: 0.0,
+            xi_angle: 0.0,
+            range_bias: 0.0,
+            range_scale: 1.0,
+        }
+    }
+}
+
+impl From<LidarCalibParam> for Calibration {
+    fn from(value: LidarCalibParam) -> Self {
+        Self {
+            a_axis_dist: value.a_axis_dist(),
+            b_axis_dist: value.b_axis_dist(),
+            theta_angle_bias: value.theta_angle_bias(),
+            alpha_angle_bias: value.alpha_angle_bias(),
+            beta_angle: value.beta_angle(),
+            xi_angle: value.xi_angle(),
+            range_bias: value.range_bias(),
+            range_scale: value.range_scale(),
+        }
+    }
+}
+
+impl Calibration {
+    #[must_use]
+    pub fn get(&self, parameter: CalibrationParameter) -> f32 {
+        match parameter {
+            CalibrationParameter::AAxisDist => self.a_axis_dist,
+            CalibrationParameter::BAxisDist => self.b_axis_dist,
+            CalibrationParameter::ThetaAngleBias => self.theta_angle_bias,
+            CalibrationParameter::AlphaAngleBias => self.alpha_angle_bias,
+            CalibrationParameter::BetaAngle => self.beta_angle,
+            CalibrationParameter::XiAngle => self.xi_angle,
+            CalibrationParameter::RangeBias => self.range_bias,
+            CalibrationParameter::RangeScale => self.range_scale,
+        }
+    }
+
+    pub fn set(&mut self, parameter: CalibrationParameter, value: f32) {
+        let field = match parameter {
+            CalibrationParameter::AAxisDist => &mut self.a_axis_dist,
+            CalibrationParameter::BAxisDist => &mut self.b_axis_dist,
+            CalibrationParameter::ThetaAngleBias => &mut self.theta_angle_bias,
+            CalibrationParameter::AlphaAngleBias => &mut self.alpha_angle_bias,
+            CalibrationParameter::BetaAngle => &mut self.beta_angle,
+            CalibrationParameter::XiAngle => &mut self.xi_angle,
+            CalibrationParameter::RangeBias => &mut self.range_bias,
+            CalibrationParameter::RangeScale => &mut self.range_scale,
+        };
+        *field = value;
+    }
+
+    /// Differences of every parameter to a reference, e.g. the calibration stored on the lidar,
+    /// in the order of [`CalibrationParameter::ALL`]
+    #[must_use]
+    pub fn compare(&self, reference: &Self) -> [(CalibrationParameter, f32); 8] {
+        CalibrationParameter::ALL
+            .map(|parameter| (parameter, self.get(parameter) - reference.get(parameter)))
+    }
+}
+
+/// A parameter of a [`Calibration`]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationParameter {
+    AAxisDist,
+    BAxisDist,
+    ThetaAngleBias,
+    AlphaAngleBias,
+    BetaAngle,
+    XiAngle,
+    RangeBias,
+    RangeScale,
+}
+
+impl CalibrationParameter {
+    pub const ALL: [Self; 8] = [
+        Self::AAxisDist,
+        Self::BAxisDist,
+        Self::ThetaAngleBias,
+        Self::AlphaAngleBias,
+        Self::BetaAngle,
+        Self::XiAngle,
+        Self::RangeBias,
+        Self::RangeScale,
+    ];
+
+    /// Change used to estimate derivatives numerically, small compared to typical errors
+    fn step(self) -> f64 {
+        match self {
+            // [m] and [rad]
+            Self::AAxisDist
+            | Self::BAxisDist
+            | Self::ThetaAngleBias
+            | Self::AlphaAngleBias
+            | Self::BetaAngle
+            | Self::XiAngle => 1e-3,
+            // [mm]
+            Self::RangeBias => 1.0,
+            Self::RangeScale => 1e-4,
+        }
+    }
+}
+
+/// Maps raw measurements to positions in the lidar frame with a fixed calibration
+#[derive(Debug, Clone, Copy)]
+pub struct BeamModel {
+    calibration: Calibration,
+    sin_beta: f32,
+    cos_beta: f32,
+    sin_xi: f32,
+    cos_xi: f32,
+}
+
+impl BeamModel {
+    #[must_use]
+    pub fn new(calibration: Calibration) -> Self {
+        let (sin_beta, cos_beta) = calibration.beta_angle.sin_cos();
+        let (sin_xi, cos_xi) = calibration.xi_angle.sin_cos();
+        Self {
+            calibration,
+            sin_beta,
+            cos_beta,
+            sin_xi,
+            cos_xi,
+        }
+    }
+
+    /// Range of a raw measurement [mm] in [m]
+    #[must_use]
+    pub fn range(&self, raw: u16) -> f32 {
+        self.calibration.range_scale * (f32::from(raw) + self.calibration.range_bias) * 0.001
+    }
+
+    /// Position of the end of a beam of `length` [m] at the raw vertical angle `alpha` and
+    /// horizontal angle `theta` [rad], i.e. without their biases
+    #[must_use]
+    pub fn position(&self, alpha: f32, theta: f32, length: f32) -> Point3<f32> {
+        let (radial, tangential, vertical) = self.in_plane(alpha);
+        let (sin_theta, cos_theta) = (theta + self.calibration.theta_angle_bias).sin_cos();
+
+        let radial = radial * length + self.calibration.b_axis_dist;
+        let tangential = tangential * length;
+        let vertical = vertical * length;
+
+        Point3::new(
+            cos_theta * radial - sin_theta * tangential,
+            sin_theta * radial + cos_theta * tangential,
+            vertical + self.calibration.a_axis_dist,
+        )
+    }
+
+    /// Unit vector along a beam at the raw vertical angle `alpha` and horizontal angle `theta`
+    /// [rad], ignoring the offsets of the axes
+    #[must_use]
+    pub fn direction(&self, alpha: f32, theta: f32) -> Vector3<f32> {
+        let (radial, tangential, vertical) = self.in_plane(alpha);
+        let (sin_theta, cos_theta) = (theta + self.calibration.theta_angle_bias).sin_cos();
+
+        Vector3::new(
+            cos_theta * radial - sin_theta * tangential,
+            sin_theta * radial + cos_theta * tangential,
+            vertical,
+        )
+    }
+
+    /// Radial, tangential and vertical component of the unit vector along a beam within the
+    /// plane of the vertical rotation
+    fn in_plane(&self, alpha: f32) -> (f32, f32, f32) {
+        let (sin_alpha, cos_alpha) = (alpha + self.calibration.alpha_angle_bias).sin_cos();
+        (
+            -self.cos_beta * self.sin_xi + self.sin_beta * self.cos_xi * sin_alpha,
+            cos_alpha * self.cos_xi,
+            self.sin_beta * self.sin_xi + self.cos_beta * self.cos_xi * sin_alpha,
+        )
+    }
+}
+
+/// A raw measurement of a point data packet
+#[derive(Debug, Clone, Copy)]
+struct RawBeam {
+    /// vertical angle without bias [rad]
+    alpha: f32,
+    /// horizontal angle without bias [rad]
+    theta: f32,
+    /// [mm]
+    range: u16,
+}
+
+/// Refines a [`Calibration`] from a recording of a room with planar walls, floor and ceiling.
+///
+/// Planes are extracted from the points computed with the initial calibration; the selected
+/// parameters are then adjusted to minimize the distances of the points to planes fit to them,
+/// using Levenberg-Marquardt. Both steps are repeated with the refined calibration until it stops
+/// improving. The lidar must not move during the recording.
+///
+/// A room made of planes stays one under a rotation around the `z` axis, a shift along it and a
+/// scaling, so [`ThetaAngleBias`](CalibrationParameter::ThetaAngleBias),
+/// [`AAxisDist`](CalibrationParameter::AAxisDist) and
+/// [`RangeScale`](CalibrationParameter::RangeScale) can't be estimated this way.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalibrationEstimator {
+    /// parameters to estimate; the others keep their initial values
+    pub parameters: Vec<CalibrationParameter>,
+    /// extracts the planes, one after the other
+    pub ransac: RansacPlaneFit,
+    /// maximum number of planes extracted
+    pub max_planes: usize,
+    /// planes with fewer points are discarded
+    pub min_plane_points: usize,
+    /// the returns are thinned out evenly to at most this many
+    pub max_samples: usize,
+    /// maximum number of times the planes are extracted again with the refined calibration
+    pub rounds: usize,
+    /// maximum number of iterations per round
+    pub max_iterations: usize,
+}
+
+impl Default for CalibrationEstimator {
+    fn default() -> Self {
+        Self {
+            parameters: vec![
+                CalibrationParameter::BAxisDist,
+                CalibrationParameter::AlphaAngleBias,
+                CalibrationParameter::BetaAngle,
+                CalibrationParameter::XiAngle,
+                CalibrationParameter::RangeBias,
+            ],
+            ransac: RansacPlaneFit {
+                iterations: 500,
+                ..RansacPlaneFit::default()
+            },
+            max_planes: 6,
+            min_plane_points: 500,
+            max_samples: 30_000,
+            rounds: 5,
+            max_iterations: 30,
+        }
+    }
+}
+
+/// Result of a [`CalibrationEstimator`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CalibrationEstimate {
+    pub calibration: Calibration,
+    /// number of planes the points have been assigned to
+    pub planes: usize,
+    /// number of points on the planes
+    pub samples: usize,
+    /// root mean square distance of the points to their planes with the initial calibration, in
+    /// the first round [m]
+    pub initial_rmse: f32,
+    /// root mean square distance of the points to their planes with the estimated calibration [m]
+    pub rmse: f32,
+}
+
+impl CalibrationEstimator {
+    /// Estimates the calibration from the returns of `packets`, starting at `initial`.
+    ///
+    /// `None` if no plane has been found or the planes don't constrain the parameters.
+    #[must_use]
+    pub fn estimate(
+        &self,
+        packets: &[LidarPointData],
+        initial: &Calibration,
+    ) -> Option<CalibrationEstimate> {
+        let beams = self.sample(packets, initial);
+        let mut estimate: Option<CalibrationEstimate> = None;
+        for _ in 0..self.rounds.max(1) {
+            let start = estimate.map_or(*initial, |estimate| estimate.calibration);
+            let planes = self.extract_planes(&beams, &start);
+            if planes.is_empty() {
+                break;
+            }
+            let (calibration, start_cost, cost) = self.optimize(&planes, &start)?;
+
+            let samples = planes.iter().map(Vec::len).sum::<usize>();
+            #[expect(
+                clippy::cast_possible_truncation,
+                clippy::cast_precision_loss,
+                reason = "errors are small"
+            )]
+            let rmse = |squared_sum: f64| (squared_sum / samples as f64).sqrt() as f32;
+            let converged = start_cost - cost <= start_cost * 0.01;
+            estimate = Some(CalibrationEstimate {
+                calibration,
+                planes: planes.len(),
+                samples,
+                initial_rmse: estimate.map_or(rmse(start_cost), |estimate| estimate.initial_rmse),
+                rmse: rmse(cost),
+            });
+            if converged {
+                break;
+            }
+        }
+        estimate
+    }
+
+    /// Minimizes the distances of the beams to their planes with Levenberg-Marquardt, using
+    /// forward differences; returns the calibration and the initial and final sum of squared
+    /// distances
+    fn optimize(
+        &self,
+        planes: &[Vec<RawBeam>],
+        start: &Calibration,
+    ) -> Option<(Calibration, f64, f64)> {
+        let mut values = DVector::from_iterator(
+            self.parameters.len(),
+            self.parameters
+                .iter()
+                .map(|&parameter| f64::from(start.get(parameter))),
+        );
+        let mut residuals = self.residuals(planes, start, &values)?;
+        let start_cost = residuals.norm_squared();
+        let mut cost = start_cost;
+
+        let mut damping = 1e-3;
+        for _ in 0..self.max_iterations {
+            let mut jacobian = DMatrix::zeros(residuals.len(), values.len());
+            for (column, parameter) in self.parameters.iter().enumerate() {
+                let mut shifted = values.clone();
+                let step = parameter.step();
+                if let Some(value) = shifted.get_mut(column) {
+                    *value += step;
+                }
+                let shifted_residuals = self.residuals(planes, start, &shifted)?;
+                jacobian.set_column(column, &((shifted_residuals - &residuals) / step));
+            }
+            let hessian = jacobian.transpose() * &jacobian;
+            let gradient = jacobian.transpose() * &residuals;
+
+            let mut improved = false;
+            while damping < 1e9 {
+                let mut damped = hessian.clone();
+                for index in 0..values.len() {
+                    if let Some(diagonal) = damped.get_mut((index, index)) {
+                        *diagonal *= 1.0 + damping;
+                    }
+                }
+                let Some(cholesky) = damped.cholesky() else {
+                    damping *= 10.0;
+                    continue;
+                };
+                let candidate = &values - cholesky.solve(&gradient);
+                if let Some(candidate_residuals) = self.residuals(planes, start, &candidate)
+                    && candidate_residuals.norm_squared() < cost
+                {
+                    let previous_cost = cost;
+                    values = candidate;
+                    residuals = candidate_residuals;
+                    cost = residuals.norm_squared();
+                    damping /= 10.0;
+                    improved = (previous_cost - cost) > previous_cost * 1e-9;
+                    break;
+                }
+                damping *= 10.0;
+            }
+            if !improved {
+                break;
+            }
+        }
+        Some((self.calibration(start, &values), start_cost, cost))
+    }
+
+    /// The returns of all packets, thinned out to at most [`max_samples`](Self::max_samples)
+    fn sample(&self, packets: &[LidarPointData], initial: &Calibration) -> Vec<RawBeam> {
+        let model = BeamModel::new(*initial);
+        let mut beams = Vec::new();
+        for data in packets {
+            let range_limits = data.range_min()..=data.range_max();
+            let mut alpha = data.angle_min();
+            let mut theta = data.com_horizontal_angle_start();
+            for &range in data.ranges() {
+                if range_limits.contains(&model.range(range)) {
+                    beams.push(RawBeam {
+                        alpha,
+                        theta,
+                        range,
+                    });
+                }
+                alpha += data.angle_increment();
+                theta += data.com_horizontal_angle_step();
+            }
+        }
+
+        let stride = beams.len().div_ceil(self.max_samples.max(1)).max(1);
+        beams.into_iter().step_by(stride).collect()
+    }
+
+    /// Groups the beams by the plane they hit, largest plane first; beams off all planes or close
+    /// to several, e.g. in corners, are dropped
+    fn extract_planes(&self, beams: &[RawBeam], initial: &Calibration) -> Vec<Vec<RawBeam>> {
+        let model = BeamModel::new(*initial);
+        let positions = beams
+            .iter()
+            .map(|beam| model.position(beam.alpha, beam.theta, model.range(beam.range)))
+            .collect::<Vec<_>>();
+        let on_plane = |plane: &Plane, position: &Point3<f32>| {
+            plane.distance(position).abs() <= self.ransac.threshold
+        };
+
+        let mut planes = Vec::new();
+        let mut remaining = positions.clone();
+        while planes.len() < self.max_planes {
+            let Some(plane) = self.ransac.fit(&remaining, None) else {
+                break;
+            };
+            let count = remaining.len();
+            remaining.retain(|position| !on_plane(&plane, position));
+            if count - remaining.len() < self.min_plane_points {
+                break;
+            }
+            planes.push(plane);
+        }
+
+        let mut groups = vec![Vec::new(); planes.len()];
+        for (beam, position) in beams.iter().zip(&positions) {
+            let mut hit = planes
+                .iter()
+                .enumerate()
+                .filter(|(_, plane)| on_plane(plane, position));
+            if let (Some((index, _)), None) = (hit.next(), hit.next())
+                && let Some(group) = groups.get_mut(index)
+            {
+                group.push(*beam);
+            }
+        }
+        groups.retain(|group| group.len() >= self.min_plane_points);
+        groups
+    }
+
+    fn calibration(&self, initial: &Calibration, values: &DVector<f64>) -> Calibration {
+        let mut calibration = *initial;
+        for (&parameter, &value) in self.parameters.iter().zip(values.iter()) {
+            #[expect(clippy::cast_possible_truncation, reason = "values are small")]
+            calibration.set(parameter, value as f32);
+        }
+        calibration
+    }
+
+    /// Distances of the beams to planes fit to them, computed with the given parameter values
+    fn residuals(
+        &self,
+        planes: &[Vec<RawBeam>],
+        initial: &Calibration,
+        values: &DVector<f64>,
+    ) -> Option<DVector<f64>> {
+        let model = BeamModel::new(self.calibration(initial, values));
+        let mut residuals = Vec::new();
+        let mut positions = Vec::new();
+        for beams in planes {
+            positions.clear();
+            positions.extend(
+                beams
+                    .iter()
+                    .map(|beam| model.position(beam.alpha, beam.theta, model.range(beam.range))),
+            );
+            let plane = Plane::fit(&positions)?;
+            residuals.extend(
+                positions
+                    .iter()
+                    .map(|position| f64::from(plane.distance(position))),
+            );
+        }
+        Some(DVector::from_vec(residuals))
+    }
+}
@@ -0,0 +1,317 @@
+use nalgebra::{Matrix3, Point3, Rotation3, SymmetricEigen, UnitQuaternion, Vector3};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Point, grid::PointGrid};
+
+/// Groups points closer than a tolerance to each other into clusters.
+///
+/// Typically applied to the points labelled [`GroundLabel::NonGround`] to find obstacles.
+///
+/// [`GroundLabel::NonGround`]: crate::GroundLabel::NonGround
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EuclideanClustering {
+    /// maximum distance between neighboring points of a cluster [m]
+    pub tolerance: f32,
+    /// smaller clusters are discarded
+    pub min_points: usize,
+    /// larger clusters are discarded
+    pub max_points: usize,
+}
+
+impl Default for EuclideanClustering {
+    fn default() -> Self {
+        Self {
+            tolerance: 0.2,
+            min_points: 5,
+            max_points: usize::MAX,
+        }
+    }
+}
+
+impl EuclideanClustering {
+    /// Clusters in the order of their first point; points without a return or with non-finite
+    /// coordinates are ignored.
+    #[must_use]
+    pub fn cluster(&self, points: &[Point]) -> Vec<Cluster> {
+        let candidates = points
+            .iter()
+            .enumerate()
+            .filter(|(_, point)| {
+                point.returned && point.position.iter().all(|coord| coord.is_finite())
+            })
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        let positions = candidates
+            .iter()
+            .filter_map(|&index| points.get(index))
+            .map(|point| point.position)
+            .collect::<Vec<_>>();
+        let grid = PointGrid::new(&positions, self.tolerance);
+
+        let mut visited = vec![false; positions.len()];
+        let mut clusters = Vec::new();
+        let mut members = Vec::new();
+        for start in 0..positions.len() {
+            if visited.get(start).is_none_or(|&visited| visited) {
+                continue;
+            }
+            if let Some(visited) = visited.get_mut(start) {
+                *visited = true;
+            }
+
+            // breadth-first flood fill, `members` doubles as the queue
+            members.clear();
+            members.push(start);
+            let mut next = 0;
+            while let Some(&current) = members.get(next) {
+                next += 1;
+                for neighbor in grid.within(current, self.tolerance) {
+                    if let Some(visited) = visited.get_mut(neighbor)
+                        && !*visited
+                    {
+                        *visited = true;
+                        members.push(neighbor);
+                    }
+                }
+            }
+
+            if (self.min_points..=self.max_points).contains(&members.len()) {
+                let member_positions = members
+                    .iter()
+                    .filter_map(|&member| positions.get(member))
+                    .copied()
+                    .collect::<Vec<_>>();
+                let indices = members
+                    .iter()
+                    .filter_map(|&member| candidates.get(member))
+                    .copied()
+                    .collect();
+                clusters.push(Cluster::new(indices, &member_positions));
+            }
+        }
+        clusters
+    }
+}
+
+/// A group of points, e.g. an obstacle
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cluster {
+    /// indices of the points in the clustered slice
+    pub indices: Vec<usize>,
+    pub centroid: Point3<f32>,
+    pub aligned_box: AlignedBox,
+    pub oriented_box: OrientedBox,
+}
+
+impl Cluster {
+    fn new(indices: Vec<usize>, positions: &[Point3<f32>]) -> Self {
+        #[expect(clippy::cast_precision_loss, reason = "only an average is needed")]
+        let count = positions.len() as f32;
+        let centroid = Point3::from(
+            positions
+                .iter()
+                .fold(Vector3::zeros(), |sum, position| sum + position.coords)
+                / count,
+        );
+        Self {
+            indices,
+            centroid,
+            aligned_box: AlignedBox::enclosing(positions),
+            oriented_box: OrientedBox::enclosing(positions, &centroid),
+        }
+    }
+
+    #[must_use]
+    pub fn point_count(&self) -> usize {
+        self.indices.len()
+    }
+}
+
+/// Box with edges parallel to the axes of the frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlignedBox {
+    pub min: Point3<f32>,
+    pub max: Point3<f32>,
+}
+
+impl AlignedBox {
+    fn enclosing(positions: &[Point3<f32>]) -> Self {
+        positions.iter().fold(
+            Self {
+                min: Point3::from([f32::INFINITY; 3]),
+                max: Point3::from([f32::NEG_INFINITY; 3]),
+            },
+            |bounds, position| Self {
+                min: bounds.min.inf(position),
+                max: bounds.max.sup(position),
+            },
+        )
+    }
+
+    #[must_use]
+    pub fn center(&self) -> Point3<f32> {
+        nalgebra::center(&self.min, &self.max)
+    }
+
+    /// Edge lengths [m]
+    #[must_use]
+    pub fn size(&self) -> Vector3<f32> {
+        self.max - self.min
+    }
+}
+
+/// Box aligned with the principal axes of the points it encloses
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrientedBox {
+    pub center: Point3<f32>,
+    /// rotation from the box's axes into the frame; the box's `x` axis is the direction of the
+    /// largest spread
+    pub orientation: UnitQuaternion<f32>,
+    /// half the edge lengths along the box's axes [m]
+    pub half_extents: Vector3<f32>,
+}
+
+impl OrientedBox {
+    fn enclosing(positions: &[Point3<f32>], centroid: &Point3<f32>) -> Self {
+        let covariance = positions.iter().fold(Matrix3::zeros(), |sum, position| {
+            let deviation = position - centroid;
+            sum + deviation * deviation.transpose()
+        });
+        let eigen = SymmetricEigen::new(covariance);
+        let mut axes = eigen
+            .eigenvalues
+            .iter()
+            .copied()
+            .zip(eigen.eigenvectors.column_iter())
+            .collect::<Vec<_>>();
+        axes.sort_by(|first, second| second.0.total_cmp(&first.0));
+        let [(_, first), (_, second), _] = axes.as_slice() else {
+            unreachable!("a 3x3 matrix should have three eigenvalues");
+        };
+        let (first, second) = (first.into_owned(), second.into_owned());
+        let rotation = Rotation3::from_matrix_unchecked(Matrix3::from_columns(&[
+            first,
+            second,
+            first.cross(&second),
+        ]));
+        let orientation = UnitQuaternion::from_rotation_matrix(&rotation);
+
+        // bounds of the points in the box's axes, relative to the centroid
+        let (min, max) = positions.iter().fold(
+            (
+                Vector3::repeat(f32::INFINITY),
+                Vector3::repeat(f32::NEG_INFINITY),
+            ),
+            |(min, max), position| {
+                let local = orientation.inverse_transform_vector(&(position - centroid));
+                (min.inf(&local), max.sup(&local))
+            },
+        );
+        Self {
+            center: centroid + orientation.transform_vector(&((min + max) / 2.0)),
+            orientation,
+            half_extents: (max - min) / 2.0,
+        }
+    }
+}
+
+/// Assigns IDs to clusters which stay the same between consecutive scans.
+///
+/// Clusters are associated with the nearest track by centroid; unmatched clusters start new
+/// tracks.
+#[derive(Debug, Clone)]
+pub struct ClusterTracker {
+    /// maximum distance a centroid may move between scans [m]
+    max_distance: f32,
+    /// number of scans a track survives without a matching cluster
+    max_missed: u32,
+    tracks: Vec<Track>,
+    next_id: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Track {
+    id: u64,
+    centroid: Point3<f32>,
+    missed: u32,
+}
+
+impl ClusterTracker {
+    #[must_use]
+    pub fn new(max_distance: f32) -> Self {
+        Self {
+            max_distance,
+            max_missed: 2,
+            tracks: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn with_max_missed(self, max_missed: u32) -> Self {
+        Self { max_missed, ..self }
+    }
+
+    /// Returns the ID of every cluster of a new scan, in the same order.
+    pub fn update(&mut self, clusters: &[Cluster]) -> Vec<u64> {
+        // closest pairs first
+        let max_squared = self.max_distance * self.max_distance;
+        let mut pairs = clusters
+            .iter()
+            .enumerate()
+            .flat_map(|(cluster, Cluster { centroid, .. })| {
+                self.tracks
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(track, known)| {
+                        let distance = (centroid - known.centroid).norm_squared();
+                        (distance <= max_squared).then_some((distance, cluster, track))
+                    })
+            })
+            .collect::<Vec<_>>();
+        pairs.sort_by(|first, second| first.0.total_cmp(&second.0));
+
+        let mut assigned = vec![None; clusters.len()];
+        let mut matched = vec![None; self.tracks.len()];
+        for (_, cluster, track) in pairs {
+            if let (Some(assigned @ None), Some(matched @ None)) =
+                (assigned.get_mut(cluster), matched.get_mut(track))
+            {
+                *assigned = Some(track);
+                *matched = Some(cluster);
+            }
+        }
+
+        for (track, matched_cluster) in self.tracks.iter_mut().zip(matched) {
+            match matched_cluster.and_then(|cluster| clusters.get(cluster)) {
+                Some(cluster) => {
+                    track.centroid = cluster.centroid;
+                    track.missed = 0;
+                }
+                None => track.missed += 1,
+            }
+        }
+
+        let mut ids = Vec::with_capacity(clusters.len());
+        for (cluster, track) in clusters.iter().zip(assigned) {
+            if let Some(track) = track.and_then(|track| self.tracks.get(track)) {
+                ids.push(track.id);
+            } else {
+                let id = self.next_id;
+                self.next_id += 1;
+                self.tracks.push(Track {
+                    id,
+                    centroid: cluster.centroid,
+                    missed: 0,
+                });
+                ids.push(id);
+            }
+        }
+
+        let max_missed = self.max_missed;
+        self.tracks.retain(|track| track.missed <= max_missed);
+        ids
+    }
+}
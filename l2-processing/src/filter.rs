@@ -0,0 +1,111 @@
+use std::f32::consts::TAU;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Frames, NoReturnPolicy, Point, PointConverter};
+
+/// A criterion deciding which points to keep.
+///
+/// Range and intensity filters only apply to valid returns; masks apply to all points, except
+/// [`NoReturnPolicy::Nan`] markers which have no position to mask.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(tag = "type", rename_all = "snake_case")
+)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum PointFilter {
+    /// keeps returns within the given distance from the lidar [m]
+    Range {
+        #[cfg_attr(feature = "serde", serde(default))]
+        min: Option<f32>,
+        #[cfg_attr(feature = "serde", serde(default))]
+        max: Option<f32>,
+    },
+    /// keeps returns within the given intensity
+    Intensity {
+        #[cfg_attr(feature = "serde", serde(default))]
+        min: Option<u8>,
+        #[cfg_attr(feature = "serde", serde(default))]
+        max: Option<u8>,
+    },
+    /// removes points whose azimuth around the `z` axis lies counter-clockwise from `start` to
+    /// `end` [rad], e.g. where the robot's body obstructs the view
+    AngularCrop { start: f32, end: f32 },
+    /// removes points within an axis-aligned box, e.g. the robot's body [m]
+    BodyBox { min: [f32; 3], max: [f32; 3] },
+}
+
+impl PointFilter {
+    #[must_use]
+    pub fn keeps(&self, point: &Point) -> bool {
+        match self {
+            Self::Range { min, max } => {
+                !point.returned
+                    || (min.is_none_or(|min| point.range >= min)
+                        && max.is_none_or(|max| point.range <= max))
+            }
+            Self::Intensity { min, max } => {
+                !point.returned
+                    || (min.is_none_or(|min| point.intensity >= min)
+                        && max.is_none_or(|max| point.intensity <= max))
+            }
+            Self::AngularCrop { start, end } => {
+                let azimuth = point.position.y.atan2(point.position.x);
+                (!point.returned && azimuth.is_nan())
+                    || (azimuth - start).rem_euclid(TAU) > (end - start).rem_euclid(TAU)
+            }
+            Self::BodyBox { min, max } => !point
+                .position
+                .iter()
+                .zip(min.iter().zip(max))
+                .all(|(coord, (min, max))| (min..=max).contains(&coord)),
+        }
+    }
+}
+
+/// A sequence of filters applied to converted points.
+///
+/// With the `serde` feature it can be loaded from a file, e.g. as JSON:
+///
+/// ```json
+/// {
+///   "no_return": "drop",
+///   "filters": [
+///     { "type": "range", "min": 0.3 },
+///     { "type": "angular_crop", "start": 2.8, "end": 3.5 },
+///     { "type": "body_box", "min": [-0.5, -0.3, -1.0], "max": [0.1, 0.3, 0.2] }
+///   ]
+/// }
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FilterPipeline {
+    pub no_return: NoReturnPolicy,
+    pub filters: Vec<PointFilter>,
+}
+
+impl FilterPipeline {
+    #[must_use]
+    pub fn with(mut self, filter: PointFilter) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    /// A converter producing points in the base frame, with the pipeline's [`NoReturnPolicy`]
+    #[must_use]
+    pub fn point_converter(&self, frames: &Frames) -> PointConverter {
+        frames.point_converter().with_no_return(self.no_return)
+    }
+
+    #[must_use]
+    pub fn keeps(&self, point: &Point) -> bool {
+        self.filters.iter().all(|filter| filter.keeps(point))
+    }
+
+    /// Removes all points which don't pass every filter.
+    pub fn apply(&self, points: &mut Vec<Point>) {
+        points.retain(|point| self.keeps(point));
+    }
+}
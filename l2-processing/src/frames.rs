@@ -0,0 +1,92 @@
+use nalgebra::{Isometry3, Translation3, UnitQuaternion};
+
+use crate::{ImuSample, PointConverter};
+
+/// Factory transform from the IMU frame into the lidar frame, as published by Unitree
+#[must_use]
+pub fn factory_imu_to_lidar() -> Isometry3<f32> {
+    Isometry3::from_parts(
+        Translation3::new(-0.007_698, -0.014_655, 0.006_67),
+        UnitQuaternion::identity(),
+    )
+}
+
+/// Transforms between the frames of the lidar and the robot it is mounted on.
+///
+/// - **lidar frame**: right-handed, origin on the axis of the horizontal (slow) motor. `z` points
+///   along that axis away from the mounting base; a beam at horizontal and vertical angle zero
+///   points along `y`. Points are computed in this frame.
+/// - **IMU frame**: axes parallel to the lidar frame, origin at the IMU chip. Orientation, angular
+///   velocity and acceleration are reported in this frame.
+/// - **base frame**: frame of the robot the lidar is mounted on, defined by the user through
+///   [`Frames::new`].
+///
+/// All transforms are named `a_to_b`, meaning they map coordinates in frame `a` into frame `b`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frames {
+    imu_to_lidar: Isometry3<f32>,
+    lidar_to_base: Isometry3<f32>,
+}
+
+impl Default for Frames {
+    /// Factory extrinsic; the base frame coincides with the lidar frame.
+    fn default() -> Self {
+        Self::new(Isometry3::identity())
+    }
+}
+
+impl Frames {
+    /// `lidar_to_base` describes how the lidar is mounted, i.e. its pose in the base frame.
+    #[must_use]
+    pub fn new(lidar_to_base: Isometry3<f32>) -> Self {
+        Self {
+            imu_to_lidar: factory_imu_to_lidar(),
+            lidar_to_base,
+        }
+    }
+
+    /// Replaces the factory extrinsic, e.g. with a calibrated one.
+    #[must_use]
+    pub fn with_imu_to_lidar(self, imu_to_lidar: Isometry3<f32>) -> Self {
+        Self {
+            imu_to_lidar,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn imu_to_lidar(&self) -> Isometry3<f32> {
+        self.imu_to_lidar
+    }
+
+    #[must_use]
+    pub fn lidar_to_base(&self) -> Isometry3<f32> {
+        self.lidar_to_base
+    }
+
+    #[must_use]
+    pub fn imu_to_base(&self) -> Isometry3<f32> {
+        self.lidar_to_base * self.imu_to_lidar
+    }
+
+    /// A converter producing points in the base frame
+    #[must_use]
+    pub fn point_converter(&self) -> PointConverter {
+        PointConverter::new(self.lidar_to_base)
+    }
+
+    /// Expresses an IMU sample in the base frame.
+    ///
+    /// The orientation becomes the orientation of the base. The acceleration is rotated only; the
+    /// centripetal and tangential terms caused by the lever arm are neglected.
+    #[must_use]
+    pub fn imu_sample_to_base(&self, sample: &ImuSample) -> ImuSample {
+        let rotation = self.imu_to_base().rotation;
+        ImuSample {
+            stamp: sample.stamp,
+            orientation: sample.orientation * rotation.inverse(),
+            angular_velocity: rotation * sample.angular_velocity,
+            linear_acceleration: rotation * sample.linear_acceleration,
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use nalgebra::Point3;
+
+/// Index of a cube of a regular grid
+pub(crate) type CellKey = [i32; 3];
+
+#[expect(
+    clippy::cast_possible_truncation,
+    reason = "coordinates beyond ±2^31 cells don't occur in practice and saturate"
+)]
+pub(crate) fn cell_key(position: &Point3<f32>, cell_size: f32) -> CellKey {
+    position
+        .coords
+        .map(|coord| (coord / cell_size).floor() as i32)
+        .into()
+}
+
+/// Spatial hash of point indices for neighbor queries
+#[derive(Debug)]
+pub(crate) struct PointGrid<'points> {
+    positions: &'points [Point3<f32>],
+    cell_size: f32,
+    cells: HashMap<CellKey, Vec<usize>>,
+}
+
+impl<'points> PointGrid<'points> {
+    pub(crate) fn new(positions: &'points [Point3<f32>], cell_size: f32) -> Self {
+        let mut cells = HashMap::<CellKey, Vec<usize>>::new();
+        for (index, position) in positions.iter().enumerate() {
+            cells
+                .entry(cell_key(position, cell_size))
+                .or_default()
+                .push(index);
+        }
+
+        Self {
+            positions,
+            cell_size,
+            cells,
+        }
+    }
+
+    /// Indices of the points within `radius` of the point at `index`, not including `index`
+    pub(crate) fn within(&self, index: usize, radius: f32) -> impl Iterator<Item = usize> {
+        let center = self.positions.get(index).copied();
+        let reach = (radius / self.cell_size).ceil();
+        #[expect(
+            clippy::cast_possible_truncation,
+            reason = "the reach is small and positive"
+        )]
+        let reach = reach as i32;
+        let radius_squared = radius * radius;
+
+        center.into_iter().flat_map(move |center| {
+            cells_within(cell_key(&center, self.cell_size), reach)
+                .filter_map(|key| self.cells.get(&key))
+                .flatten()
+                .copied()
+                .filter(move |&other| {
+                    other != index
+                        && self
+                            .positions
+                            .get(other)
+                            .is_some_and(|other| (other - center).norm_squared() <= radius_squared)
+                })
+        })
+    }
+
+    /// Number of points within `radius` of the point at `index`, not counting the point itself,
+    /// stopping early once `limit` is reached
+    pub(crate) fn count_within(&self, index: usize, radius: f32, limit: usize) -> usize {
+        self.within(index, radius).take(limit).count()
+    }
+}
+
+/// All cells within `reach` cells of `center` along every axis
+fn cells_within(center: CellKey, reach: i32) -> impl Iterator<Item = CellKey> {
+    let [x, y, z] = center;
+    (x.saturating_sub(reach)..=x.saturating_add(reach)).flat_map(move |cell_x| {
+        (y.saturating_sub(reach)..=y.saturating_add(reach)).flat_map(move |cell_y| {
+            (z.saturating_sub(reach)..=z.saturating_add(reach))
+                .map(move |cell_z| [cell_x, cell_y, cell_z])
+        })
+    })
+}
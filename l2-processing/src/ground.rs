@@ -0,0 +1,336 @@
+use std::f32::consts::TAU;
+
+use nalgebra::{Matrix3, Point3, SymmetricEigen, UnitVector3, Vector3};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::Point;
+
+/// The plane of all positions `p` with `normal · p = offset`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    pub normal: UnitVector3<f32>,
+    /// signed distance of the plane from the origin along the normal [m]
+    pub offset: f32,
+}
+
+impl Plane {
+    /// The plane through three positions; `None` if they are collinear
+    #[must_use]
+    pub fn through(first: &Point3<f32>, second: &Point3<f32>, third: &Point3<f32>) -> Option<Self> {
+        let normal = UnitVector3::try_new((second - first).cross(&(third - first)), f32::EPSILON)?;
+        Some(Self {
+            normal,
+            offset: normal.dot(&first.coords),
+        })
+    }
+
+    /// Least squares fit; `None` if the positions don't span a plane.
+    ///
+    /// The side the normal points to is arbitrary, see [`facing`](Self::facing).
+    #[must_use]
+    pub fn fit(positions: &[Point3<f32>]) -> Option<Self> {
+        if positions.len() < 3 {
+            return None;
+        }
+        #[expect(clippy::cast_precision_loss, reason = "only an average is needed")]
+        let count = positions.len() as f32;
+        let centroid = positions
+            .iter()
+            .fold(Vector3::zeros(), |sum, position| sum + position.coords)
+            / count;
+        let covariance = positions.iter().fold(Matrix3::zeros(), |sum, position| {
+            let deviation = position.coords - centroid;
+            sum + deviation * deviation.transpose()
+        });
+
+        // the normal is the direction of the least variance
+        let eigen = SymmetricEigen::new(covariance);
+        let mut axes = eigen
+            .eigenvalues
+            .iter()
+            .copied()
+            .zip(eigen.eigenvectors.column_iter())
+            .collect::<Vec<_>>();
+        axes.sort_by(|first, second| first.0.total_cmp(&second.0));
+        let [(_, normal), (middle, _), (largest, _)] = axes.as_slice() else {
+            unreachable!("a 3x3 matrix should have three eigenvalues");
+        };
+        if *middle <= largest * f32::EPSILON {
+            return None;
+        }
+
+        let normal = UnitVector3::try_new(normal.into_owned(), f32::EPSILON)?;
+        Some(Self {
+            normal,
+            offset: normal.dot(&centroid),
+        })
+    }
+
+    /// Signed distance of a position from the plane, positive on the side the normal points to
+    #[must_use]
+    pub fn distance(&self, position: &Point3<f32>) -> f32 {
+        self.normal.dot(&position.coords) - self.offset
+    }
+
+    /// The same plane with the normal pointing to the same side as `up`
+    #[must_use]
+    pub fn facing(self, up: &UnitVector3<f32>) -> Self {
+        if self.normal.dot(up) < 0.0 {
+            Self {
+                normal: -self.normal,
+                offset: -self.offset,
+            }
+        } else {
+            self
+        }
+    }
+
+    /// Angle between the normal and `up`, regardless of the normal's sign [rad]
+    #[must_use]
+    pub fn tilt(&self, up: &UnitVector3<f32>) -> f32 {
+        self.normal.dot(up).abs().min(1.0).acos()
+    }
+}
+
+/// Robust plane fit by random sampling, ignoring points off the plane.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RansacPlaneFit {
+    /// number of random candidate planes
+    pub iterations: usize,
+    /// maximum distance of inliers from the plane [m]
+    pub threshold: f32,
+    /// maximum angle between the plane's normal and the prior normal, if one is given [rad]
+    pub max_tilt: f32,
+    /// seed of the random sampling, making fits reproducible
+    pub seed: u64,
+}
+
+impl Default for RansacPlaneFit {
+    fn default() -> Self {
+        Self {
+            iterations: 100,
+            threshold: 0.05,
+            max_tilt: 0.35,
+            seed: 0x5eed,
+        }
+    }
+}
+
+impl RansacPlaneFit {
+    /// The plane with the most inliers, refined by a least squares fit to them.
+    ///
+    /// With a `prior` only planes within [`max_tilt`](Self::max_tilt) of it are considered and the
+    /// normal of the result points to the same side. `None` if no plane has been found.
+    #[must_use]
+    pub fn fit(
+        &self,
+        positions: &[Point3<f32>],
+        prior: Option<&UnitVector3<f32>>,
+    ) -> Option<Plane> {
+        let acceptable =
+            |plane: &Plane| prior.is_none_or(|prior| plane.tilt(prior) <= self.max_tilt);
+
+        let mut random = SplitMix64(self.seed);
+        let mut best = None;
+        let mut best_inliers = 0;
+        for _ in 0..self.iterations {
+            let mut sample = || positions.get(random.below(positions.len()));
+            let (Some(first), Some(second), Some(third)) = (sample(), sample(), sample()) else {
+                return None;
+            };
+            let Some(plane) = Plane::through(first, second, third).filter(acceptable) else {
+                continue;
+            };
+            let inliers = positions
+                .iter()
+                .filter(|position| plane.distance(position).abs() <= self.threshold)
+                .count();
+            if inliers > best_inliers {
+                best = Some(plane);
+                best_inliers = inliers;
+            }
+        }
+
+        let best = best?;
+        let inliers = positions
+            .iter()
+            .filter(|position| best.distance(position).abs() <= self.threshold)
+            .copied()
+            .collect::<Vec<_>>();
+        let plane = Plane::fit(&inliers).filter(acceptable).unwrap_or(best);
+        Some(match prior {
+            Some(prior) => plane.facing(prior),
+            None => plane,
+        })
+    }
+}
+
+/// Classification of a point by [`GroundSegmentation`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroundLabel {
+    Ground,
+    NonGround,
+}
+
+/// Separates terrain from obstacles by fitting local planes.
+///
+/// The scan is divided into rings and sectors around the `up` axis. In every bin a plane is fit to
+/// its lowest points; bins with too few points, points beyond the last ring or bins whose plane
+/// is too steep fall back to a plane fit to the whole scan. Points within
+/// [`threshold`](Self::threshold) of their plane are ground.
+///
+/// `up` is typically the gravity direction measured by the IMU, see [`ImuSample::up`], expressed
+/// in the frame of the points, e.g. with [`Frames::imu_sample_to_base`].
+///
+/// [`ImuSample::up`]: crate::ImuSample::up
+/// [`Frames::imu_sample_to_base`]: crate::Frames::imu_sample_to_base
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroundSegmentation {
+    /// outer radii of the rings around the `up` axis, ascending [m]
+    pub ring_edges: Vec<f32>,
+    /// number of sectors per ring
+    pub sectors: usize,
+    /// maximum distance of ground points from their plane [m]
+    pub threshold: f32,
+    /// points within this height above the lowest point of a bin are used to fit its plane [m]
+    pub seed_height: f32,
+    /// minimum number of points in a bin to fit its own plane
+    pub min_points: usize,
+    /// fit of the planes; its `max_tilt` is the steepest slope considered ground
+    pub ransac: RansacPlaneFit,
+}
+
+impl Default for GroundSegmentation {
+    fn default() -> Self {
+        Self {
+            ring_edges: vec![2.0, 4.0, 7.0, 11.0, 16.0, 22.0, 30.0],
+            sectors: 16,
+            threshold: 0.08,
+            seed_height: 0.2,
+            min_points: 10,
+            ransac: RansacPlaneFit {
+                iterations: 30,
+                ..RansacPlaneFit::default()
+            },
+        }
+    }
+}
+
+impl GroundSegmentation {
+    /// Labels every point; points without a return or with non-finite coordinates are
+    /// [`GroundLabel::NonGround`].
+    #[must_use]
+    pub fn segment(&self, points: &[Point], up: &UnitVector3<f32>) -> Vec<GroundLabel> {
+        let candidates = points
+            .iter()
+            .enumerate()
+            .filter(|(_, point)| {
+                point.returned && point.position.iter().all(|coord| coord.is_finite())
+            })
+            .map(|(index, point)| (index, point.position))
+            .collect::<Vec<_>>();
+        let positions = candidates
+            .iter()
+            .map(|(_, position)| *position)
+            .collect::<Vec<_>>();
+        let global = self.ransac.fit(&positions, Some(up));
+
+        let (first_axis, second_axis) = horizontal_axes(up);
+        let sectors = self.sectors.max(1);
+        let mut bins = vec![Vec::new(); self.ring_edges.len() * sectors];
+        let mut outside = Vec::new();
+        for &(index, position) in &candidates {
+            let (first, second) = (
+                first_axis.dot(&position.coords),
+                second_axis.dot(&position.coords),
+            );
+            let ring = self
+                .ring_edges
+                .partition_point(|&edge| edge <= first.hypot(second));
+            let azimuth = second.atan2(first).rem_euclid(TAU) / TAU;
+            #[expect(
+                clippy::cast_possible_truncation,
+                clippy::cast_sign_loss,
+                clippy::cast_precision_loss,
+                reason = "the azimuth is within [0, 1] and sector counts are small"
+            )]
+            let sector = ((azimuth * sectors as f32) as usize).min(sectors - 1);
+            match bins.get_mut(ring * sectors + sector) {
+                Some(bin) => bin.push((index, position)),
+                None => outside.push((index, position)),
+            }
+        }
+
+        let mut labels = vec![GroundLabel::NonGround; points.len()];
+        let mut label = |bin: &[(usize, Point3<f32>)], plane: Option<Plane>| {
+            let Some(plane) = plane else {
+                return;
+            };
+            for (index, position) in bin {
+                if plane.distance(position).abs() <= self.threshold
+                    && let Some(label) = labels.get_mut(*index)
+                {
+                    *label = GroundLabel::Ground;
+                }
+            }
+        };
+        for bin in &bins {
+            label(bin, self.local_plane(bin, up).or(global));
+        }
+        label(&outside, global);
+        labels
+    }
+
+    /// Plane fit to the lowest points of a bin
+    fn local_plane(&self, bin: &[(usize, Point3<f32>)], up: &UnitVector3<f32>) -> Option<Plane> {
+        if bin.len() < self.min_points.max(3) {
+            return None;
+        }
+        let lowest = bin
+            .iter()
+            .map(|(_, position)| up.dot(&position.coords))
+            .fold(f32::INFINITY, f32::min);
+        let seeds = bin
+            .iter()
+            .map(|(_, position)| *position)
+            .filter(|position| up.dot(&position.coords) <= lowest + self.seed_height)
+            .collect::<Vec<_>>();
+        if seeds.len() < 3 {
+            return None;
+        }
+        self.ransac.fit(&seeds, Some(up))
+    }
+}
+
+/// Two unit vectors completing `up` to an orthonormal basis
+fn horizontal_axes(up: &UnitVector3<f32>) -> (Vector3<f32>, Vector3<f32>) {
+    let first = up
+        .cross(&Vector3::x())
+        .try_normalize(0.1)
+        .or_else(|| up.cross(&Vector3::y()).try_normalize(0.1))
+        .unwrap_or_else(Vector3::y);
+    (first, up.cross(&first))
+}
+
+/// Small deterministic generator for sampling
+#[derive(Debug)]
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut value = self.0;
+        value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        value ^ (value >> 31)
+    }
+
+    /// A value in `0..bound`, or 0 if `bound` is 0
+    fn below(&mut self, bound: usize) -> usize {
+        let bound = u64::try_from(bound).unwrap_or(u64::MAX);
+        usize::try_from(self.next().checked_rem(bound).unwrap_or_default()).unwrap_or_default()
+    }
+}
@@ -0,0 +1,167 @@
+use nalgebra::{Isometry3, Matrix6, Point3, Translation3, UnitQuaternion, Vector3, Vector6};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Plane, kdtree::KdTree};
+
+/// Point-to-plane ICP: aligns a source cloud with a target cloud by minimizing the distances of
+/// the source points to the local planes of their nearest target points.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointToPlaneIcp {
+    pub max_iterations: usize,
+    /// source points farther from their nearest target point are ignored [m]
+    pub max_correspondence_distance: f32,
+    /// number of target points the normal of each target point is estimated from
+    pub normal_neighbors: usize,
+    /// registration fails with fewer correspondences
+    pub min_correspondences: usize,
+    /// iteration stops once an update moves less than this [m]
+    pub translation_tolerance: f32,
+    /// and rotates less than this [rad]
+    pub rotation_tolerance: f32,
+}
+
+impl Default for PointToPlaneIcp {
+    fn default() -> Self {
+        Self {
+            max_iterations: 30,
+            max_correspondence_distance: 1.0,
+            normal_neighbors: 10,
+            min_correspondences: 20,
+            translation_tolerance: 1e-4,
+            rotation_tolerance: 1e-4,
+        }
+    }
+}
+
+/// Result of a [`PointToPlaneIcp`] registration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Registration {
+    /// moves the source cloud onto the target cloud
+    pub source_to_target: Isometry3<f32>,
+    /// covariance of `source_to_target`, as a small rotation [rad] followed by a small translation
+    /// [m] applied after it, i.e. expressed in the target frame
+    pub covariance: Matrix6<f32>,
+    /// number of source points matched in the last iteration
+    pub correspondences: usize,
+    /// root mean square of the point-to-plane distances in the last iteration [m]
+    pub rmse: f32,
+    pub iterations: usize,
+    /// whether the tolerances have been reached before the maximum number of iterations
+    pub converged: bool,
+}
+
+impl PointToPlaneIcp {
+    /// Estimates the transform from `source` to `target`, starting at `initial`.
+    ///
+    /// `None` if there are too few correspondences or the geometry doesn't constrain all
+    /// degrees of freedom, e.g. a single plane.
+    #[must_use]
+    pub fn register(
+        &self,
+        source: &[Point3<f32>],
+        target: &[Point3<f32>],
+        initial: &Isometry3<f32>,
+    ) -> Option<Registration> {
+        let tree = KdTree::new(target);
+        let normals = target
+            .iter()
+            .map(|position| {
+                let neighbors = tree
+                    .nearest(position, self.normal_neighbors)
+                    .into_iter()
+                    .filter_map(|(index, _)| target.get(index))
+                    .copied()
+                    .collect::<Vec<_>>();
+                Plane::fit(&neighbors).map(|plane| plane.normal)
+            })
+            .collect::<Vec<_>>();
+        let max_squared = self.max_correspondence_distance * self.max_correspondence_distance;
+
+        let mut registration = Registration {
+            source_to_target: *initial,
+            covariance: Matrix6::zeros(),
+            correspondences: 0,
+            rmse: 0.0,
+            iterations: 0,
+            converged: false,
+        };
+        while registration.iterations < self.max_iterations && !registration.converged {
+            registration.iterations += 1;
+
+            // Gauss-Newton on a small rotation and translation applied after the transform
+            let mut hessian = Matrix6::<f64>::zeros();
+            let mut gradient = Vector6::<f64>::zeros();
+            let mut squared_sum = 0.0;
+            let mut correspondences = 0_usize;
+            for position in source {
+                let moved = registration.source_to_target * position;
+                let Some(&(index, distance)) = tree.nearest(&moved, 1).first() else {
+                    continue;
+                };
+                let (Some(nearest), Some(Some(normal))) = (target.get(index), normals.get(index))
+                else {
+                    continue;
+                };
+                if distance > max_squared {
+                    continue;
+                }
+
+                let residual = f64::from(normal.dot(&(moved - nearest)));
+                let jacobian = Vector6::from_iterator(
+                    moved
+                        .coords
+                        .cross(normal)
+                        .iter()
+                        .chain(normal.iter())
+                        .map(|&value| f64::from(value)),
+                );
+                hessian += jacobian * jacobian.transpose();
+                gradient += jacobian * residual;
+                squared_sum += residual * residual;
+                correspondences += 1;
+            }
+            if correspondences < self.min_correspondences.max(7) {
+                return None;
+            }
+
+            #[expect(clippy::cast_precision_loss, reason = "only an average is needed")]
+            let (mean_squared, noise) = (
+                squared_sum / correspondences as f64,
+                squared_sum / (correspondences - 6) as f64,
+            );
+            let inverse = hessian.cholesky()?.inverse();
+            let update = -(inverse * gradient).cast::<f32>();
+            let rotation = update.fixed_rows::<3>(0).into_owned();
+            let translation = update.fixed_rows::<3>(3).into_owned();
+
+            registration.source_to_target = Isometry3::from_parts(
+                Translation3::from(translation),
+                UnitQuaternion::from_scaled_axis(rotation),
+            ) * registration.source_to_target;
+            registration.covariance = (inverse * noise).cast();
+            registration.correspondences = correspondences;
+            #[expect(clippy::cast_possible_truncation, reason = "errors are small")]
+            let rmse = mean_squared.sqrt() as f32;
+            registration.rmse = rmse;
+            registration.converged = rotation.norm() < self.rotation_tolerance
+                && translation.norm() < self.translation_tolerance;
+        }
+        Some(registration)
+    }
+}
+
+/// Adjoint of a rigid transform, mapping a small rotation and translation applied before it to
+/// one applied after it
+pub(crate) fn adjoint(transform: &Isometry3<f32>) -> Matrix6<f32> {
+    let rotation = transform.rotation.to_rotation_matrix().into_inner();
+    let translation = Vector3::from(transform.translation.vector).cross_matrix();
+    let mut adjoint = Matrix6::zeros();
+    adjoint.fixed_view_mut::<3, 3>(0, 0).copy_from(&rotation);
+    adjoint
+        .fixed_view_mut::<3, 3>(3, 0)
+        .copy_from(&(translation * rotation));
+    adjoint.fixed_view_mut::<3, 3>(3, 3).copy_from(&rotation);
+    adjoint
+}
@@ -0,0 +1,216 @@
+use std::time::Duration;
+
+use l2_protocol::LidarImuData;
+use nalgebra::{Matrix3x4, Quaternion, UnitQuaternion, UnitVector3, Vector3, Vector4};
+
+/// Standard acceleration due to gravity [m/s²]
+pub const STANDARD_GRAVITY: f32 = 9.806_65;
+
+/// A measurement of the lidar's IMU
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImuSample {
+    /// timestamp of the measurement, as reported by the lidar
+    pub stamp: Duration,
+    /// orientation of the sensor as estimated by the lidar
+    pub orientation: UnitQuaternion<f32>,
+    /// [rad/s]
+    pub angular_velocity: Vector3<f32>,
+    /// acceleration including gravity [m/s²]
+    pub linear_acceleration: Vector3<f32>,
+}
+
+impl From<&LidarImuData> for ImuSample {
+    fn from(value: &LidarImuData) -> Self {
+        let [x, y, z, w] = value.quaternion();
+        Self {
+            stamp: value.stamp(),
+            // the lidar reports a zero quaternion until its own filter has settled
+            orientation: UnitQuaternion::try_new(Quaternion::new(w, x, y, z), f32::EPSILON)
+                .unwrap_or_default(),
+            angular_velocity: value.angular_velocity().into(),
+            linear_acceleration: value.linear_acceleration().into(),
+        }
+    }
+}
+
+impl ImuSample {
+    /// Acceleration caused by motion alone, given the orientation of the sensor.
+    #[must_use]
+    pub fn motion_acceleration(&self, orientation: &UnitQuaternion<f32>) -> Vector3<f32> {
+        // an accelerometer at rest measures the reaction to gravity, pointing upwards
+        self.linear_acceleration
+            - orientation.inverse_transform_vector(&(Vector3::z() * STANDARD_GRAVITY))
+    }
+
+    /// Direction opposite to gravity in the sensor frame, as measured by the accelerometer.
+    ///
+    /// Only meaningful while the sensor accelerates little; `None` in free fall.
+    #[must_use]
+    pub fn up(&self) -> Option<UnitVector3<f32>> {
+        UnitVector3::try_new(self.linear_acceleration, f32::EPSILON)
+    }
+
+    /// A copy of this sample with the given gyroscope bias removed
+    #[must_use]
+    pub fn without_bias(&self, bias: &Vector3<f32>) -> Self {
+        Self {
+            angular_velocity: self.angular_velocity - bias,
+            ..*self
+        }
+    }
+}
+
+/// Limits within which the sensor is considered to be at rest
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StationaryThresholds {
+    /// [rad/s]
+    pub max_angular_velocity: f32,
+    /// maximum difference between the magnitude of the acceleration and gravity [m/s²]
+    pub max_acceleration_deviation: f32,
+    /// time the sensor has to be at rest before the bias is estimated
+    pub min_duration: Duration,
+}
+
+impl Default for StationaryThresholds {
+    fn default() -> Self {
+        Self {
+            max_angular_velocity: 0.05,
+            max_acceleration_deviation: 0.3,
+            min_duration: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Estimates the gyroscope bias as the mean angular velocity during periods at rest.
+#[derive(Debug, Clone, Default)]
+pub struct GyroBiasEstimator {
+    thresholds: StationaryThresholds,
+    /// start of the current period at rest
+    start: Option<Duration>,
+    sum: Vector3<f32>,
+    count: u32,
+    bias: Option<Vector3<f32>>,
+}
+
+impl GyroBiasEstimator {
+    #[must_use]
+    pub fn new(thresholds: StationaryThresholds) -> Self {
+        Self {
+            thresholds,
+            ..Self::default()
+        }
+    }
+
+    /// Adds a sample; returns `true` if the bias estimate has been updated.
+    pub fn update(&mut self, sample: &ImuSample) -> bool {
+        let StationaryThresholds {
+            max_angular_velocity,
+            max_acceleration_deviation,
+            min_duration,
+        } = self.thresholds;
+
+        let stationary = sample.angular_velocity.norm() <= max_angular_velocity
+            && (sample.linear_acceleration.norm() - STANDARD_GRAVITY).abs()
+                <= max_acceleration_deviation;
+        let start = match self.start {
+            Some(start) if stationary && sample.stamp >= start => start,
+            _ => {
+                self.start = stationary.then_some(sample.stamp);
+                self.sum = Vector3::zeros();
+                self.count = 0;
+                if !stationary {
+                    return false;
+                }
+                sample.stamp
+            }
+        };
+
+        self.sum += sample.angular_velocity;
+        self.count += 1;
+
+        if sample.stamp.saturating_sub(start) < min_duration {
+            return false;
+        }
+        #[expect(
+            clippy::cast_precision_loss,
+            reason = "sample counts stay far below 2^24"
+        )]
+        let count = self.count as f32;
+        self.bias = Some(self.sum / count);
+        true
+    }
+
+    /// The most recent estimate; `None` until the sensor has been at rest long enough
+    #[must_use]
+    pub fn bias(&self) -> Option<Vector3<f32>> {
+        self.bias
+    }
+}
+
+/// Madgwick's gradient descent orientation filter using angular velocity and acceleration.
+///
+/// It serves as an alternative to the orientation reported by the lidar. Without a magnetometer
+/// the heading is only integrated from the gyroscope and will drift.
+#[derive(Debug, Clone)]
+pub struct MadgwickFilter {
+    /// gain of the correction towards the measured gravity
+    beta: f32,
+    orientation: UnitQuaternion<f32>,
+    last_stamp: Option<Duration>,
+}
+
+impl MadgwickFilter {
+    /// A common choice for `beta` is `0.1`; larger values trust the accelerometer more.
+    #[must_use]
+    pub fn new(beta: f32) -> Self {
+        Self {
+            beta,
+            orientation: UnitQuaternion::identity(),
+            last_stamp: None,
+        }
+    }
+
+    /// Integrates a sample and returns the updated orientation.
+    ///
+    /// The first sample only sets the time base.
+    pub fn update(&mut self, sample: &ImuSample) -> UnitQuaternion<f32> {
+        let last_stamp = self.last_stamp.replace(sample.stamp);
+        let Some(dt) = last_stamp.and_then(|last_stamp| sample.stamp.checked_sub(last_stamp))
+        else {
+            return self.orientation;
+        };
+
+        let quat = self.orientation.into_inner();
+        let omega = sample.angular_velocity;
+        let mut rate = quat * Quaternion::new(0.0, omega.x, omega.y, omega.z) * 0.5;
+
+        if let Some(accel) = sample.linear_acceleration.try_normalize(f32::EPSILON) {
+            let (w, x, y, z) = (quat.w, quat.i, quat.j, quat.k);
+            // difference between the expected and the measured direction of gravity
+            let error = Vector3::new(
+                2.0 * (x * z - w * y) - accel.x,
+                2.0 * (w * x + y * z) - accel.y,
+                2.0 * (0.5 - x * x - y * y) - accel.z,
+            );
+            #[rustfmt::skip]
+            let jacobian = Matrix3x4::new(
+                -2.0 * y, 2.0 * z, -2.0 * w, 2.0 * x,
+                2.0 * x, 2.0 * w, 2.0 * z, 2.0 * y,
+                0.0, -4.0 * x, -4.0 * y, 0.0,
+            );
+            // components in the order w, x, y, z
+            let gradient: Vector4<f32> = jacobian.transpose() * error;
+            if let Some(step) = gradient.try_normalize(f32::EPSILON) {
+                rate -= Quaternion::new(step.x, step.y, step.z, step.w) * self.beta;
+            }
+        }
+
+        self.orientation = UnitQuaternion::new_normalize(quat + rate * dt.as_secs_f32());
+        self.orientation
+    }
+
+    #[must_use]
+    pub fn orientation(&self) -> UnitQuaternion<f32> {
+        self.orientation
+    }
+}
@@ -0,0 +1,165 @@
+use nalgebra::Point3;
+
+/// Subtrees with at most this many points are searched exhaustively
+const LEAF_SIZE: usize = 8;
+
+/// Static k-d tree for nearest neighbor queries.
+///
+/// The tree is stored implicitly: every subslice of `nodes` has its splitting node at the middle,
+/// with the nodes before it not above and the nodes after it not below along the splitting
+/// node's axis.
+#[derive(Debug)]
+pub(crate) struct KdTree<'points> {
+    positions: &'points [Point3<f32>],
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    position: Point3<f32>,
+    index: usize,
+    axis: usize,
+}
+
+impl<'points> KdTree<'points> {
+    pub(crate) fn new(positions: &'points [Point3<f32>]) -> Self {
+        let mut nodes = positions
+            .iter()
+            .enumerate()
+            .map(|(index, position)| Node {
+                position: *position,
+                index,
+                axis: 0,
+            })
+            .collect::<Vec<_>>();
+        build(&mut nodes);
+        Self { positions, nodes }
+    }
+
+    /// Indices and squared distances of the `count` points nearest to `position`, nearest first
+    pub(crate) fn nearest(&self, position: &Point3<f32>, count: usize) -> Vec<(usize, f32)> {
+        self.search(*position, None, count)
+    }
+
+    /// Mean distance of the point at `index` to its `neighbors` nearest neighbors; `None` if there
+    /// are no other points
+    pub(crate) fn mean_neighbor_distance(&self, index: usize, neighbors: usize) -> Option<f32> {
+        let nearest = self.search(*self.positions.get(index)?, Some(index), neighbors);
+        #[expect(clippy::cast_precision_loss, reason = "neighbor counts are small")]
+        let count = nearest.len() as f32;
+        (!nearest.is_empty()).then(|| {
+            nearest
+                .iter()
+                .map(|(_, distance)| distance.sqrt())
+                .sum::<f32>()
+                / count
+        })
+    }
+
+    fn search(
+        &self,
+        center: Point3<f32>,
+        exclude: Option<usize>,
+        count: usize,
+    ) -> Vec<(usize, f32)> {
+        let mut search = Search {
+            center,
+            exclude,
+            neighbors: count,
+            nearest: Vec::with_capacity(count + 1),
+        };
+        search.visit(&self.nodes);
+        search.nearest
+    }
+}
+
+/// Splits along the axis of the largest extent, which keeps surfaces aligned with an axis from
+/// degenerating the tree
+fn build(nodes: &mut [Node]) {
+    if nodes.len() <= LEAF_SIZE {
+        return;
+    }
+
+    let mut min = Point3::from([f32::INFINITY; 3]);
+    let mut max = Point3::from([f32::NEG_INFINITY; 3]);
+    for node in &*nodes {
+        min = min.inf(&node.position);
+        max = max.sup(&node.position);
+    }
+    let axis = (max - min).imax();
+
+    let middle = nodes.len() / 2;
+    nodes.select_nth_unstable_by(middle, |first, second| {
+        coordinate(&first.position, axis).total_cmp(&coordinate(&second.position, axis))
+    });
+    let (lower, upper) = nodes.split_at_mut(middle);
+    let Some((split, upper)) = upper.split_first_mut() else {
+        unreachable!("the middle should be within the nodes");
+    };
+    split.axis = axis;
+
+    build(lower);
+    build(upper);
+}
+
+fn coordinate(position: &Point3<f32>, axis: usize) -> f32 {
+    position.coords.get(axis).copied().unwrap_or_default()
+}
+
+struct Search {
+    center: Point3<f32>,
+    exclude: Option<usize>,
+    neighbors: usize,
+    /// indices and squared distances of the nearest neighbors found so far, nearest first
+    nearest: Vec<(usize, f32)>,
+}
+
+impl Search {
+    fn visit(&mut self, nodes: &[Node]) {
+        if nodes.len() <= LEAF_SIZE {
+            for node in nodes {
+                self.offer(node);
+            }
+            return;
+        }
+
+        let middle = nodes.len() / 2;
+        let (Some(lower), Some((split, upper))) = (
+            nodes.get(..middle),
+            nodes.get(middle..).and_then(<[Node]>::split_first),
+        ) else {
+            unreachable!("the middle should be within the nodes");
+        };
+        self.offer(split);
+
+        let offset = coordinate(&self.center, split.axis) - coordinate(&split.position, split.axis);
+        let (near, far) = if offset < 0.0 {
+            (lower, upper)
+        } else {
+            (upper, lower)
+        };
+        self.visit(near);
+        if self.nearest.len() < self.neighbors
+            || self
+                .nearest
+                .last()
+                .is_some_and(|&(_, farthest)| offset * offset < farthest)
+        {
+            self.visit(far);
+        }
+    }
+
+    fn offer(&mut self, node: &Node) {
+        if self.exclude == Some(node.index) {
+            return;
+        }
+        let distance = (node.position - self.center).norm_squared();
+        let insert_at = self
+            .nearest
+            .partition_point(|&(_, known)| known <= distance);
+        if insert_at < self.neighbors {
+            self.nearest.insert(insert_at, (node.index, distance));
+            self.nearest.truncate(self.neighbors);
+        }
+    }
+}
@@ -0,0 +1,43 @@
+#![allow(unused_crate_dependencies, reason = "used in benchmarks")]
+
+//! Processing of the measurements reported by the lidar
+
+mod calibration;
+mod cluster;
+mod filter;
+mod frames;
+mod grid;
+mod ground;
+mod icp;
+mod imu;
+mod kdtree;
+mod map;
+mod occupancy;
+mod odometry;
+mod outlier;
+mod points;
+mod reflectivity;
+mod scan;
+mod voxel;
+mod voxel_map;
+
+pub use calibration::{
+    BeamModel, Calibration, CalibrationEstimate, CalibrationEstimator, CalibrationParameter,
+};
+pub use cluster::{AlignedBox, Cluster, ClusterTracker, EuclideanClustering, OrientedBox};
+pub use filter::{FilterPipeline, PointFilter};
+pub use frames::{Frames, factory_imu_to_lidar};
+pub use ground::{GroundLabel, GroundSegmentation, Plane, RansacPlaneFit};
+pub use icp::{PointToPlaneIcp, Registration};
+pub use imu::{
+    GyroBiasEstimator, ImuSample, MadgwickFilter, STANDARD_GRAVITY, StationaryThresholds,
+};
+pub use map::{Keyframe, MapAccumulator, MapConfig};
+pub use occupancy::{OccupancyConfig, OccupancyGrid};
+pub use odometry::{IcpOdometry, PoseEstimate};
+pub use outlier::{RadiusOutlierFilter, StatisticalOutlierFilter};
+pub use points::{NoReturnPolicy, Point, PointConverter};
+pub use reflectivity::{ReflectivityCalibration, ReflectivityModel};
+pub use scan::{Scan, ScanAssembler, ScanPacket};
+pub use voxel::{VoxelGrid, VoxelMode};
+pub use voxel_map::{VoxelMap, VoxelMapConfig};
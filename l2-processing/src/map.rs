@@ -0,0 +1,240 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::Duration,
+};
+
+use nalgebra::Isometry3;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Point, VoxelMode,
+    grid::{CellKey, cell_key},
+    voxel::Voxel,
+};
+
+/// Parameters of a [`MapAccumulator`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MapConfig {
+    /// a scan becomes a keyframe once the sensor moved this far from the last keyframe [m]
+    pub keyframe_distance: f32,
+    /// or rotated this much [rad]
+    pub keyframe_angle: f32,
+    /// edge length of the voxels the keyframes are merged in [m]
+    pub leaf_size: f32,
+    pub mode: VoxelMode,
+}
+
+impl Default for MapConfig {
+    fn default() -> Self {
+        Self {
+            keyframe_distance: 0.5,
+            keyframe_angle: 15.0_f32.to_radians(),
+            leaf_size: 0.05,
+            mode: VoxelMode::default(),
+        }
+    }
+}
+
+/// A scan added to a [`MapAccumulator`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keyframe {
+    pub stamp: Duration,
+    /// pose of the scan's frame in the map frame
+    pub pose: Isometry3<f32>,
+    /// number of points merged into the map
+    pub point_count: usize,
+}
+
+/// Merges scans taken at known poses into one cloud in the map frame.
+///
+/// Only keyframes are merged, i.e. scans taken after the sensor moved or rotated enough since the
+/// last keyframe, which keeps the map from being dominated by a standing sensor. The poses come
+/// e.g. from an [`IcpOdometry`](crate::IcpOdometry).
+#[derive(Debug, Clone)]
+pub struct MapAccumulator {
+    config: MapConfig,
+    keyframes: Vec<Keyframe>,
+    indices: HashMap<CellKey, usize>,
+    /// in the order they have been first hit
+    voxels: Vec<Voxel>,
+}
+
+impl MapAccumulator {
+    #[must_use]
+    pub fn new(config: MapConfig) -> Self {
+        Self {
+            config,
+            keyframes: Vec::new(),
+            indices: HashMap::new(),
+            voxels: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn config(&self) -> &MapConfig {
+        &self.config
+    }
+
+    #[must_use]
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    /// number of points of the merged cloud
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.voxels.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.voxels.is_empty()
+    }
+
+    /// Whether a scan taken at `pose` would become a keyframe
+    #[must_use]
+    pub fn is_keyframe(&self, pose: &Isometry3<f32>) -> bool {
+        self.keyframes.last().is_none_or(|last| {
+            let motion = last.pose.inverse() * pose;
+            motion.translation.vector.norm() >= self.config.keyframe_distance
+                || motion.rotation.angle() >= self.config.keyframe_angle
+        })
+    }
+
+    /// Merges a scan taken at `pose` if it's a keyframe and returns whether it was.
+    ///
+    /// Points without a return or with non-finite coordinates are skipped.
+    pub fn insert(&mut self, stamp: Duration, pose: &Isometry3<f32>, points: &[Point]) -> bool {
+        if !self.is_keyframe(pose) {
+            return false;
+        }
+
+        let mut point_count = 0;
+        for point in points {
+            if !(point.returned && point.position.iter().all(|coord| coord.is_finite())) {
+                continue;
+            }
+            let point = Point {
+                position: pose * point.position,
+                ..*point
+            };
+            let key = cell_key(&point.position, self.config.leaf_size);
+            let index = *self.indices.entry(key).or_insert_with(|| {
+                self.voxels.push(Voxel::new(point));
+                self.voxels.len() - 1
+            });
+            let Some(voxel) = self.voxels.get_mut(index) else {
+                unreachable!("voxel indices should be valid");
+            };
+            voxel.add(&point);
+            point_count += 1;
+        }
+
+        self.keyframes.push(Keyframe {
+            stamp,
+            pose: *pose,
+            point_count,
+        });
+        true
+    }
+
+    /// The merged cloud in the map frame, one point per voxel
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        self.voxels
+            .iter()
+            .map(|voxel| voxel.point(self.config.mode))
+    }
+
+    /// Writes the merged cloud to `path`, as PCD or PLY depending on its extension.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the extension is neither `pcd` nor `ply` or the file can't be written.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        let pcd = match extension.as_deref() {
+            Some("pcd") => true,
+            Some("ply") => false,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "map path should end in .pcd or .ply",
+                ));
+            }
+        };
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        if pcd {
+            self.write_pcd(&mut writer)?;
+        } else {
+            self.write_ply(&mut writer)?;
+        }
+        writer.flush()
+    }
+
+    /// Writes the merged cloud as binary PCD with the fields `x y z intensity`, all `f32`, as
+    /// read by PCL into `PointXYZI`.
+    ///
+    /// # Errors
+    ///
+    /// Errors if writing fails.
+    pub fn write_pcd(&self, mut writer: impl Write) -> io::Result<()> {
+        let count = self.len();
+        write!(
+            writer,
+            "# .PCD v0.7 - Point Cloud Data file format\n\
+             VERSION 0.7\n\
+             FIELDS x y z intensity\n\
+             SIZE 4 4 4 4\n\
+             TYPE F F F F\n\
+             COUNT 1 1 1 1\n\
+             WIDTH {count}\n\
+             HEIGHT 1\n\
+             VIEWPOINT 0 0 0 1 0 0 0\n\
+             POINTS {count}\n\
+             DATA binary\n"
+        )?;
+        for point in self.points() {
+            for value in point.position.iter().chain([&f32::from(point.intensity)]) {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the merged cloud as binary little endian PLY with the float properties `x y z` and
+    /// the uchar property `intensity`.
+    ///
+    /// # Errors
+    ///
+    /// Errors if writing fails.
+    pub fn write_ply(&self, mut writer: impl Write) -> io::Result<()> {
+        write!(
+            writer,
+            "ply\n\
+             format binary_little_endian 1.0\n\
+             element vertex {}\n\
+             property float x\n\
+             property float y\n\
+             property float z\n\
+             property uchar intensity\n\
+             end_header\n",
+            self.len()
+        )?;
+        for point in self.points() {
+            for value in point.position.iter() {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+            writer.write_all(&[point.intensity])?;
+        }
+        Ok(())
+    }
+}
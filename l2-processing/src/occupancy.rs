@@ -0,0 +1,349 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use l2_protocol::Lidar2DPointData;
+use nalgebra::{Isometry2, Point2, Point3, Vector2};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::Point;
+
+/// Parameters of an [`OccupancyGrid`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OccupancyConfig {
+    /// edge length of the cells [m]
+    pub resolution: f32,
+    /// points of 3D scans below this height are ignored [m]
+    pub min_height: f32,
+    /// points of 3D scans above this height are ignored [m]
+    pub max_height: f32,
+    /// log-odds added to a cell a beam ends in
+    pub hit: f32,
+    /// log-odds added to a cell a beam passes through; negative
+    pub miss: f32,
+    /// lower bound of the log-odds, limiting how certain a cell can be free
+    pub min_log_odds: f32,
+    /// upper bound of the log-odds, limiting how certain a cell can be occupied
+    pub max_log_odds: f32,
+}
+
+impl Default for OccupancyConfig {
+    fn default() -> Self {
+        Self {
+            resolution: 0.05,
+            min_height: 0.1,
+            max_height: 1.5,
+            hit: 0.85,
+            miss: -0.4,
+            min_log_odds: -2.0,
+            max_log_odds: 3.5,
+        }
+    }
+}
+
+/// Probability above which cells are exported as occupied
+const OCCUPIED_THRESHOLD: f32 = 0.65;
+/// Probability below which cells are exported as free
+const FREE_THRESHOLD: f32 = 0.196;
+
+/// A 2D map of the probability of cells being occupied, stored as log-odds.
+///
+/// The grid lies in the `x`-`y` plane of the map frame, heights are measured along `z`. Cells a
+/// beam passes through become more likely free, the cell it ends in more likely occupied. Scans
+/// have to be in the map frame already, de-skewed if the sensor moves noticeably during a scan.
+#[derive(Debug, Clone)]
+pub struct OccupancyGrid {
+    config: OccupancyConfig,
+    /// corner of the cell `(0, 0)` in the map frame
+    origin: Point2<f32>,
+    width: usize,
+    height: usize,
+    /// row-major, starting at the origin
+    log_odds: Vec<f32>,
+}
+
+impl OccupancyGrid {
+    /// A grid covering `size` [m] starting at the corner `origin`, with all cells unknown.
+    #[must_use]
+    pub fn new(config: OccupancyConfig, origin: Point2<f32>, size: Vector2<f32>) -> Self {
+        #[expect(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            reason = "the cell counts are positive and small"
+        )]
+        let [width, height] = (size / config.resolution)
+            .map(|cells| cells.ceil().max(1.0) as usize)
+            .into();
+        Self {
+            config,
+            origin,
+            width,
+            height,
+            log_odds: vec![0.0; width * height],
+        }
+    }
+
+    #[must_use]
+    pub fn config(&self) -> &OccupancyConfig {
+        &self.config
+    }
+
+    #[must_use]
+    pub fn origin(&self) -> Point2<f32> {
+        self.origin
+    }
+
+    /// number of cells along `x`
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// number of cells along `y`
+    #[must_use]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Log-odds of the cell containing `position`; `None` outside of the grid
+    #[must_use]
+    pub fn log_odds(&self, position: &Point2<f32>) -> Option<f32> {
+        let [column, row] = self.cell(*position);
+        self.index(column, row)
+            .and_then(|index| self.log_odds.get(index))
+            .copied()
+    }
+
+    /// Probability of the cell containing `position` being occupied; `None` outside of the grid
+    #[must_use]
+    pub fn probability(&self, position: &Point2<f32>) -> Option<f32> {
+        self.log_odds(position).map(probability)
+    }
+
+    /// Adds a 3D scan taken from `origin`, both in the map frame.
+    ///
+    /// Points outside of the height band are ignored. Points without a return, e.g. placed at the
+    /// maximum range by [`NoReturnPolicy::MaxRange`], only clear the cells along their beam.
+    ///
+    /// [`NoReturnPolicy::MaxRange`]: crate::NoReturnPolicy::MaxRange
+    pub fn insert_scan(&mut self, origin: &Point3<f32>, points: &[Point]) {
+        let start = origin.xy();
+        for point in points {
+            let position = point.position;
+            if position.iter().all(|coord| coord.is_finite())
+                && (self.config.min_height..=self.config.max_height).contains(&position.z)
+            {
+                self.insert_beam(start, position.xy(), point.returned);
+            }
+        }
+    }
+
+    /// Adds the beams of a 2D scan taken at `sensor_to_map`.
+    ///
+    /// The beams are interpreted like a planar laser scan: beam `i` points at
+    /// `angle_min + i * angle_increment` counter-clockwise from the sensor's `x` axis. Ranges
+    /// outside of the range reported by the lidar only clear the cells up to the maximum range.
+    /// Beams without a finite end, e.g. due to a corrupt maximum range, are skipped.
+    pub fn insert_2d_point_data(
+        &mut self,
+        data: &Lidar2DPointData,
+        sensor_to_map: &Isometry2<f32>,
+    ) {
+        let param = data.param();
+        let start = Point2::from(sensor_to_map.translation.vector);
+        let range_limits = data.range_min()..=data.range_max();
+
+        let mut angle = data.angle_min();
+        for &range in data.ranges() {
+            // [mm] → [m]
+            let range = param.range_scale() * (f32::from(range) + param.range_bias()) * 0.001;
+            let returned = range_limits.contains(&range);
+            let length = if returned { range } else { data.range_max() };
+
+            let (sin, cos) = angle.sin_cos();
+            let end = sensor_to_map * Point2::new(cos * length, sin * length);
+            self.insert_beam(start, end, returned);
+
+            angle += data.angle_increment();
+        }
+    }
+
+    /// Clears the cells from `start` to `end` and marks the cell of `end` as occupied if `hit`.
+    ///
+    /// Only the part of the beam within the grid is walked, so beams reaching far beyond it don't
+    /// take long.
+    fn insert_beam(&mut self, start: Point2<f32>, end: Point2<f32>, hit: bool) {
+        let from = self.cells(start);
+        let to = self.cells(end);
+        if !from.iter().chain(to.iter()).all(|cells| cells.is_finite()) {
+            return;
+        }
+        let Some((enter, leave)) = self.clip(from, to) else {
+            return;
+        };
+        // the beam ends outside of the grid, so the last cell within is only passed through
+        let hit = hit && leave >= 1.0;
+        let [start_column, start_row] = floor(from + (to - from) * enter);
+        let [end_column, end_row] = floor(from + (to - from) * leave);
+
+        // Bresenham's line, excluding the end cell
+        let (delta_column, delta_row) = (
+            (end_column - start_column).abs(),
+            -(end_row - start_row).abs(),
+        );
+        let (step_column, step_row) = (
+            (end_column - start_column).signum(),
+            (end_row - start_row).signum(),
+        );
+        let (mut column, mut row) = (start_column, start_row);
+        let mut error = delta_column + delta_row;
+        while (column, row) != (end_column, end_row) {
+            self.update(column, row, self.config.miss);
+            let doubled = 2 * error;
+            if doubled >= delta_row {
+                error += delta_row;
+                column += step_column;
+            }
+            if doubled <= delta_column {
+                error += delta_column;
+                row += step_row;
+            }
+        }
+
+        let end_update = if hit {
+            self.config.hit
+        } else {
+            self.config.miss
+        };
+        self.update(end_column, end_row, end_update);
+    }
+
+    fn update(&mut self, column: i64, row: i64, change: f32) {
+        let (min, max) = (self.config.min_log_odds, self.config.max_log_odds);
+        if let Some(cell) = self
+            .index(column, row)
+            .and_then(|index| self.log_odds.get_mut(index))
+        {
+            *cell = (*cell + change).clamp(min, max);
+        }
+    }
+
+    /// Position relative to the origin, in cells
+    fn cells(&self, position: Point2<f32>) -> Vector2<f32> {
+        (position - self.origin) / self.config.resolution
+    }
+
+    fn cell(&self, position: Point2<f32>) -> [i64; 2] {
+        floor(self.cells(position))
+    }
+
+    /// Clips the segment from `from` to `to`, both in cells, to the grid.
+    ///
+    /// Returns the fractions of the segment at which it enters and leaves the grid; `None` if it
+    /// misses the grid.
+    #[expect(clippy::cast_precision_loss, reason = "the cell counts are small")]
+    fn clip(&self, from: Vector2<f32>, to: Vector2<f32>) -> Option<(f32, f32)> {
+        let size = Vector2::new(self.width as f32, self.height as f32);
+        let (mut enter, mut leave) = (0.0_f32, 1.0_f32);
+        for ((start, delta), max) in from.iter().zip((to - from).iter()).zip(size.iter()) {
+            if delta.abs() <= f32::EPSILON {
+                // parallel to this boundary
+                if !(0.0..=*max).contains(start) {
+                    return None;
+                }
+                continue;
+            }
+            let (low, high) = ((0.0 - start) / delta, (max - start) / delta);
+            enter = enter.max(low.min(high));
+            leave = leave.min(low.max(high));
+        }
+        (enter <= leave).then_some((enter, leave))
+    }
+
+    fn index(&self, column: impl TryInto<usize>, row: impl TryInto<usize>) -> Option<usize> {
+        let (Ok(column), Ok(row)) = (column.try_into(), row.try_into()) else {
+            return None;
+        };
+        (column < self.width && row < self.height).then_some(row * self.width + column)
+    }
+
+    /// Writes the map in the format of the common ROS map servers: a YAML description at `path`
+    /// and the image next to it, with the same name and the extension `pgm`.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the files can't be written.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let image_path = path.with_extension("pgm");
+        let image_name = image_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid map path"))?;
+
+        let mut yaml = BufWriter::new(File::create(path)?);
+        self.write_yaml(&mut yaml, image_name)?;
+        yaml.flush()?;
+
+        let mut image = BufWriter::new(File::create(&image_path)?);
+        self.write_pgm(&mut image)?;
+        image.flush()
+    }
+
+    /// Writes the map as binary PGM image: occupied cells black, free cells white and unknown cells
+    /// grey, with the top row at the largest `y`.
+    ///
+    /// # Errors
+    ///
+    /// Errors if writing fails.
+    pub fn write_pgm(&self, mut writer: impl Write) -> io::Result<()> {
+        write!(writer, "P5\n{} {}\n255\n", self.width, self.height)?;
+        for row in self.log_odds.chunks(self.width.max(1)).rev() {
+            let pixels = row
+                .iter()
+                .map(|&log_odds| match probability(log_odds) {
+                    probability if probability > OCCUPIED_THRESHOLD => 0,
+                    probability if probability < FREE_THRESHOLD => 254,
+                    _ => 205,
+                })
+                .collect::<Vec<u8>>();
+            writer.write_all(&pixels)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the map server's description of the image written by
+    /// [`write_pgm`](Self::write_pgm).
+    ///
+    /// # Errors
+    ///
+    /// Errors if writing fails.
+    pub fn write_yaml(&self, mut writer: impl Write, image_name: &str) -> io::Result<()> {
+        writeln!(writer, "image: {image_name}")?;
+        writeln!(writer, "mode: trinary")?;
+        writeln!(writer, "resolution: {}", self.config.resolution)?;
+        writeln!(
+            writer,
+            "origin: [{}, {}, 0.0]",
+            self.origin.x, self.origin.y
+        )?;
+        writeln!(writer, "negate: 0")?;
+        writeln!(writer, "occupied_thresh: {OCCUPIED_THRESHOLD}")?;
+        writeln!(writer, "free_thresh: {FREE_THRESHOLD}")
+    }
+}
+
+#[expect(
+    clippy::cast_possible_truncation,
+    reason = "positions beyond ±2^63 cells saturate and are outside of the grid"
+)]
+fn floor(cells: Vector2<f32>) -> [i64; 2] {
+    cells.map(|cells| cells.floor() as i64).into()
+}
+
+fn probability(log_odds: f32) -> f32 {
+    1.0 - 1.0 / (1.0 + log_odds.exp())
+}
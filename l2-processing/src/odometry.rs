@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+use nalgebra::{Isometry3, Matrix6, Point3, UnitQuaternion};
+
+use crate::{ImuSample, Point, PointToPlaneIcp, icp::adjoint};
+
+/// A pose of the trajectory estimated by [`IcpOdometry`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PoseEstimate {
+    /// timestamp of the scan
+    pub stamp: Duration,
+    /// pose of the scan's frame relative to the frame of the first scan
+    pub pose: Isometry3<f32>,
+    /// covariance of `pose`, as a small rotation [rad] followed by a small translation [m]
+    /// applied before it, i.e. expressed in the scan's frame; zero for the first scan
+    pub covariance: Matrix6<f32>,
+}
+
+/// Lidar-inertial odometry registering every scan against the previous one.
+///
+/// The rotation between two scans is integrated from the gyroscope and seeds the registration,
+/// together with the translation of the previous step. Scans and IMU samples have to be
+/// expressed in the same frame, e.g. the base frame using
+/// [`Frames::imu_sample_to_base`](crate::Frames::imu_sample_to_base). Scans are best
+/// downsampled, e.g. with a [`VoxelGrid`](crate::VoxelGrid), and de-skewed beforehand.
+#[derive(Debug, Clone)]
+pub struct IcpOdometry {
+    icp: PointToPlaneIcp,
+    previous: Option<Vec<Point3<f32>>>,
+    /// rotation integrated from the gyroscope since the previous scan
+    rotation: UnitQuaternion<f32>,
+    last_imu_stamp: Option<Duration>,
+    /// motion between the previous two scans
+    motion: Isometry3<f32>,
+    trajectory: Vec<PoseEstimate>,
+}
+
+impl IcpOdometry {
+    #[must_use]
+    pub fn new(icp: PointToPlaneIcp) -> Self {
+        Self {
+            icp,
+            previous: None,
+            rotation: UnitQuaternion::identity(),
+            last_imu_stamp: None,
+            motion: Isometry3::identity(),
+            trajectory: Vec::new(),
+        }
+    }
+
+    /// Integrates the angular velocity of a sample; the first sample only sets the time base.
+    pub fn update_imu(&mut self, sample: &ImuSample) {
+        let last_stamp = self.last_imu_stamp.replace(sample.stamp);
+        if let Some(dt) = last_stamp.and_then(|last_stamp| sample.stamp.checked_sub(last_stamp)) {
+            self.rotation *=
+                UnitQuaternion::from_scaled_axis(sample.angular_velocity * dt.as_secs_f32());
+        }
+    }
+
+    /// Registers a scan against the previous one and appends its pose to the trajectory.
+    ///
+    /// Points without a return or with non-finite coordinates are ignored. `None` if the
+    /// registration failed; the scan is dropped and the next one is registered against the last
+    /// successful one.
+    pub fn update_scan(&mut self, stamp: Duration, points: &[Point]) -> Option<&PoseEstimate> {
+        let positions = points
+            .iter()
+            .filter(|point| point.returned && point.position.iter().all(|coord| coord.is_finite()))
+            .map(|point| point.position)
+            .collect::<Vec<_>>();
+
+        let Some(previous) = &self.previous else {
+            self.previous = Some(positions);
+            self.rotation = UnitQuaternion::identity();
+            self.trajectory.push(PoseEstimate {
+                stamp,
+                pose: Isometry3::identity(),
+                covariance: Matrix6::zeros(),
+            });
+            return self.trajectory.last();
+        };
+
+        let seed = Isometry3::from_parts(self.motion.translation, self.rotation);
+        let registration = self.icp.register(&positions, previous, &seed)?;
+        let motion = registration.source_to_target;
+
+        // move the covariance of the motion into the new scan's frame and add it to the previous
+        // one carried along the motion
+        let inverse = adjoint(&motion.inverse());
+        let last = self.trajectory.last().copied().unwrap_or(PoseEstimate {
+            stamp,
+            pose: Isometry3::identity(),
+            covariance: Matrix6::zeros(),
+        });
+        let covariance =
+            inverse * (last.covariance + registration.covariance) * inverse.transpose();
+
+        self.motion = motion;
+        self.rotation = UnitQuaternion::identity();
+        self.previous = Some(positions);
+        self.trajectory.push(PoseEstimate {
+            stamp,
+            pose: last.pose * motion,
+            covariance,
+        });
+        self.trajectory.last()
+    }
+
+    /// Pose of the latest scan; identity before the first one
+    #[must_use]
+    pub fn pose(&self) -> Isometry3<f32> {
+        self.trajectory
+            .last()
+            .map_or_else(Isometry3::identity, |estimate| estimate.pose)
+    }
+
+    #[must_use]
+    pub fn trajectory(&self) -> &[PoseEstimate] {
+        &self.trajectory
+    }
+}
@@ -0,0 +1,97 @@
+use nalgebra::Point3;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Point, grid::PointGrid, kdtree::KdTree};
+
+/// Removes points with too few neighbors within a radius, e.g. isolated returns from dust or rain.
+///
+/// Points without finite coordinates are kept as they are.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RadiusOutlierFilter {
+    /// [m]
+    pub radius: f32,
+    /// number of other points required within the radius
+    pub min_neighbors: usize,
+}
+
+impl RadiusOutlierFilter {
+    pub fn apply(&self, points: &mut Vec<Point>) {
+        let positions = positions(points);
+        let grid = PointGrid::new(&positions, self.radius);
+        let keep = (0..positions.len())
+            .map(|index| {
+                grid.count_within(index, self.radius, self.min_neighbors) >= self.min_neighbors
+            })
+            .collect::<Vec<_>>();
+        retain_flagged(points, &keep);
+    }
+}
+
+/// Removes points whose mean distance to their nearest neighbors is unusually large compared to
+/// the rest of the cloud.
+///
+/// Points without finite coordinates are kept as they are.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatisticalOutlierFilter {
+    /// number of nearest neighbors to average the distance over
+    pub neighbors: usize,
+    /// points with a mean distance above `mean + std_ratio * standard deviation` are removed
+    pub std_ratio: f32,
+}
+
+impl StatisticalOutlierFilter {
+    pub fn apply(&self, points: &mut Vec<Point>) {
+        let positions = positions(points);
+        if positions.len() <= self.neighbors {
+            return;
+        }
+
+        let tree = KdTree::new(&positions);
+        let distances = (0..positions.len())
+            .map(|index| tree.mean_neighbor_distance(index, self.neighbors))
+            .collect::<Vec<_>>();
+
+        let (sum, sum_squared, count) = distances.iter().flatten().fold(
+            (0.0_f64, 0.0_f64, 0.0_f64),
+            |(sum, sum_squared, count), &distance| {
+                let distance = f64::from(distance);
+                (
+                    sum + distance,
+                    sum_squared + distance * distance,
+                    count + 1.0,
+                )
+            },
+        );
+        let mean = sum / count;
+        let std_dev = (sum_squared / count - mean * mean).max(0.0).sqrt();
+        let limit = mean + f64::from(self.std_ratio) * std_dev;
+
+        let keep = distances
+            .iter()
+            .map(|distance| distance.is_some_and(|distance| f64::from(distance) <= limit))
+            .collect::<Vec<_>>();
+        retain_flagged(points, &keep);
+    }
+}
+
+fn is_finite(point: &Point) -> bool {
+    point.position.iter().all(|coord| coord.is_finite())
+}
+
+/// Positions of the points with finite coordinates
+fn positions(points: &[Point]) -> Vec<Point3<f32>> {
+    points
+        .iter()
+        .filter(|point| is_finite(point))
+        .map(|point| point.position)
+        .collect()
+}
+
+/// Removes the points with finite coordinates whose flag is unset; others are kept
+fn retain_flagged(points: &mut Vec<Point>, keep: &[bool]) {
+    let mut keep = keep.iter();
+    points.retain(|point| !is_finite(point) || keep.next().copied().unwrap_or(true));
+}
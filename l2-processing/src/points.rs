@@ -0,0 +1,127 @@
+use l2_protocol::LidarPointData;
+use nalgebra::{Isometry3, Point3};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{BeamModel, Calibration};
+
+/// A single measurement of the lidar
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    /// [m]
+    pub position: Point3<f32>,
+    /// distance measured from the lidar's origin [m]
+    pub range: f32,
+    /// `false` for beams without a valid return; see [`NoReturnPolicy`]
+    pub returned: bool,
+    /// reflectivity [0-255]
+    pub intensity: u8,
+    /// time of the measurement relative to the timestamp of its packet [s]
+    pub time: f32,
+}
+
+/// How beams are represented whose range is outside of the range reported by the lidar, e.g.
+/// because nothing reflected the laser
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NoReturnPolicy {
+    /// skip them
+    #[default]
+    Drop,
+    /// place them at the maximum range, e.g. to mark free space along the beam
+    MaxRange,
+    /// keep them with NaN coordinates to preserve the structure of the scan
+    Nan,
+}
+
+/// Computes the position of the points in point data packets.
+///
+/// Points are computed in the lidar frame (see [`Frames`](crate::Frames)) and then moved into the
+/// target frame.
+#[derive(Debug, Clone, Default)]
+pub struct PointConverter {
+    lidar_to_target: Isometry3<f32>,
+    no_return: NoReturnPolicy,
+    /// replaces the calibration reported in the packets
+    calibration: Option<Calibration>,
+}
+
+impl PointConverter {
+    /// `lidar_to_target` moves points from the lidar frame into the frame of the output.
+    #[must_use]
+    pub fn new(lidar_to_target: Isometry3<f32>) -> Self {
+        Self {
+            lidar_to_target,
+            no_return: NoReturnPolicy::default(),
+            calibration: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_no_return(self, no_return: NoReturnPolicy) -> Self {
+        Self { no_return, ..self }
+    }
+
+    /// Uses `calibration` instead of the calibration reported in every packet.
+    #[must_use]
+    pub fn with_calibration(self, calibration: Calibration) -> Self {
+        Self {
+            calibration: Some(calibration),
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn convert(&self, data: &LidarPointData) -> Vec<Point> {
+        let mut points = Vec::with_capacity(data.ranges().len());
+        self.convert_into(data, &mut points);
+        points
+    }
+
+    /// Appends the points of `data` to `points`.
+    ///
+    /// Beams outside of the range reported by the lidar are treated according to the
+    /// [`NoReturnPolicy`].
+    pub fn convert_into(&self, data: &LidarPointData, points: &mut Vec<Point>) {
+        let model = BeamModel::new(
+            self.calibration
+                .unwrap_or_else(|| Calibration::from(data.param())),
+        );
+        let range_limits = data.range_min()..=data.range_max();
+
+        let mut alpha = data.angle_min();
+        let mut theta = data.com_horizontal_angle_start();
+        let mut time = 0.0;
+
+        for (&range, &intensity) in data.ranges().iter().zip(data.intensities()) {
+            let range = model.range(range);
+            let returned = range_limits.contains(&range);
+
+            let beam_length = match (returned, self.no_return) {
+                (true, _) => Some(range),
+                (false, NoReturnPolicy::Drop) => None,
+                (false, NoReturnPolicy::MaxRange) => Some(data.range_max()),
+                (false, NoReturnPolicy::Nan) => Some(f32::NAN),
+            };
+
+            if let Some(beam_length) = beam_length {
+                let position = model.position(alpha, theta, beam_length);
+                points.push(Point {
+                    position: self.lidar_to_target * position,
+                    range,
+                    returned,
+                    intensity,
+                    time,
+                });
+            }
+
+            alpha += data.angle_increment();
+            theta += data.com_horizontal_angle_step();
+            time += data.time_increment();
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use nalgebra::Point3;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Plane, Point, RansacPlaneFit, kdtree::KdTree};
+
+/// Relation between the raw intensity reported by the lidar and the reflectivity of a surface.
+///
+/// A diffuse surface with reflectivity `ρ` in `[0, 1]` hit at range `r` and incidence angle `θ` is
+/// assumed to return the raw intensity `ρ · gain(r) · cos θ`. The gain is interpolated linearly
+/// between the calibrated ranges and constant beyond them; fit it with a
+/// [`ReflectivityCalibration`]. The default model maps intensities to reflectivities one to one.
+///
+/// With the `serde` feature it can be stored in a file, e.g. as JSON:
+///
+/// ```json
+/// {
+///   "ranges": [1.0, 3.0, 8.0],
+///   "gains": [240.0, 180.0, 95.0],
+///   "incidence_correction": true
+/// }
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReflectivityModel {
+    /// ranges the gains have been calibrated at, ascending [m]
+    pub ranges: Vec<f32>,
+    /// raw intensity of a perfectly reflecting surface facing the lidar, per range
+    pub gains: Vec<f32>,
+    /// whether to correct for the incidence angle, estimated from the normals of neighboring points
+    pub incidence_correction: bool,
+    /// number of points the normal of each point is estimated from
+    pub normal_neighbors: usize,
+    /// lower bound of the incidence angle's cosine, limiting the correction of grazing beams
+    pub min_cos_incidence: f32,
+}
+
+impl Default for ReflectivityModel {
+    fn default() -> Self {
+        Self {
+            ranges: vec![1.0],
+            gains: vec![255.0],
+            incidence_correction: false,
+            normal_neighbors: 10,
+            min_cos_incidence: 0.2,
+        }
+    }
+}
+
+impl ReflectivityModel {
+    /// Raw intensity of a perfectly reflecting surface facing the lidar at `range` [m]
+    #[must_use]
+    pub fn gain(&self, range: f32) -> f32 {
+        let upper = self
+            .ranges
+            .partition_point(|&calibrated| calibrated < range);
+        let lower = upper.saturating_sub(1);
+        match (
+            self.ranges.get(lower).zip(self.gains.get(lower)),
+            self.ranges.get(upper).zip(self.gains.get(upper)),
+        ) {
+            (Some((&lower_range, &lower_gain)), Some((&upper_range, &upper_gain)))
+                if upper_range > lower_range =>
+            {
+                let fraction =
+                    ((range - lower_range) / (upper_range - lower_range)).clamp(0.0, 1.0);
+                lower_gain + (upper_gain - lower_gain) * fraction
+            }
+            (_, Some((_, &gain))) | (Some((_, &gain)), None) => gain,
+            (None, None) => 255.0,
+        }
+    }
+
+    /// Reflectivity of a surface returning `intensity` at `range` [m], where the beam's incidence
+    /// angle has the cosine `cos_incidence`; about `[0, 1]` but not clamped
+    #[must_use]
+    pub fn reflectivity(&self, intensity: u8, range: f32, cos_incidence: f32) -> f32 {
+        f32::from(intensity)
+            / (self.gain(range) * cos_incidence.clamp(self.min_cos_incidence, 1.0))
+                .max(f32::EPSILON)
+    }
+
+    /// Replaces the intensities of a scan taken from `origin` with reflectivities scaled to
+    /// `0..=255`.
+    ///
+    /// Points without a return or with non-finite coordinates are left as they are. Normals are
+    /// estimated from the scan itself, so the incidence correction needs a dense scan, e.g. an
+    /// assembled one rather than a single packet.
+    pub fn apply(&self, points: &mut [Point], origin: &Point3<f32>) {
+        let valid =
+            |point: &Point| point.returned && point.position.iter().all(|coord| coord.is_finite());
+        let positions = points
+            .iter()
+            .filter(|point| valid(point))
+            .map(|point| point.position)
+            .collect::<Vec<_>>();
+        let tree = self.incidence_correction.then(|| KdTree::new(&positions));
+
+        for (index, point) in points.iter_mut().filter(|point| valid(point)).enumerate() {
+            let cos_incidence = tree.as_ref().map_or(1.0, |tree| {
+                cos_incidence(tree, &positions, index, origin, self.normal_neighbors)
+            });
+            #[expect(
+                clippy::cast_possible_truncation,
+                clippy::cast_sign_loss,
+                reason = "the value is clamped to the range of u8"
+            )]
+            let scaled = (self.reflectivity(point.intensity, point.range, cos_incidence) * 255.0)
+                .round()
+                .clamp(0.0, 255.0) as u8;
+            point.intensity = scaled;
+        }
+    }
+}
+
+/// Cosine of the angle between the beam from `origin` to a point and the normal of the surface
+/// around it; 1 if no normal can be estimated
+fn cos_incidence(
+    tree: &KdTree<'_>,
+    positions: &[Point3<f32>],
+    index: usize,
+    origin: &Point3<f32>,
+    neighbors: usize,
+) -> f32 {
+    let Some(position) = positions.get(index) else {
+        return 1.0;
+    };
+    let neighborhood = tree
+        .nearest(position, neighbors)
+        .into_iter()
+        .filter_map(|(neighbor, _)| positions.get(neighbor))
+        .copied()
+        .collect::<Vec<_>>();
+    match (
+        Plane::fit(&neighborhood),
+        (position - origin).try_normalize(f32::EPSILON),
+    ) {
+        (Some(plane), Some(beam)) => plane.normal.dot(&beam).abs(),
+        _ => 1.0,
+    }
+}
+
+/// Beams hitting the target at a larger incidence angle are not used for calibration
+const MIN_CALIBRATION_COS_INCIDENCE: f32 = 0.5;
+
+/// Fits a [`ReflectivityModel`] from scans of a flat target with known reflectivity.
+///
+/// The target should be observed over the whole range of interest, e.g. by moving it away from
+/// the lidar during a recording. The samples are grouped into range bins; the gain of every bin is
+/// the median of its samples, corrected for the incidence angle.
+#[derive(Debug, Clone)]
+pub struct ReflectivityCalibration {
+    /// reflectivity of the target in `[0, 1]`
+    reflectivity: f32,
+    /// width of the range bins [m]
+    bin_width: f32,
+    /// bins with fewer samples are skipped
+    min_samples: usize,
+    ransac: RansacPlaneFit,
+    /// range [m] and gain of every sample
+    samples: Vec<(f32, f32)>,
+}
+
+impl ReflectivityCalibration {
+    #[must_use]
+    pub fn new(reflectivity: f32) -> Self {
+        Self {
+            reflectivity,
+            bin_width: 0.5,
+            min_samples: 20,
+            ransac: RansacPlaneFit::default(),
+            samples: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_bin_width(self, bin_width: f32) -> Self {
+        Self { bin_width, ..self }
+    }
+
+    #[must_use]
+    pub fn with_min_samples(self, min_samples: usize) -> Self {
+        Self {
+            min_samples,
+            ..self
+        }
+    }
+
+    /// Replaces the plane fit finding the target's surface.
+    #[must_use]
+    pub fn with_ransac(self, ransac: RansacPlaneFit) -> Self {
+        Self { ransac, ..self }
+    }
+
+    #[must_use]
+    pub fn sample_count(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Adds the points of one scan of the target taken from `origin`, e.g. the points within a box
+    /// around it, and returns the number of samples added.
+    ///
+    /// The target's plane is fit with RANSAC; points off it, without a return or hit at a grazing
+    /// angle are ignored.
+    pub fn add_scan(&mut self, points: &[Point], origin: &Point3<f32>) -> usize {
+        let points = points
+            .iter()
+            .filter(|point| point.returned && point.position.iter().all(|coord| coord.is_finite()))
+            .collect::<Vec<_>>();
+        let positions = points
+            .iter()
+            .map(|point| point.position)
+            .collect::<Vec<_>>();
+        let Some(plane) = self.ransac.fit(&positions, None) else {
+            return 0;
+        };
+
+        let before = self.samples.len();
+        for point in points {
+            if plane.distance(&point.position).abs() > self.ransac.threshold {
+                continue;
+            }
+            let Some(beam) = (point.position - origin).try_normalize(f32::EPSILON) else {
+                continue;
+            };
+            let cos_incidence = plane.normal.dot(&beam).abs();
+            if cos_incidence >= MIN_CALIBRATION_COS_INCIDENCE {
+                self.samples.push((
+                    point.range,
+                    f32::from(point.intensity) / (self.reflectivity * cos_incidence),
+                ));
+            }
+        }
+        self.samples.len() - before
+    }
+
+    /// The model with the median gain of every bin with enough samples, placed at the bin's mean
+    /// range, and incidence correction enabled; `None` if no bin has enough samples.
+    #[must_use]
+    pub fn fit(&self) -> Option<ReflectivityModel> {
+        #[expect(
+            clippy::cast_possible_truncation,
+            reason = "ranges are far below 2^31 bins"
+        )]
+        let mut samples = self
+            .samples
+            .iter()
+            .map(|&(range, gain)| ((range / self.bin_width).floor() as i32, range, gain))
+            .collect::<Vec<_>>();
+        samples.sort_by(|first, second| first.1.total_cmp(&second.1));
+
+        let mut model = ReflectivityModel {
+            ranges: Vec::new(),
+            gains: Vec::new(),
+            incidence_correction: true,
+            ..ReflectivityModel::default()
+        };
+        for bin in samples.chunk_by(|first, second| first.0 == second.0) {
+            if bin.len() < self.min_samples.max(1) {
+                continue;
+            }
+            #[expect(clippy::cast_precision_loss, reason = "only an average is needed")]
+            let range = bin.iter().map(|(_, range, _)| range).sum::<f32>() / bin.len() as f32;
+            let mut gains = bin.iter().map(|(_, _, gain)| *gain).collect::<Vec<_>>();
+            gains.sort_by(f32::total_cmp);
+            let Some(&median) = gains.get(gains.len() / 2) else {
+                unreachable!("bins should not be empty");
+            };
+            model.ranges.push(range);
+            model.gains.push(median);
+        }
+        (!model.ranges.is_empty()).then_some(model)
+    }
+}
@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use l2_protocol::LidarPointData;
+
+use crate::Point;
+
+/// The points of consecutive point data packets covering one scan period
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Scan {
+    /// timestamp of the first packet
+    pub stamp: Duration,
+    pub points: Vec<Point>,
+    /// the packets the points came from, in the order of `points`
+    pub packets: Vec<ScanPacket>,
+}
+
+/// A packet that contributed to a [`Scan`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanPacket {
+    pub seq: u32,
+    pub stamp: Duration,
+    /// index of the first point of the next packet in [`Scan::points`]
+    pub end: usize,
+}
+
+impl Scan {
+    /// The points of each packet along with the packet
+    pub fn packets(&self) -> impl Iterator<Item = (&ScanPacket, &[Point])> {
+        self.packets.iter().scan(0, |begin, packet| {
+            let points = self.points.get(*begin..packet.end).unwrap_or_default();
+            *begin = packet.end;
+            Some((packet, points))
+        })
+    }
+}
+
+/// Assembles the points of consecutive packets into scans.
+///
+/// A scan is complete once a packet arrives a scan period after its first packet. The period is
+/// taken from the packets unless it is set with [`ScanAssembler::with_period`]. Single packets only
+/// cover a thin slice of the surroundings, so registration, plane fits and normal estimation need
+/// whole scans.
+#[derive(Debug, Clone, Default)]
+pub struct ScanAssembler {
+    period: Option<Duration>,
+    /// `None` before the first packet of the next scan
+    scan: Option<Scan>,
+}
+
+impl ScanAssembler {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uses `period` instead of the scan period reported by the lidar.
+    #[must_use]
+    pub fn with_period(self, period: Duration) -> Self {
+        Self {
+            period: Some(period),
+            ..self
+        }
+    }
+
+    /// Adds the points computed from a packet and returns the scan completed before it, if any.
+    pub fn push(&mut self, data: &LidarPointData, points: &[Point]) -> Option<Scan> {
+        let stamp = data.stamp();
+        let period = self
+            .period
+            .unwrap_or_else(|| Duration::try_from_secs_f32(data.scan_period()).unwrap_or_default());
+        let completed = self
+            .scan
+            .take_if(|scan| stamp.saturating_sub(scan.stamp) >= period);
+
+        let scan = self.scan.get_or_insert_with(|| Scan {
+            stamp,
+            ..Scan::default()
+        });
+        scan.points.extend_from_slice(points);
+        scan.packets.push(ScanPacket {
+            seq: data.seq(),
+            stamp,
+            end: scan.points.len(),
+        });
+        completed
+    }
+
+    /// Returns the scan in progress, e.g. at the end of a recording; `None` if no packet has been
+    /// added since the last scan.
+    pub fn finish(&mut self) -> Option<Scan> {
+        self.scan.take()
+    }
+}
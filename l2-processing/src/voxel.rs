@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use nalgebra::Vector3;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Point,
+    grid::{CellKey, cell_key},
+};
+
+/// Which position represents the points of a voxel
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VoxelMode {
+    /// mean position of all points; smoother but creates new positions
+    #[default]
+    Centroid,
+    /// position of the first point; keeps measured positions and is slightly faster
+    First,
+}
+
+/// Reduces a cloud to one point per cube of a regular grid.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VoxelGrid {
+    /// edge length of the voxels [m]
+    pub leaf_size: f32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mode: VoxelMode,
+}
+
+/// Accumulates the points falling into one voxel
+#[derive(Debug, Clone)]
+pub(crate) struct Voxel {
+    first: Point,
+    sum: Vector3<f32>,
+    intensity_sum: u32,
+    range_sum: f32,
+    count: u32,
+    /// `count` as float to avoid casting in the hot loop
+    weight: f32,
+}
+
+impl Voxel {
+    pub(crate) fn new(first: Point) -> Self {
+        Self {
+            first,
+            sum: Vector3::zeros(),
+            intensity_sum: 0,
+            range_sum: 0.0,
+            count: 0,
+            weight: 0.0,
+        }
+    }
+
+    pub(crate) fn add(&mut self, point: &Point) {
+        self.sum += point.position.coords;
+        self.intensity_sum += u32::from(point.intensity);
+        self.range_sum += point.range;
+        self.count += 1;
+        self.weight += 1.0;
+    }
+
+    /// The point representing the voxel; intensity and range are averaged, the time is taken from
+    /// its first point.
+    pub(crate) fn point(&self, mode: VoxelMode) -> Point {
+        Point {
+            position: match mode {
+                VoxelMode::Centroid => (self.sum / self.weight).into(),
+                VoxelMode::First => self.first.position,
+            },
+            range: self.range_sum / self.weight,
+            intensity: u8::try_from(self.intensity_sum / self.count).unwrap_or(u8::MAX),
+            ..self.first
+        }
+    }
+}
+
+impl VoxelGrid {
+    #[must_use]
+    pub fn new(leaf_size: f32) -> Self {
+        Self {
+            leaf_size,
+            mode: VoxelMode::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_mode(self, mode: VoxelMode) -> Self {
+        Self { mode, ..self }
+    }
+
+    /// Returns one point per occupied voxel in the order the voxels have been first hit.
+    ///
+    /// Intensity and range are averaged over the voxel, the time is taken from its first point.
+    /// Points without finite coordinates are skipped.
+    #[must_use]
+    pub fn downsample(&self, points: &[Point]) -> Vec<Point> {
+        let mut indices = HashMap::<CellKey, usize>::new();
+        let mut voxels = Vec::<Voxel>::new();
+
+        for point in points {
+            if !point.position.iter().all(|coord| coord.is_finite()) {
+                continue;
+            }
+            let key = cell_key(&point.position, self.leaf_size);
+            let index = *indices.entry(key).or_insert_with(|| {
+                voxels.push(Voxel::new(*point));
+                voxels.len() - 1
+            });
+            let Some(voxel) = voxels.get_mut(index) else {
+                unreachable!("voxel indices should be valid");
+            };
+            voxel.add(point);
+        }
+
+        voxels
+            .into_iter()
+            .map(|voxel| voxel.point(self.mode))
+            .collect()
+    }
+}
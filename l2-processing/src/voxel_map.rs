@@ -0,0 +1,388 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use nalgebra::{Isometry3, Point3, Vector3};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Point,
+    grid::{CellKey, cell_key},
+};
+
+/// Parameters of a [`VoxelMap`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VoxelMapConfig {
+    /// edge length of the voxels [m]
+    pub resolution: f32,
+    /// log-odds added to a voxel a beam ends in
+    pub hit: f32,
+    /// log-odds added to a voxel a beam passes through; negative
+    pub miss: f32,
+    /// lower bound of the log-odds, limiting how certain a voxel can be free
+    pub min_log_odds: f32,
+    /// upper bound of the log-odds, limiting how certain a voxel can be occupied
+    pub max_log_odds: f32,
+    /// longer beams are shortened and don't mark their end as occupied [m]
+    pub max_range: f32,
+}
+
+impl Default for VoxelMapConfig {
+    fn default() -> Self {
+        Self {
+            resolution: 0.05,
+            hit: 0.85,
+            miss: -0.4,
+            min_log_odds: -2.0,
+            max_log_odds: 3.5,
+            max_range: 30.0,
+        }
+    }
+}
+
+/// Start of the binary format written by [`VoxelMap::write`]
+const MAGIC: &[u8; 8] = b"L2VOXMAP";
+const FORMAT_VERSION: u32 = 1;
+
+/// A sparse 3D map of the probability of voxels being occupied, stored as log-odds.
+///
+/// Only voxels observed at least once are stored. Every voxel is updated at most once per scan;
+/// a voxel both traversed and hit counts as hit.
+#[derive(Debug, Clone)]
+pub struct VoxelMap {
+    config: VoxelMapConfig,
+    voxels: HashMap<CellKey, f32>,
+}
+
+impl VoxelMap {
+    #[must_use]
+    pub fn new(config: VoxelMapConfig) -> Self {
+        Self {
+            config,
+            voxels: HashMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn config(&self) -> &VoxelMapConfig {
+        &self.config
+    }
+
+    /// number of observed voxels
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.voxels.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.voxels.is_empty()
+    }
+
+    /// Adds a scan taken by a lidar at `origin`, both in the frame of the points, which is placed
+    /// in the map by `to_map`.
+    ///
+    /// `origin` is e.g. `Frames::lidar_to_base().translation` for points in the base frame.
+    /// Points without a return, e.g. placed at the maximum range by
+    /// [`NoReturnPolicy::MaxRange`], only clear the voxels along their beam.
+    ///
+    /// [`NoReturnPolicy::MaxRange`]: crate::NoReturnPolicy::MaxRange
+    pub fn insert_scan(&mut self, points: &[Point], origin: &Point3<f32>, to_map: &Isometry3<f32>) {
+        let start = to_map * origin;
+        let mut free = HashSet::new();
+        let mut occupied = HashSet::new();
+
+        for point in points {
+            if !point.position.iter().all(|coord| coord.is_finite()) {
+                continue;
+            }
+            let mut end = to_map * point.position;
+            let mut hit = point.returned;
+            let length = (end - start).norm();
+            if length > self.config.max_range {
+                end = start + (end - start) * (self.config.max_range / length);
+                hit = false;
+            }
+
+            let end_key = cell_key(&end, self.config.resolution);
+            traverse(&start, &end, self.config.resolution, |key| {
+                if key != end_key {
+                    free.insert(key);
+                }
+                true
+            });
+            if hit {
+                occupied.insert(end_key);
+            } else {
+                free.insert(end_key);
+            }
+        }
+
+        let VoxelMapConfig {
+            hit,
+            miss,
+            min_log_odds,
+            max_log_odds,
+            ..
+        } = self.config;
+        for (key, change) in free
+            .difference(&occupied)
+            .map(|key| (key, miss))
+            .chain(occupied.iter().map(|key| (key, hit)))
+        {
+            let log_odds = self.voxels.entry(*key).or_default();
+            *log_odds = (*log_odds + change).clamp(min_log_odds, max_log_odds);
+        }
+    }
+
+    /// Log-odds of the voxel containing `position`; `None` if it hasn't been observed
+    #[must_use]
+    pub fn log_odds(&self, position: &Point3<f32>) -> Option<f32> {
+        self.voxels
+            .get(&cell_key(position, self.config.resolution))
+            .copied()
+    }
+
+    /// Probability of the voxel containing `position` being occupied; `None` if it hasn't been
+    /// observed
+    #[must_use]
+    pub fn probability(&self, position: &Point3<f32>) -> Option<f32> {
+        self.log_odds(position)
+            .map(|log_odds| 1.0 - 1.0 / (1.0 + log_odds.exp()))
+    }
+
+    /// Whether the voxel containing `position` is more likely occupied than free; `None` if it
+    /// hasn't been observed
+    #[must_use]
+    pub fn is_occupied(&self, position: &Point3<f32>) -> Option<bool> {
+        self.log_odds(position).map(|log_odds| log_odds > 0.0)
+    }
+
+    /// Center of the first occupied voxel along a ray, up to `max_range` [m].
+    ///
+    /// Unobserved voxels are passed through, as are free ones.
+    #[must_use]
+    pub fn cast_ray(
+        &self,
+        origin: &Point3<f32>,
+        direction: &Vector3<f32>,
+        max_range: f32,
+    ) -> Option<Point3<f32>> {
+        let direction = direction.try_normalize(f32::EPSILON)?;
+        let mut hit = None;
+        traverse(
+            origin,
+            &(origin + direction * max_range),
+            self.config.resolution,
+            |key| {
+                if self
+                    .voxels
+                    .get(&key)
+                    .is_some_and(|&log_odds| log_odds > 0.0)
+                {
+                    hit = Some(key);
+                }
+                hit.is_none()
+            },
+        );
+        hit.map(|key| self.center(key))
+    }
+
+    /// Centers of all voxels more likely occupied than free
+    pub fn occupied(&self) -> impl Iterator<Item = Point3<f32>> {
+        self.voxels
+            .iter()
+            .filter(|(_, log_odds)| **log_odds > 0.0)
+            .map(|(key, _)| self.center(*key))
+    }
+
+    #[expect(
+        clippy::cast_precision_loss,
+        reason = "voxel indices stay far below 2^24"
+    )]
+    fn center(&self, key: CellKey) -> Point3<f32> {
+        Point3::from(key.map(|index| (index as f32 + 0.5) * self.config.resolution))
+    }
+
+    /// Writes the map to `path` in the format of [`write`](Self::write).
+    ///
+    /// # Errors
+    ///
+    /// Errors if the file can't be written.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Reads a map saved with [`save`](Self::save).
+    ///
+    /// # Errors
+    ///
+    /// Errors if the file can't be read or isn't a voxel map.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Writes the map in a compact binary format: a header with the configuration followed by
+    /// the index and log-odds of every observed voxel, all little endian.
+    ///
+    /// # Errors
+    ///
+    /// Errors if writing fails.
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        let VoxelMapConfig {
+            resolution,
+            hit,
+            miss,
+            min_log_odds,
+            max_log_odds,
+            max_range,
+        } = self.config;
+        for value in [resolution, hit, miss, min_log_odds, max_log_odds, max_range] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+
+        // sorted to produce identical files for identical maps
+        let mut voxels = self.voxels.iter().collect::<Vec<_>>();
+        voxels.sort_unstable_by_key(|(key, _)| **key);
+        writer.write_all(
+            &u64::try_from(voxels.len())
+                .unwrap_or(u64::MAX)
+                .to_le_bytes(),
+        )?;
+        for (key, log_odds) in voxels {
+            for index in key {
+                writer.write_all(&index.to_le_bytes())?;
+            }
+            writer.write_all(&log_odds.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Reads a map written by [`write`](Self::write).
+    ///
+    /// # Errors
+    ///
+    /// Errors if reading fails or the data isn't a voxel map of a supported version.
+    pub fn read(mut reader: impl Read) -> io::Result<Self> {
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a voxel map"));
+        }
+        let version = u32::from_le_bytes(read_array(&mut reader)?);
+        if version != FORMAT_VERSION {
+            return Err(invalid_data(format!(
+                "unsupported voxel map version {version}"
+            )));
+        }
+
+        let mut read_f32 = || read_array(&mut reader).map(f32::from_le_bytes);
+        let config = VoxelMapConfig {
+            resolution: read_f32()?,
+            hit: read_f32()?,
+            miss: read_f32()?,
+            min_log_odds: read_f32()?,
+            max_log_odds: read_f32()?,
+            max_range: read_f32()?,
+        };
+        if !(config.resolution.is_finite() && config.resolution > 0.0) {
+            return Err(invalid_data("invalid resolution"));
+        }
+
+        let count = u64::from_le_bytes(read_array(&mut reader)?);
+        let mut voxels = HashMap::new();
+        for _ in 0..count {
+            let key = [
+                i32::from_le_bytes(read_array(&mut reader)?),
+                i32::from_le_bytes(read_array(&mut reader)?),
+                i32::from_le_bytes(read_array(&mut reader)?),
+            ];
+            voxels.insert(key, f32::from_le_bytes(read_array(&mut reader)?));
+        }
+        Ok(Self { config, voxels })
+    }
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Visits the voxels a segment passes through in order, starting with the one containing `start`
+/// (Amanatides & Woo). Stops early once `visit` returns `false`.
+fn traverse(
+    start: &Point3<f32>,
+    end: &Point3<f32>,
+    resolution: f32,
+    mut visit: impl FnMut(CellKey) -> bool,
+) {
+    let mut key = cell_key(start, resolution);
+    let end_key = cell_key(end, resolution);
+    let length = (end - start).norm();
+    let direction = (end - start) / length;
+
+    // distance along the segment to the next voxel boundary and between boundaries, per axis
+    let mut next = [f32::INFINITY; 3];
+    let mut delta = [f32::INFINITY; 3];
+    let mut step = [0; 3];
+    for axis in 0..3 {
+        let (Some(next), Some(delta), Some(step), Some(&direction), Some(&start)) = (
+            next.get_mut(axis),
+            delta.get_mut(axis),
+            step.get_mut(axis),
+            direction.get(axis),
+            start.coords.get(axis),
+        ) else {
+            unreachable!("axes should be within the vectors");
+        };
+        if direction.is_finite() && direction != 0.0 {
+            let fraction = (start / resolution).fract().rem_euclid(1.0);
+            let remaining = if direction > 0.0 {
+                1.0 - fraction
+            } else {
+                fraction
+            };
+            *delta = resolution / direction.abs();
+            *next = remaining * *delta;
+            *step = if direction > 0.0 { 1 } else { -1 };
+        }
+    }
+
+    while visit(key) && key != end_key {
+        let Some((axis, distance)) = next
+            .iter()
+            .copied()
+            .enumerate()
+            .min_by(|first, second| first.1.total_cmp(&second.1))
+        else {
+            unreachable!("there should be three axes");
+        };
+        // guards against rounding errors missing the end voxel
+        if distance > length {
+            break;
+        }
+        if let (Some(index), Some(next), Some(&delta), Some(&step)) = (
+            key.get_mut(axis),
+            next.get_mut(axis),
+            delta.get(axis),
+            step.get(axis),
+        ) {
+            *index = index.saturating_add(step);
+            *next += delta;
+        }
+    }
+}
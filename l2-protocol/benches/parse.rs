@@ -0,0 +1,83 @@
+#![allow(unused_crate_dependencies, reason = "used in library")]
+#![expect(clippy::unwrap_used, reason = "ok for benchmarks")]
+
+//! Compares the owned [`Packet`] parser against the borrowed [`PacketRef`] view.
+
+use std::hint::black_box;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use l2_protocol::{Packet, PacketRef};
+
+const POINT_DATA_TYPE: u32 = 102;
+const POINT_DATA_LEN: usize = 1020;
+const POINT_NUM: u16 = 300;
+
+/// Builds a point data frame with synthetic ranges and intensities
+fn point_data_frame() -> Vec<u8> {
+    let mut payload = Vec::with_capacity(POINT_DATA_LEN);
+    // packet info, inside state, calibration and line info
+    payload.resize(116, 0);
+    payload.extend_from_slice(&u32::from(POINT_NUM).to_le_bytes());
+    for index in 0..POINT_NUM {
+        payload.extend_from_slice(&(index * 10).to_le_bytes());
+    }
+    for index in 0..POINT_NUM {
+        payload.push(u8::try_from(index % 256).unwrap());
+    }
+    assert_eq!(payload.len(), POINT_DATA_LEN, "payload size mismatch");
+
+    let crc = crc32fast::hash(&payload);
+    let packet_size = u32::try_from(12 + payload.len() + 12).unwrap();
+
+    let mut frame = vec![0x55, 0xAA, 0x05, 0x0A];
+    frame.extend_from_slice(&POINT_DATA_TYPE.to_le_bytes());
+    frame.extend_from_slice(&packet_size.to_le_bytes());
+    frame.extend_from_slice(&payload);
+    frame.extend_from_slice(&crc.to_le_bytes());
+    frame.extend_from_slice(&[0; 6]);
+    frame.extend_from_slice(&[0x00, 0xFF]);
+    frame
+}
+
+fn sum_owned(frame: &[u8]) -> u32 {
+    let (packet, _) = Packet::parse(frame).unwrap();
+    let Packet::LidarPointData(data) = packet else {
+        unreachable!("expected point data");
+    };
+    let ranges: u32 = data.ranges().iter().copied().map(u32::from).sum();
+    let intensities: u32 = data.intensities().iter().copied().map(u32::from).sum();
+    ranges + intensities
+}
+
+fn sum_borrowed(frame: &[u8]) -> u32 {
+    let (packet, _) = PacketRef::parse(frame).unwrap();
+    let PacketRef::LidarPointData(data) = packet else {
+        unreachable!("expected point data");
+    };
+    let ranges: u32 = data.ranges().map(u32::from).sum();
+    let intensities: u32 = data.intensities().iter().copied().map(u32::from).sum();
+    ranges + intensities
+}
+
+fn point_data(criterion: &mut Criterion) {
+    let frame = point_data_frame();
+    assert_eq!(sum_owned(&frame), sum_borrowed(&frame), "parsers disagree");
+
+    let mut group = criterion.benchmark_group("point_data");
+    group.bench_function("owned/parse", |bencher| {
+        bencher.iter(|| Packet::parse(black_box(&frame)).unwrap());
+    });
+    group.bench_function("borrowed/parse", |bencher| {
+        bencher.iter(|| PacketRef::parse(black_box(&frame)).unwrap());
+    });
+    group.bench_function("owned/sum", |bencher| {
+        bencher.iter(|| sum_owned(black_box(&frame)));
+    });
+    group.bench_function("borrowed/sum", |bencher| {
+        bencher.iter(|| sum_borrowed(black_box(&frame)));
+    });
+    group.finish();
+}
+
+criterion_group!(benches, point_data);
+criterion_main!(benches);
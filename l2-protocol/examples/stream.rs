@@ -0,0 +1,45 @@
+#![allow(unused_crate_dependencies, reason = "used in library")]
+
+//! Starts the lidar and prints all packets it sends.
+//!
+//! Usage: `cargo run --example stream --features serial -- [/dev/ttyACM0 | 192.168.1.62:6101]`
+
+use std::{env, net::SocketAddr, pin::pin};
+
+use anyhow::Result;
+use futures::{Sink, SinkExt, Stream, StreamExt};
+use l2_protocol::{
+    DEFAULT_BAUD_RATE, DEFAULT_USER_ADDR, Packet, SerialSource, StandbyType, UdpSource, UserCmd,
+};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let target = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "/dev/ttyACM0".to_owned());
+
+    if let Ok(lidar_addr) = target.parse::<SocketAddr>() {
+        run(UdpSource::bind(DEFAULT_USER_ADDR, lidar_addr).await?).await
+    } else {
+        run(SerialSource::open(&target, DEFAULT_BAUD_RATE)?).await
+    }
+}
+
+async fn run(
+    source: impl Stream<Item = Result<Packet>> + Sink<Packet, Error = anyhow::Error>,
+) -> Result<()> {
+    let mut source = pin!(source);
+
+    source
+        .send(Packet::LidarUserCmd(UserCmd::StandbyType(
+            StandbyType::Start,
+        )))
+        .await?;
+
+    while let Some(packet) = source.next().await {
+        println!("{}", packet?);
+    }
+
+    println!("LIDAR disconnected");
+    Ok(())
+}
@@ -1,11 +1,28 @@
-use std::fmt::{self, Display};
+use core::fmt::{self, Display};
 
-use anyhow::{Result, bail};
-use bytes::Buf;
+#[cfg(feature = "std")]
+use bytes::BufMut;
 
-use crate::{command::Command, frame::PacketType, user_ctrl_cmd::UserCmd};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "std")]
+use crate::Packet;
+use crate::{
+    buf::Buf,
+    command::{Command, LidarCommand},
+    error::{ProtocolError, Result},
+    frame::PacketType,
+    user_ctrl_cmd::{LidarUserCtrlCmd, UserCmd},
+};
+
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
 #[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AckStatus {
     Success = Self::SUCCESS,
     CrcError = Self::CRC_ERROR,
@@ -24,7 +41,7 @@ impl AckStatus {
 }
 
 impl TryFrom<u32> for AckStatus {
-    type Error = anyhow::Error;
+    type Error = ProtocolError;
 
     fn try_from(value: u32) -> Result<Self, Self::Error> {
         match value {
@@ -33,7 +50,19 @@ impl TryFrom<u32> for AckStatus {
             Self::HEADER_ERROR => Ok(Self::HeaderError),
             Self::BLOCK_ERROR => Ok(Self::BlockError),
             Self::WAIT_ERROR => Ok(Self::WaitError),
-            unknown => bail!("unknown ack status: {unknown}"),
+            unknown => Err(ProtocolError::UnknownAckStatus(unknown)),
+        }
+    }
+}
+
+impl From<&AckStatus> for u32 {
+    fn from(value: &AckStatus) -> Self {
+        match value {
+            AckStatus::Success => AckStatus::SUCCESS,
+            AckStatus::CrcError => AckStatus::CRC_ERROR,
+            AckStatus::HeaderError => AckStatus::HEADER_ERROR,
+            AckStatus::BlockError => AckStatus::BLOCK_ERROR,
+            AckStatus::WaitError => AckStatus::WAIT_ERROR,
         }
     }
 }
@@ -51,6 +80,12 @@ impl Display for AckStatus {
     }
 }
 
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Ack {
     UserCmd {
         cmd: UserCmd,
@@ -67,10 +102,44 @@ pub enum Ack {
         cmd_value: u32,
         status: AckStatus,
     },
+    /// acknowledges any other packet, e.g. network configuration or parameters
+    Other {
+        packet_type: PacketType,
+        /// likely always 0
+        cmd_type: u32,
+        /// likely always 0
+        cmd_value: u32,
+        status: AckStatus,
+    },
+}
+
+impl Ack {
+    #[must_use]
+    pub fn status(&self) -> AckStatus {
+        match self {
+            Ack::UserCmd { status, .. }
+            | Ack::Command { status, .. }
+            | Ack::WorkMode { status, .. }
+            | Ack::Other { status, .. } => *status,
+        }
+    }
+
+    /// Whether this is the response to `packet`
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn acknowledges(&self, packet: &Packet) -> bool {
+        match (self, packet) {
+            (Ack::UserCmd { cmd, .. }, Packet::LidarUserCmd(sent)) => cmd == sent,
+            (Ack::Command { cmd, .. }, Packet::LidarCommand(sent)) => cmd == sent,
+            (Ack::WorkMode { .. }, Packet::LidarWorkMode(_)) => true,
+            (Ack::Other { packet_type, .. }, sent) => *packet_type == sent.packet_type(),
+            _ => false,
+        }
+    }
 }
 
 impl TryFrom<LidarAckData> for Ack {
-    type Error = anyhow::Error;
+    type Error = ProtocolError;
 
     fn try_from(value: LidarAckData) -> Result<Self, Self::Error> {
         let LidarAckData {
@@ -96,7 +165,64 @@ impl TryFrom<LidarAckData> for Ack {
                 cmd_value,
                 status,
             }),
-            unknown => bail!("ack for unknown packet type: {unknown}"),
+            other => Ok(Self::Other {
+                packet_type: other.try_into()?,
+                cmd_type,
+                cmd_value,
+                status,
+            }),
+        }
+    }
+}
+
+impl From<&Ack> for LidarAckData {
+    fn from(ack: &Ack) -> Self {
+        match ack {
+            Ack::UserCmd { cmd, status } => {
+                let LidarUserCtrlCmd {
+                    cmd_type,
+                    cmd_value,
+                } = cmd.into();
+                Self {
+                    packet_type: PacketType::LIDAR_USER_CMD,
+                    cmd_type,
+                    cmd_value,
+                    status: status.into(),
+                }
+            }
+            Ack::Command { cmd, status } => {
+                let LidarCommand {
+                    cmd_type,
+                    cmd_value,
+                } = cmd.into();
+                Self {
+                    packet_type: PacketType::LIDAR_COMMAND,
+                    cmd_type,
+                    cmd_value,
+                    status: status.into(),
+                }
+            }
+            Ack::WorkMode {
+                cmd_type,
+                cmd_value,
+                status,
+            } => Self {
+                packet_type: PacketType::LIDAR_WORK_MODE,
+                cmd_type: *cmd_type,
+                cmd_value: *cmd_value,
+                status: status.into(),
+            },
+            Ack::Other {
+                packet_type,
+                cmd_type,
+                cmd_value,
+                status,
+            } => Self {
+                packet_type: *packet_type as u32,
+                cmd_type: *cmd_type,
+                cmd_value: *cmd_value,
+                status: status.into(),
+            },
         }
     }
 }
@@ -114,6 +240,15 @@ impl Display for Ack {
                 f,
                 "Ack::WorkMode(type:{cmd_type}, value:{cmd_value}, {status})"
             ),
+            Ack::Other {
+                packet_type,
+                cmd_type,
+                cmd_value,
+                status,
+            } => write!(
+                f,
+                "Ack::Other({packet_type:?}, type:{cmd_type}, value:{cmd_value}, {status})"
+            ),
         }
     }
 }
@@ -140,11 +275,10 @@ impl LidarAckData {
 
     pub(crate) fn parse(bytes: &[u8]) -> Result<(Self, &[u8])> {
         let Some((mut bytes, remainder)) = bytes.split_at_checked(Self::LEN) else {
-            bail!(
-                "expected a minimum of {} bytes but got {}",
-                Self::LEN,
-                bytes.len()
-            );
+            return Err(ProtocolError::Truncated {
+                expected: Self::LEN,
+                actual: bytes.len(),
+            });
         };
 
         let packet_type = bytes.get_u32_le();
@@ -166,6 +300,14 @@ impl LidarAckData {
             remainder,
         ))
     }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn write(&self, buf: &mut impl BufMut) {
+        buf.put_u32_le(self.packet_type);
+        buf.put_u32_le(self.cmd_type);
+        buf.put_u32_le(self.cmd_value);
+        buf.put_u32_le(self.status);
+    }
 }
 
 impl Display for LidarAckData {
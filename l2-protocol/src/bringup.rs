@@ -0,0 +1,364 @@
+use std::{future::poll_fn, pin::Pin, time::Duration};
+
+use futures_core::Stream;
+use futures_sink::Sink;
+use thiserror::Error;
+use tokio::time::{self, Instant};
+
+use crate::{
+    AckStatus, Capabilities, CapabilityTable, Packet, PacketType, StandbyType, UserCmd, Version,
+    VersionNumber, WorkMode,
+};
+
+/// Timeouts and thresholds of [`bring_up`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BringUpConfig {
+    /// how long to wait for the lidar to answer a request
+    pub response_timeout: Duration,
+    /// how long to wait for data after starting, including the spin-up of the motors
+    pub data_timeout: Duration,
+    /// number of point data packets, and IMU packets unless disabled, that need to arrive before
+    /// the data is considered flowing
+    pub min_packets: u32,
+}
+
+impl Default for BringUpConfig {
+    fn default() -> Self {
+        Self {
+            response_timeout: Duration::from_secs(2),
+            data_timeout: Duration::from_secs(10),
+            min_packets: 10,
+        }
+    }
+}
+
+/// A step of [`bring_up`] that has succeeded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BringUpStep {
+    /// the lidar reported its firmware, whose capabilities are used from now on
+    VersionRead {
+        version: Version,
+        capabilities: Capabilities,
+        /// whether the capability table has been verified against the firmware
+        tested: bool,
+    },
+    /// the lidar already was in the desired work mode
+    WorkModeUnchanged(WorkMode),
+    /// the work mode has been changed and read back
+    WorkModeApplied {
+        previous: WorkMode,
+        current: WorkMode,
+    },
+    /// the lidar acknowledged the start command
+    Started,
+    /// enough data has arrived after starting
+    DataFlowing {
+        /// point data packets, 2D or 3D
+        points: u32,
+        imu: u32,
+        /// time since the start command had been acknowledged
+        elapsed: Duration,
+    },
+}
+
+/// Why [`bring_up`] failed
+#[derive(Debug, Error)]
+pub enum BringUpError {
+    #[error("failed to exchange packets with the lidar")]
+    Transport(#[source] anyhow::Error),
+    #[error("the connection has been closed")]
+    Closed,
+    #[error("no answer to the version request within {timeout:?}")]
+    NoVersion { timeout: Duration },
+    #[error("no answer to the work mode request within {timeout:?}")]
+    NoWorkMode { timeout: Duration },
+    #[error("firmware {software} doesn't support 2D measurement")]
+    Unsupported2D { software: VersionNumber },
+    #[error("the capabilities name {packet_type:?}, which can't be used for this request")]
+    UnusablePacketType { packet_type: PacketType },
+    #[error("the lidar rejected {packet_type:?}: {status}")]
+    Rejected {
+        packet_type: PacketType,
+        status: AckStatus,
+    },
+    #[error("the lidar didn't acknowledge {packet_type:?} within {timeout:?}")]
+    NotAcknowledged {
+        packet_type: PacketType,
+        timeout: Duration,
+    },
+    #[error(
+        "the lidar didn't report its work mode within {timeout:?} after changing it; changing the \
+         interface moves it to the other one"
+    )]
+    WorkModeLost { timeout: Duration },
+    #[error(
+        "the lidar acknowledged the work mode {desired} but reports {applied}; the change may \
+         only take effect after a reset"
+    )]
+    WorkModeNotApplied {
+        desired: WorkMode,
+        applied: WorkMode,
+    },
+    #[error(
+        "no data within {timeout:?} after starting; the motors may still be spinning up or the \
+         data is sent elsewhere"
+    )]
+    NoData { timeout: Duration },
+    #[error(
+        "only {points} point data packets within {timeout:?} after starting, along with {imu} IMU \
+         packets; the motors may not have reached their speed"
+    )]
+    TooFewPoints {
+        points: u32,
+        imu: u32,
+        timeout: Duration,
+    },
+    #[error(
+        "point data flows but only {imu} IMU packets arrived within {timeout:?}; the IMU may be \
+         faulty"
+    )]
+    TooFewImu {
+        points: u32,
+        imu: u32,
+        timeout: Duration,
+    },
+}
+
+/// Brings a lidar up until it streams data.
+///
+/// Requests the version to look up the capabilities of the firmware in `table`, reads the work
+/// mode and changes it to what `desired` returns for it, sends the start command and waits until
+/// point data, and IMU data unless disabled, arrives. Each step that succeeds is passed to
+/// `on_step`. A fixed work mode can be requested with `|_| mode`.
+///
+/// # Errors
+///
+/// A [`BringUpError`] diagnosing the first step that failed.
+pub async fn bring_up<T>(
+    transport: &mut T,
+    table: &CapabilityTable,
+    desired: impl FnOnce(WorkMode) -> WorkMode,
+    config: &BringUpConfig,
+    mut on_step: impl FnMut(&BringUpStep),
+) -> Result<(), BringUpError>
+where
+    T: Stream<Item = anyhow::Result<Packet>> + Sink<Packet, Error = anyhow::Error> + Unpin,
+{
+    let mut link = Link {
+        transport,
+        timeout: config.response_timeout,
+    };
+
+    let version = link
+        .query(
+            Packet::LidarUserCmd(UserCmd::VersionGet(0)),
+            |packet| match packet {
+                Packet::LidarVersion(version) => Some(version),
+                _ => None,
+            },
+        )
+        .await?
+        .ok_or(BringUpError::NoVersion {
+            timeout: config.response_timeout,
+        })?;
+    let software = version.software();
+    let capabilities = table.capabilities(software);
+    on_step(&BringUpStep::VersionRead {
+        version,
+        capabilities,
+        tested: table.is_tested(software),
+    });
+
+    let current = link
+        .read_work_mode()
+        .await?
+        .ok_or(BringUpError::NoWorkMode {
+            timeout: config.response_timeout,
+        })?;
+    let mode = desired(current);
+    if mode.measure_2d && !capabilities.measure_2d {
+        return Err(BringUpError::Unsupported2D { software });
+    }
+    if mode == current {
+        on_step(&BringUpStep::WorkModeUnchanged(current));
+    } else {
+        let packet = capabilities
+            .work_mode(mode)
+            .ok_or(BringUpError::UnusablePacketType {
+                packet_type: capabilities.work_mode_packet,
+            })?;
+        link.request(packet).await?;
+        let applied = link
+            .read_work_mode()
+            .await?
+            .ok_or(BringUpError::WorkModeLost {
+                timeout: config.response_timeout,
+            })?;
+        if applied != mode {
+            return Err(BringUpError::WorkModeNotApplied {
+                desired: mode,
+                applied,
+            });
+        }
+        on_step(&BringUpStep::WorkModeApplied {
+            previous: current,
+            current: applied,
+        });
+    }
+
+    let start = capabilities
+        .command(UserCmd::StandbyType(StandbyType::Start))
+        .ok_or(BringUpError::UnusablePacketType {
+            packet_type: capabilities.command_packet,
+        })?;
+    link.request(start).await?;
+    on_step(&BringUpStep::Started);
+
+    let flowing = await_data(&mut link, config, &mode).await?;
+    on_step(&flowing);
+    Ok(())
+}
+
+/// Waits until enough point data, and IMU data unless disabled, has arrived.
+async fn await_data<T>(
+    link: &mut Link<'_, T>,
+    config: &BringUpConfig,
+    mode: &WorkMode,
+) -> Result<BringUpStep, BringUpError>
+where
+    T: Stream<Item = anyhow::Result<Packet>> + Sink<Packet, Error = anyhow::Error> + Unpin,
+{
+    let started = Instant::now();
+    let mut points = 0;
+    let mut imu = 0;
+    let flowing = link
+        .receive(config.data_timeout, |packet| {
+            match packet {
+                Packet::LidarPointData(_) | Packet::Lidar2DPointData(_) => points += 1,
+                Packet::LidarImuData(_) => imu += 1,
+                _ => {}
+            }
+            Ok(
+                (points >= config.min_packets && (mode.disable_imu || imu >= config.min_packets))
+                    .then_some(()),
+            )
+        })
+        .await?;
+
+    let timeout = config.data_timeout;
+    match flowing {
+        Some(()) => Ok(BringUpStep::DataFlowing {
+            points,
+            imu,
+            elapsed: started.elapsed(),
+        }),
+        None if points == 0 && imu == 0 => Err(BringUpError::NoData { timeout }),
+        None if points < config.min_packets => Err(BringUpError::TooFewPoints {
+            points,
+            imu,
+            timeout,
+        }),
+        None => Err(BringUpError::TooFewImu {
+            points,
+            imu,
+            timeout,
+        }),
+    }
+}
+
+/// Request/response access to the transport during [`bring_up`]
+struct Link<'transport, T> {
+    transport: &'transport mut T,
+    timeout: Duration,
+}
+
+impl<T> Link<'_, T>
+where
+    T: Stream<Item = anyhow::Result<Packet>> + Sink<Packet, Error = anyhow::Error> + Unpin,
+{
+    async fn send(&mut self, packet: Packet) -> Result<(), BringUpError> {
+        let transport = &mut *self.transport;
+        poll_fn(|cx| Pin::new(&mut *transport).poll_ready(cx))
+            .await
+            .map_err(BringUpError::Transport)?;
+        Pin::new(&mut *transport)
+            .start_send(packet)
+            .map_err(BringUpError::Transport)?;
+        poll_fn(|cx| Pin::new(&mut *transport).poll_flush(cx))
+            .await
+            .map_err(BringUpError::Transport)
+    }
+
+    /// Waits for the first packet `select` returns a value for; `None` after `timeout`.
+    async fn receive<V>(
+        &mut self,
+        timeout: Duration,
+        mut select: impl FnMut(Packet) -> Result<Option<V>, BringUpError>,
+    ) -> Result<Option<V>, BringUpError> {
+        let transport = &mut *self.transport;
+
+        let received = time::timeout(timeout, async {
+            while let Some(packet) = poll_fn(|cx| Pin::new(&mut *transport).poll_next(cx)).await {
+                if let Some(value) = select(packet.map_err(BringUpError::Transport)?)? {
+                    return Ok(value);
+                }
+            }
+            Err(BringUpError::Closed)
+        })
+        .await;
+
+        received.map_or(Ok(None), |result| result.map(Some))
+    }
+
+    /// Sends a packet and waits for the response `select` returns a value for.
+    ///
+    /// Fails early if the lidar rejects the packet.
+    async fn query<V>(
+        &mut self,
+        packet: Packet,
+        mut select: impl FnMut(Packet) -> Option<V>,
+    ) -> Result<Option<V>, BringUpError> {
+        self.send(packet.clone()).await?;
+
+        self.receive(self.timeout, |response| match &response {
+            Packet::LidarAckData(ack) if ack.acknowledges(&packet) => {
+                if ack.status() != AckStatus::Success {
+                    return Err(BringUpError::Rejected {
+                        packet_type: packet.packet_type(),
+                        status: ack.status(),
+                    });
+                }
+                Ok(select(response))
+            }
+            // acks of earlier requests
+            Packet::LidarAckData(_) => Ok(None),
+            _ => Ok(select(response)),
+        })
+        .await
+    }
+
+    /// Sends a packet and waits until the lidar has acknowledged it.
+    async fn request(&mut self, packet: Packet) -> Result<(), BringUpError> {
+        let packet_type = packet.packet_type();
+        self.query(packet, |response| match response {
+            Packet::LidarAckData(_) => Some(()),
+            _ => None,
+        })
+        .await?
+        .ok_or(BringUpError::NotAcknowledged {
+            packet_type,
+            timeout: self.timeout,
+        })
+    }
+
+    async fn read_work_mode(&mut self) -> Result<Option<WorkMode>, BringUpError> {
+        self.query(
+            Packet::LidarUserCmd(UserCmd::ConfigGet(0)),
+            |packet| match packet {
+                Packet::LidarWorkModeConfig(mode) | Packet::LidarWorkMode(mode) => Some(mode),
+                _ => None,
+            },
+        )
+        .await
+    }
+}
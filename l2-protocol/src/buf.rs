@@ -0,0 +1,33 @@
+/// Minimal replacement for `bytes::Buf` that works without `alloc`.
+///
+/// Like its counterpart, reading past the end of the buffer panics, so callers must check the
+/// length beforehand.
+pub(crate) trait Buf<'buf> {
+    fn get_chunk<const N: usize>(&mut self) -> &'buf [u8; N];
+
+    fn get_u8(&mut self) -> u8 {
+        u8::from_le_bytes(*self.get_chunk())
+    }
+
+    fn get_u16_le(&mut self) -> u16 {
+        u16::from_le_bytes(*self.get_chunk())
+    }
+
+    fn get_u32_le(&mut self) -> u32 {
+        u32::from_le_bytes(*self.get_chunk())
+    }
+
+    fn get_f32_le(&mut self) -> f32 {
+        f32::from_le_bytes(*self.get_chunk())
+    }
+}
+
+impl<'buf> Buf<'buf> for &'buf [u8] {
+    fn get_chunk<const N: usize>(&mut self) -> &'buf [u8; N] {
+        let (chunk, remainder) = self
+            .split_first_chunk()
+            .expect("length should've been checked upfront");
+        *self = remainder;
+        chunk
+    }
+}
@@ -0,0 +1,169 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::PacketType;
+#[cfg(feature = "std")]
+use crate::{Command, Packet, UserCmd, VersionNumber, WorkMode};
+
+/// Parts of the protocol that differ between firmware versions
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// packet type of the commands to start, stop, reset and query the lidar, either
+    /// [`PacketType::LidarUserCmd`] or [`PacketType::LidarCommand`]
+    pub command_packet: PacketType,
+    /// packet type to change the work mode with, either [`PacketType::LidarWorkModeConfig`] or
+    /// [`PacketType::LidarWorkMode`]
+    pub work_mode_packet: PacketType,
+    /// whether the lidar can measure in 2D, sending [`PacketType::Lidar2DPointData`]
+    pub measure_2d: bool,
+    /// whether the lidar reports its MAC address along with the network configuration
+    pub mac_address: bool,
+}
+
+impl Capabilities {
+    /// What this crate assumes in the absence of better knowledge
+    pub const BASELINE: Self = Self {
+        command_packet: PacketType::LidarUserCmd,
+        work_mode_packet: PacketType::LidarWorkMode,
+        measure_2d: true,
+        mac_address: false,
+    };
+
+    /// The packet sending `cmd` as [`Self::command_packet`].
+    ///
+    /// `None` if that isn't a command packet type or has no equivalent of `cmd`.
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn command(&self, cmd: UserCmd) -> Option<Packet> {
+        match self.command_packet {
+            PacketType::LidarUserCmd => Some(Packet::LidarUserCmd(cmd)),
+            PacketType::LidarCommand => {
+                let command = match cmd {
+                    UserCmd::ResetType(value) => Command::ResetType(value),
+                    UserCmd::StandbyType(standby) => Command::StandbyType((&standby).into()),
+                    UserCmd::VersionGet(value) => Command::VersionGet(value),
+                    UserCmd::LatencyType(value) => Command::LatencyType(value),
+                    UserCmd::ConfigReset(value) => Command::ConfigReset(value),
+                    UserCmd::ConfigGet(_) | UserCmd::ConfigAutoStandby(_) => return None,
+                };
+                Some(Packet::LidarCommand(command))
+            }
+            _ => None,
+        }
+    }
+
+    /// The packet changing the work mode as [`Self::work_mode_packet`].
+    ///
+    /// `None` if that isn't a work mode packet type.
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn work_mode(&self, mode: WorkMode) -> Option<Packet> {
+        match self.work_mode_packet {
+            PacketType::LidarWorkMode => Some(Packet::LidarWorkMode(mode)),
+            PacketType::LidarWorkModeConfig => Some(Packet::LidarWorkModeConfig(mode)),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self::BASELINE
+    }
+}
+
+/// The capabilities of the firmware from a version on
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FirmwareCapabilities {
+    /// first software version with these capabilities
+    pub since: VersionNumber,
+    pub capabilities: Capabilities,
+}
+
+/// Capabilities keyed by the software version reported in [`Version`](crate::Version).
+///
+/// The default table only holds the [`Capabilities::BASELINE`] and knows no tested firmware; add
+/// the versions a driver has been verified against so it can warn about others. With the `serde`
+/// feature it can be stored in a file, e.g. as JSON:
+///
+/// ```json
+/// {
+///   "entries": [
+///     {
+///       "since": [1, 0, 0, 0],
+///       "capabilities": {
+///         "command_packet": "lidar_user_cmd",
+///         "work_mode_packet": "lidar_work_mode",
+///         "measure_2d": true,
+///         "mac_address": true
+///       }
+///     }
+///   ],
+///   "tested": [[1, 0, 2, 0]]
+/// }
+/// ```
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapabilityTable {
+    /// in any order; the entry with the highest version not above the firmware's applies
+    pub entries: Vec<FirmwareCapabilities>,
+    /// software versions the table has been verified against
+    pub tested: Vec<VersionNumber>,
+}
+
+#[cfg(feature = "std")]
+impl Default for CapabilityTable {
+    fn default() -> Self {
+        Self {
+            entries: vec![FirmwareCapabilities {
+                since: VersionNumber::default(),
+                capabilities: Capabilities::BASELINE,
+            }],
+            tested: Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl CapabilityTable {
+    /// Adds the capabilities of the firmware from `since` on, replacing an entry for the same
+    /// version.
+    #[must_use]
+    pub fn with_capabilities(mut self, since: VersionNumber, capabilities: Capabilities) -> Self {
+        self.entries.retain(|entry| entry.since != since);
+        self.entries.push(FirmwareCapabilities {
+            since,
+            capabilities,
+        });
+        self
+    }
+
+    /// Marks a software version as verified.
+    #[must_use]
+    pub fn with_tested(mut self, software: VersionNumber) -> Self {
+        if !self.tested.contains(&software) {
+            self.tested.push(software);
+        }
+        self
+    }
+
+    /// Capabilities of the firmware; the [`Capabilities::BASELINE`] if it predates all entries
+    #[must_use]
+    pub fn capabilities(&self, software: VersionNumber) -> Capabilities {
+        self.entries
+            .iter()
+            .filter(|entry| entry.since <= software)
+            .max_by_key(|entry| entry.since)
+            .map_or(Capabilities::BASELINE, |entry| entry.capabilities)
+    }
+
+    /// Whether the firmware has been verified, as opposed to its capabilities being extrapolated
+    #[must_use]
+    pub fn is_tested(&self, software: VersionNumber) -> bool {
+        self.tested.contains(&software)
+    }
+}
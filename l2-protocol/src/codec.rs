@@ -0,0 +1,299 @@
+use anyhow::Result;
+use bytes::{Buf, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::{
+    Packet, ToUsize,
+    frame::{FrameHeader, FrameTail, parse_frame},
+};
+
+/// Frames announcing a larger size than this are considered corrupt.
+///
+/// The largest known packet (2D point data) is about 5.5 KiB.
+const MAX_FRAME_LEN: usize = 16 * 1024;
+
+/// Smallest possible frame: header and tail without any payload
+const MIN_FRAME_LEN: usize = FrameHeader::LEN + FrameTail::LEN;
+
+/// Counters collected while decoding a byte stream.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CodecStats {
+    /// number of successfully decoded packets
+    pub packets: u64,
+    /// number of frames that have been dropped due to a CRC or framing error
+    pub crc_errors: u64,
+    /// number of frames with a valid CRC whose payload could not be decoded
+    pub invalid_packets: u64,
+    /// number of bytes that have been skipped while searching for the next frame header
+    pub skipped_bytes: u64,
+}
+
+/// A [`Decoder`]/[`Encoder`] for L2 frames.
+///
+/// The decoder resynchronizes on the frame header after garbage or corrupted frames, so a single
+/// bad frame doesn't terminate the stream. Dropped frames and skipped bytes are accounted for in
+/// [`CodecStats`].
+#[derive(Debug, Default)]
+pub struct PacketCodec {
+    stats: CodecStats,
+}
+
+impl PacketCodec {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn stats(&self) -> &CodecStats {
+        &self.stats
+    }
+
+    fn skip(&mut self, src: &mut BytesMut, count: usize) {
+        src.advance(count);
+        self.stats.skipped_bytes += count as u64;
+    }
+}
+
+impl Decoder for PacketCodec {
+    type Item = Packet;
+    type Error = anyhow::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>> {
+        loop {
+            let Some(start) = src
+                .windows(FrameHeader::FRAME_HEADER_ARRAY.len())
+                .position(|window| window == FrameHeader::FRAME_HEADER_ARRAY)
+            else {
+                // the end of the buffer might contain the beginning of the next header
+                let keep = src.len().min(FrameHeader::FRAME_HEADER_ARRAY.len() - 1);
+                self.skip(src, src.len() - keep);
+                return Ok(None);
+            };
+            self.skip(src, start);
+
+            if src.len() < FrameHeader::LEN {
+                src.reserve(FrameHeader::LEN - src.len());
+                return Ok(None);
+            }
+
+            let (header, _) = FrameHeader::parse(src)?;
+            let frame_len = header.packet_size.to_usize();
+            if !(MIN_FRAME_LEN..=MAX_FRAME_LEN).contains(&frame_len) {
+                // false positive or corrupted header; search for the next one
+                self.skip(src, 1);
+                continue;
+            }
+
+            if src.len() < frame_len {
+                src.reserve(frame_len - src.len());
+                return Ok(None);
+            }
+
+            let Ok((packet_type, payload, _)) = parse_frame(src) else {
+                self.stats.crc_errors += 1;
+                self.skip(src, 1);
+                continue;
+            };
+
+            let packet = Packet::parse_payload(packet_type, payload);
+            src.advance(frame_len);
+
+            if let Ok(packet) = packet {
+                self.stats.packets += 1;
+                return Ok(Some(packet));
+            }
+            self.stats.invalid_packets += 1;
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>> {
+        let packet = self.decode(src)?;
+        if packet.is_none() {
+            // a truncated frame at the end of the stream (or datagram) can never be completed
+            let len = src.len();
+            self.skip(src, len);
+        }
+        Ok(packet)
+    }
+}
+
+impl Encoder<Packet> for PacketCodec {
+    type Error = anyhow::Error;
+
+    fn encode(&mut self, item: Packet, dst: &mut BytesMut) -> Result<()> {
+        Ok(item.encode(dst)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PacketType, UserCmd, WorkMode};
+
+    fn packets() -> [Packet; 3] {
+        [
+            Packet::LidarUserCmd(UserCmd::VersionGet(0)),
+            Packet::LidarWorkMode(WorkMode::default()),
+            Packet::LidarParamData(vec![1, 2, 3, 4]),
+        ]
+    }
+
+    fn encode(packets: &[Packet]) -> BytesMut {
+        let mut buf = BytesMut::new();
+        for packet in packets {
+            packet.encode(&mut buf).expect("packet should be encodable");
+        }
+        buf
+    }
+
+    fn decode_all(codec: &mut PacketCodec, buf: &mut BytesMut) -> Vec<Packet> {
+        let mut decoded = Vec::new();
+        while let Some(packet) = codec.decode_eof(buf).expect("decoding should not fail") {
+            decoded.push(packet);
+        }
+        decoded
+    }
+
+    fn types(packets: &[Packet]) -> Vec<PacketType> {
+        packets.iter().map(Packet::packet_type).collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let packets = packets();
+        let mut buf = encode(&packets);
+        let mut codec = PacketCodec::new();
+
+        let decoded = decode_all(&mut codec, &mut buf);
+        assert_eq!(
+            types(&decoded),
+            types(&packets),
+            "all packets should be decoded"
+        );
+        assert!(
+            matches!(
+                decoded.as_slice(),
+                [
+                    Packet::LidarUserCmd(UserCmd::VersionGet(0)),
+                    Packet::LidarWorkMode(mode),
+                    Packet::LidarParamData(raw),
+                ] if *mode == WorkMode::default() && raw == &[1, 2, 3, 4]
+            ),
+            "packets should survive the round trip: {decoded:?}"
+        );
+        assert_eq!(
+            codec.stats(),
+            &CodecStats {
+                packets: 3,
+                ..CodecStats::default()
+            },
+            "nothing should be dropped"
+        );
+        assert!(buf.is_empty(), "all bytes should be consumed");
+    }
+
+    #[test]
+    fn waits_for_the_rest_of_a_frame() {
+        let full = encode(&packets()[..1]);
+        let mut codec = PacketCodec::new();
+        let mut buf = BytesMut::new();
+
+        for (index, byte) in full.iter().enumerate() {
+            buf.extend_from_slice(&[*byte]);
+            let packet = codec.decode(&mut buf).expect("decoding should not fail");
+            assert_eq!(
+                packet.is_some(),
+                index == full.len() - 1,
+                "a packet should be decoded exactly once the frame is complete"
+            );
+        }
+        assert_eq!(codec.stats().skipped_bytes, 0, "no byte should be skipped");
+    }
+
+    #[test]
+    fn skips_garbage_before_a_frame() {
+        let mut buf = BytesMut::from(&[0x55, 0xAA, 0x00, 0x12, 0x34][..]);
+        buf.extend_from_slice(&encode(&packets()));
+        let mut codec = PacketCodec::new();
+
+        let decoded = decode_all(&mut codec, &mut buf);
+        assert_eq!(
+            types(&decoded),
+            types(&packets()),
+            "all packets should be decoded"
+        );
+        assert_eq!(
+            codec.stats().skipped_bytes,
+            5,
+            "only the garbage should be skipped"
+        );
+    }
+
+    #[test]
+    fn resyncs_after_a_bad_crc() {
+        let mut buf = encode(&packets());
+        // the first byte of the payload of the first frame
+        if let Some(byte) = buf.get_mut(FrameHeader::LEN) {
+            *byte ^= 0xFF;
+        }
+        let mut codec = PacketCodec::new();
+
+        let decoded = decode_all(&mut codec, &mut buf);
+        assert_eq!(
+            types(&decoded),
+            types(&packets()[1..]),
+            "only the corrupted packet should be lost"
+        );
+        assert_eq!(
+            codec.stats().crc_errors,
+            1,
+            "the CRC error should be counted"
+        );
+        assert_eq!(
+            codec.stats().skipped_bytes,
+            encode(&packets()[..1]).len() as u64,
+            "the corrupted frame should be skipped byte by byte"
+        );
+    }
+
+    #[test]
+    fn rejects_oversized_frames() {
+        let mut buf = encode(&packets());
+        // announce a frame larger than any packet in the first header
+        let size = u32::try_from(MAX_FRAME_LEN + 1).expect("limit should fit into the header");
+        if let Some(bytes) = buf.get_mut(8..12) {
+            bytes.copy_from_slice(&size.to_le_bytes());
+        }
+        let mut codec = PacketCodec::new();
+
+        let decoded = decode_all(&mut codec, &mut buf);
+        assert_eq!(
+            types(&decoded),
+            types(&packets()[1..]),
+            "decoding should resume at the next header instead of waiting for the announced size"
+        );
+        assert_eq!(codec.stats().crc_errors, 0, "no CRC should be checked");
+    }
+
+    #[test]
+    fn drops_a_truncated_tail_at_the_end_of_the_stream() {
+        let mut buf = encode(&packets()[..2]);
+        buf.truncate(buf.len() - 3);
+        let truncated = encode(&packets()[1..2]).len() - 3;
+        let mut codec = PacketCodec::new();
+
+        let decoded = decode_all(&mut codec, &mut buf);
+        assert_eq!(
+            types(&decoded),
+            types(&packets()[..1]),
+            "the complete packet should be decoded"
+        );
+        assert!(buf.is_empty(), "the truncated frame should be dropped");
+        assert_eq!(
+            codec.stats().skipped_bytes,
+            truncated as u64,
+            "the truncated frame should be counted as skipped"
+        );
+    }
+}
@@ -1,8 +1,22 @@
-use std::fmt::{self, Display};
+use core::fmt::{self, Display};
 
-use anyhow::{Result, bail};
-use bytes::Buf;
+#[cfg(feature = "std")]
+use bytes::BufMut;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    buf::Buf,
+    error::{ProtocolError, Result},
+};
+
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Command {
     ResetType(u32),
     ParamSave(u32),
@@ -24,7 +38,7 @@ impl Command {
 }
 
 impl TryFrom<LidarCommand> for Command {
-    type Error = anyhow::Error;
+    type Error = ProtocolError;
 
     fn try_from(cmd: LidarCommand) -> Result<Self, Self::Error> {
         (cmd.cmd_type, cmd.cmd_value).try_into()
@@ -32,7 +46,7 @@ impl TryFrom<LidarCommand> for Command {
 }
 
 impl TryFrom<(u32, u32)> for Command {
-    type Error = anyhow::Error;
+    type Error = ProtocolError;
 
     fn try_from((typ, value): (u32, u32)) -> Result<Self, Self::Error> {
         match typ {
@@ -43,7 +57,26 @@ impl TryFrom<(u32, u32)> for Command {
             Self::STANDBY_TYPE => Ok(Self::StandbyType(value)),
             Self::LATENCY_TYPE => Ok(Self::LatencyType(value)),
             Self::CONFIG_RESET => Ok(Self::ConfigReset(value)),
-            unknown => bail!("unknown command type: {unknown}"),
+            unknown => Err(ProtocolError::UnknownCommandType(unknown)),
+        }
+    }
+}
+
+impl From<&Command> for LidarCommand {
+    fn from(command: &Command) -> Self {
+        let (cmd_type, cmd_value) = match *command {
+            Command::ResetType(value) => (Command::RESET_TYPE, value),
+            Command::ParamSave(value) => (Command::PARAM_SAVE, value),
+            Command::ParamGet(value) => (Command::PARAM_GET, value),
+            Command::VersionGet(value) => (Command::VERSION_GET, value),
+            Command::StandbyType(value) => (Command::STANDBY_TYPE, value),
+            Command::LatencyType(value) => (Command::LATENCY_TYPE, value),
+            Command::ConfigReset(value) => (Command::CONFIG_RESET, value),
+        };
+
+        Self {
+            cmd_type,
+            cmd_value,
         }
     }
 }
@@ -68,7 +101,7 @@ impl Display for Command {
 // }
 
 // impl TryFrom<u32> for StandbyType {
-//     type Error = anyhow::Error;
+//     type Error = ProtocolError;
 
 //     fn try_from(value: u32) -> Result<Self, Self::Error> {
 //         match value {
@@ -97,8 +130,8 @@ impl Display for Command {
 #[repr(C)]
 pub struct LidarCommand {
     ///   0:null, 1:standby
-    cmd_type: u32,
-    cmd_value: u32,
+    pub(crate) cmd_type: u32,
+    pub(crate) cmd_value: u32,
 }
 
 impl LidarCommand {
@@ -106,11 +139,10 @@ impl LidarCommand {
 
     pub(crate) fn parse(bytes: &[u8]) -> Result<(Self, &[u8])> {
         let Some((mut bytes, remainder)) = bytes.split_at_checked(Self::LEN) else {
-            bail!(
-                "expected a minimum of {} bytes but got {}",
-                Self::LEN,
-                bytes.len()
-            );
+            return Err(ProtocolError::Truncated {
+                expected: Self::LEN,
+                actual: bytes.len(),
+            });
         };
 
         let cmd_type = bytes.get_u32_le();
@@ -128,6 +160,12 @@ impl LidarCommand {
             remainder,
         ))
     }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn write(&self, buf: &mut impl BufMut) {
+        buf.put_u32_le(self.cmd_type);
+        buf.put_u32_le(self.cmd_value);
+    }
 }
 
 impl Display for LidarCommand {
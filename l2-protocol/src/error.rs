@@ -0,0 +1,44 @@
+use core::{result, str::Utf8Error};
+
+use thiserror::Error;
+
+pub(crate) type Result<T, E = ProtocolError> = result::Result<T, E>;
+
+/// Errors that can occur while decoding or encoding packets
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ProtocolError {
+    #[error("expected a minimum of {expected} bytes but got {actual}")]
+    Truncated { expected: usize, actual: usize },
+    #[error("wrong magic bytes")]
+    MagicBytes,
+    #[error("wrong tail")]
+    Tail,
+    #[error("packet is too small to hold any payload")]
+    PacketTooSmall,
+    #[error("payload truncated")]
+    PayloadTruncated,
+    #[error("CRC mismatch")]
+    CrcMismatch,
+    #[error("unknown packet type: {0}")]
+    UnknownPacketType(u32),
+    #[error("unknown command type: {0}")]
+    UnknownCommandType(u32),
+    #[error("unknown standby mode: {0}")]
+    UnknownStandbyMode(u32),
+    #[error("unknown ack status: {0}")]
+    UnknownAckStatus(u32),
+    #[error("unknown mode flags: {0:#034b}")]
+    UnknownModeFlags(u32),
+    #[error("device name contained invalid utf-8")]
+    InvalidDeviceName(#[source] Utf8Error),
+    #[error("device name too long")]
+    DeviceNameTooLong,
+    #[error("unexpected date format")]
+    InvalidDate,
+    #[error("unexpected version format")]
+    InvalidVersion,
+    #[error("packet too large")]
+    PacketTooLarge,
+    #[error("invalid number of points: {ranges} ranges, {intensities} intensities")]
+    InvalidPointCount { ranges: usize, intensities: usize },
+}
@@ -1,14 +1,23 @@
-use std::fmt::{self, Display};
+#[cfg(feature = "std")]
+use core::fmt::{self, Display};
 
-use anyhow::{Context, Result, bail};
-use bytes::Buf;
-use crc_fast::CrcAlgorithm;
+#[cfg(feature = "std")]
+use bytes::{BufMut, BytesMut};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 use crate::{
     ToUsize,
+    buf::Buf,
+    error::{ProtocolError, Result},
+};
+#[cfg(feature = "std")]
+use crate::{
     ack::{Ack, LidarAckData},
     command::{Command, LidarCommand},
     imu::LidarImuData,
+    network::{IpAddressConfig, LidarIpAddressConfig, LidarMacAddressConfig, MacAddressConfig},
     point_data::LidarPointData,
     user_ctrl_cmd::{LidarUserCtrlCmd, UserCmd},
     version::{LidarVersionData, Version},
@@ -30,19 +39,18 @@ impl FrameHeader {
     pub(crate) const LEN: usize = size_of::<Self>();
 
     /// Every frame starts with these magic bytes
-    const FRAME_HEADER_ARRAY: [u8; 4] = [0x55, 0xAA, 0x05, 0x0A];
+    pub(crate) const FRAME_HEADER_ARRAY: [u8; 4] = [0x55, 0xAA, 0x05, 0x0A];
 
     pub(crate) fn parse(bytes: &[u8]) -> Result<(Self, &[u8])> {
         let Some((bytes, remainder)) = bytes.split_at_checked(Self::LEN) else {
-            bail!(
-                "expected a minimum of {} bytes but got {}",
-                Self::LEN,
-                bytes.len()
-            );
+            return Err(ProtocolError::Truncated {
+                expected: Self::LEN,
+                actual: bytes.len(),
+            });
         };
 
         let Some(mut bytes) = bytes.strip_prefix(&Self::FRAME_HEADER_ARRAY) else {
-            bail!("wrong magic bytes");
+            return Err(ProtocolError::MagicBytes);
         };
 
         let packet_type = bytes.get_u32_le();
@@ -61,6 +69,13 @@ impl FrameHeader {
             remainder,
         ))
     }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn write(&self, buf: &mut impl BufMut) {
+        buf.put_slice(&self.header);
+        buf.put_u32_le(self.packet_type);
+        buf.put_u32_le(self.packet_size);
+    }
 }
 
 /**
@@ -89,18 +104,17 @@ impl FrameTail {
 
     pub(crate) fn parse(bytes: &[u8]) -> Result<(Self, &[u8])> {
         let Some((mut bytes, remainder)) = bytes.split_at_checked(Self::LEN) else {
-            bail!(
-                "expected a minimum of {} bytes but got {}",
-                Self::LEN,
-                bytes.len()
-            );
+            return Err(ProtocolError::Truncated {
+                expected: Self::LEN,
+                actual: bytes.len(),
+            });
         };
 
         let crc32 = bytes.get_u32_le();
         let msg_type_check = bytes.get_u32_le();
         let reserve = [bytes.get_u8(), bytes.get_u8()];
         if bytes != Self::FRAME_TAIL_ARRAY {
-            bail!("wrong tail");
+            return Err(ProtocolError::Tail);
         }
 
         Ok((
@@ -113,13 +127,29 @@ impl FrameTail {
             remainder,
         ))
     }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn write(&self, buf: &mut impl BufMut) {
+        buf.put_u32_le(self.crc32);
+        buf.put_u32_le(self.msg_type_check);
+        buf.put_slice(&self.reserve);
+        buf.put_slice(&self.tail);
+    }
 }
 
+/// Type of a packet as announced in its frame header
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
 #[repr(u32)]
-pub(crate) enum PacketType {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PacketType {
     LidarUserCmd = Self::LIDAR_USER_CMD,
     LidarAckData = Self::LIDAR_ACK_DATA,
     LidarPointData = Self::LIDAR_POINT_DATA,
+    #[cfg_attr(feature = "serde", serde(rename = "lidar_2d_point_data"))]
     Lidar2DPointData = Self::LIDAR_2D_POINT_DATA,
     LidarImuData = Self::LIDAR_IMU_DATA,
     LidarVersion = Self::LIDAR_VERSION,
@@ -148,10 +178,46 @@ impl PacketType {
     pub(crate) const LIDAR_PARAM_DATA: u32 = 2001;
     /// TODO this is a guess
     pub(crate) const LIDAR_WORK_MODE: u32 = 2002;
+
+    pub const ALL: [Self; 13] = [
+        Self::LidarUserCmd,
+        Self::LidarAckData,
+        Self::LidarPointData,
+        Self::Lidar2DPointData,
+        Self::LidarImuData,
+        Self::LidarVersion,
+        Self::LidarTimeStamp,
+        Self::LidarWorkModeConfig,
+        Self::LidarIpAddressConfig,
+        Self::LidarMacAddressConfig,
+        Self::LidarCommand,
+        Self::LidarParamData,
+        Self::LidarWorkMode,
+    ];
+
+    /// Name in snake case, as used for the `type` field with the `serde` feature
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::LidarUserCmd => "lidar_user_cmd",
+            Self::LidarAckData => "lidar_ack_data",
+            Self::LidarPointData => "lidar_point_data",
+            Self::Lidar2DPointData => "lidar_2d_point_data",
+            Self::LidarImuData => "lidar_imu_data",
+            Self::LidarVersion => "lidar_version",
+            Self::LidarTimeStamp => "lidar_time_stamp",
+            Self::LidarWorkModeConfig => "lidar_work_mode_config",
+            Self::LidarIpAddressConfig => "lidar_ip_address_config",
+            Self::LidarMacAddressConfig => "lidar_mac_address_config",
+            Self::LidarCommand => "lidar_command",
+            Self::LidarParamData => "lidar_param_data",
+            Self::LidarWorkMode => "lidar_work_mode",
+        }
+    }
 }
 
 impl TryFrom<u32> for PacketType {
-    type Error = anyhow::Error;
+    type Error = ProtocolError;
 
     fn try_from(value: u32) -> Result<Self, Self::Error> {
         match value {
@@ -168,27 +234,70 @@ impl TryFrom<u32> for PacketType {
             Self::LIDAR_COMMAND => Ok(Self::LidarCommand),
             Self::LIDAR_PARAM_DATA => Ok(Self::LidarParamData),
             Self::LIDAR_WORK_MODE => Ok(Self::LidarWorkMode),
-            unknown => bail!("unknown packet type: {unknown}"),
+            unknown => Err(ProtocolError::UnknownPacketType(unknown)),
         }
     }
 }
 
+/// Validates the framing and CRC of the packet at the start of `input`.
+///
+/// Returns the raw packet type, the payload and the remaining non-consumed bytes.
+pub(crate) fn parse_frame(input: &[u8]) -> Result<(u32, &[u8], &[u8])> {
+    let (header, mut remainder) = FrameHeader::parse(input)?;
+
+    let Some(payload_len) = header
+        .packet_size
+        .to_usize()
+        .checked_sub(FrameHeader::LEN + FrameTail::LEN)
+    else {
+        return Err(ProtocolError::PacketTooSmall);
+    };
+
+    let payload_bytes = remainder
+        .split_off(..payload_len)
+        .ok_or(ProtocolError::PayloadTruncated)?;
+
+    // println!("payload {}", payload_bytes.len());
+    let payload_crc = crc32fast::hash(payload_bytes);
+
+    let (tail, remainder) = FrameTail::parse(remainder)?;
+
+    if payload_crc != tail.crc32 {
+        return Err(ProtocolError::CrcMismatch);
+    }
+
+    Ok((header.packet_type, payload_bytes, remainder))
+}
+
+/// A decoded packet.
+///
+/// With the `serde` feature it serializes as `{"type": "lidar_imu_data", "data": {..}}`. Payloads
+/// which aren't decoded yet are represented by their raw bytes.
+#[cfg(feature = "std")]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(tag = "type", content = "data", rename_all = "snake_case")
+)]
+#[derive(Debug, Clone)]
 pub enum Packet {
     LidarUserCmd(UserCmd),
     LidarAckData(Ack),
     LidarPointData(Box<LidarPointData>),
+    #[cfg_attr(feature = "serde", serde(rename = "lidar_2d_point_data"))]
     Lidar2DPointData(Vec<u8>),
     LidarImuData(LidarImuData),
     LidarVersion(Version),
     LidarTimeStamp(Vec<u8>),
     LidarWorkModeConfig(WorkMode),
-    LidarIpAddressConfig(Vec<u8>),
-    LidarMacAddressConfig(Vec<u8>),
+    LidarIpAddressConfig(IpAddressConfig),
+    LidarMacAddressConfig(MacAddressConfig),
     LidarCommand(Command),
     LidarParamData(Vec<u8>),
     LidarWorkMode(WorkMode),
 }
 
+#[cfg(feature = "std")]
 impl Packet {
     /// Deserializes a packet from the given input.
     ///
@@ -203,30 +312,15 @@ impl Packet {
     /// - has a CRC mismatch
     /// - contains illegal values
     pub fn parse(input: &[u8]) -> Result<(Self, &[u8])> {
-        let (header, mut remainder) = FrameHeader::parse(input)?;
-
-        let Some(payload_len) = header
-            .packet_size
-            .to_usize()
-            .checked_sub(FrameHeader::LEN + FrameTail::LEN)
-        else {
-            bail!("packet is too small to hold any payload");
-        };
-
-        let payload_bytes = remainder
-            .split_off(..payload_len)
-            .context("payload truncated")?;
-
-        // println!("payload {}", payload_bytes.len());
-        let payload_crc = crc_fast::checksum(CrcAlgorithm::Crc32IsoHdlc, payload_bytes);
-
-        let (tail, remainder) = FrameTail::parse(remainder)?;
+        let (packet_type, payload_bytes, remainder) = parse_frame(input)?;
+        let packet = Self::parse_payload(packet_type, payload_bytes)?;
 
-        if payload_crc != u64::from(tail.crc32) {
-            bail!("CRC mismatch");
-        }
+        Ok((packet, remainder))
+    }
 
-        let packet_type = PacketType::try_from(header.packet_type)?;
+    /// Decodes the payload of a frame that has already been validated by [`parse_frame`].
+    pub(crate) fn parse_payload(packet_type: u32, payload_bytes: &[u8]) -> Result<Self> {
+        let packet_type = PacketType::try_from(packet_type)?;
         let packet = match packet_type {
             PacketType::LidarUserCmd => {
                 let (cmd, _) = LidarUserCtrlCmd::parse(payload_bytes)?;
@@ -263,11 +357,13 @@ impl Packet {
             }
             PacketType::LidarIpAddressConfig => {
                 // TODO never seen in the wild so far
-                Self::LidarIpAddressConfig(payload_bytes.to_vec())
+                let (config, _) = LidarIpAddressConfig::parse(payload_bytes)?;
+                Self::LidarIpAddressConfig(config.into())
             }
             PacketType::LidarMacAddressConfig => {
                 // TODO never seen in the wild so far
-                Self::LidarMacAddressConfig(payload_bytes.to_vec())
+                let (config, _) = LidarMacAddressConfig::parse(payload_bytes)?;
+                Self::LidarMacAddressConfig(config.into())
             }
             PacketType::LidarCommand => {
                 let (command, _) = LidarCommand::parse(payload_bytes)?;
@@ -280,10 +376,85 @@ impl Packet {
             }
         };
 
-        Ok((packet, remainder))
+        Ok(packet)
+    }
+
+    /// Serializes the packet into a complete frame (header, payload and tail) and appends it to
+    /// the given buffer.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the packet contains values that cannot be represented on the wire.
+    pub fn encode(&self, dst: &mut BytesMut) -> Result<()> {
+        let mut payload = BytesMut::new();
+        self.write_payload(&mut payload)?;
+
+        let packet_size = u32::try_from(FrameHeader::LEN + payload.len() + FrameTail::LEN)
+            .map_err(|_overflow| ProtocolError::PacketTooLarge)?;
+        let crc32 = crc32fast::hash(&payload);
+
+        let header = FrameHeader {
+            header: FrameHeader::FRAME_HEADER_ARRAY,
+            packet_type: self.packet_type() as u32,
+            packet_size,
+        };
+        let tail = FrameTail {
+            crc32,
+            msg_type_check: 0,
+            reserve: [0; 2],
+            tail: FrameTail::FRAME_TAIL_ARRAY,
+        };
+
+        dst.reserve(packet_size.to_usize());
+        header.write(dst);
+        dst.put_slice(&payload);
+        tail.write(dst);
+
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn packet_type(&self) -> PacketType {
+        match self {
+            Self::LidarUserCmd(_) => PacketType::LidarUserCmd,
+            Self::LidarAckData(_) => PacketType::LidarAckData,
+            Self::LidarPointData(_) => PacketType::LidarPointData,
+            Self::Lidar2DPointData(_) => PacketType::Lidar2DPointData,
+            Self::LidarImuData(_) => PacketType::LidarImuData,
+            Self::LidarVersion(_) => PacketType::LidarVersion,
+            Self::LidarTimeStamp(_) => PacketType::LidarTimeStamp,
+            Self::LidarWorkModeConfig(_) => PacketType::LidarWorkModeConfig,
+            Self::LidarIpAddressConfig(_) => PacketType::LidarIpAddressConfig,
+            Self::LidarMacAddressConfig(_) => PacketType::LidarMacAddressConfig,
+            Self::LidarCommand(_) => PacketType::LidarCommand,
+            Self::LidarParamData(_) => PacketType::LidarParamData,
+            Self::LidarWorkMode(_) => PacketType::LidarWorkMode,
+        }
+    }
+
+    fn write_payload(&self, buf: &mut BytesMut) -> Result<()> {
+        match self {
+            Self::LidarUserCmd(cmd) => LidarUserCtrlCmd::from(cmd).write(buf),
+            Self::LidarAckData(ack) => LidarAckData::from(ack).write(buf),
+            Self::LidarPointData(data) => data.write(buf),
+            Self::LidarImuData(data) => data.write(buf),
+            Self::LidarVersion(version) => LidarVersionData::try_from(version)?.write(buf),
+            Self::LidarWorkModeConfig(mode) | Self::LidarWorkMode(mode) => {
+                LidarWorkModeConfig::from(mode).write(buf);
+            }
+            Self::LidarIpAddressConfig(config) => LidarIpAddressConfig::from(config).write(buf),
+            Self::LidarMacAddressConfig(config) => LidarMacAddressConfig::from(config).write(buf),
+            Self::LidarCommand(command) => LidarCommand::from(command).write(buf),
+            Self::Lidar2DPointData(raw) | Self::LidarTimeStamp(raw) | Self::LidarParamData(raw) => {
+                buf.put_slice(raw);
+            }
+        }
+
+        Ok(())
     }
 }
 
+#[cfg(feature = "std")]
 impl Display for Packet {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -295,11 +466,35 @@ impl Display for Packet {
             Packet::LidarVersion(version) => write!(f, "Version({version})"),
             Packet::LidarTimeStamp(raw) => write!(f, "TimeStamp({})", raw.len()),
             Packet::LidarWorkModeConfig(config) => write!(f, "WorkModeConfig({config})"),
-            Packet::LidarIpAddressConfig(raw) => write!(f, "IpAddressConfig({})", raw.len()),
-            Packet::LidarMacAddressConfig(raw) => write!(f, "MacAddressConfig({})", raw.len()),
+            Packet::LidarIpAddressConfig(config) => write!(f, "IpAddressConfig({config})"),
+            Packet::LidarMacAddressConfig(config) => write!(f, "MacAddressConfig({config})"),
             Packet::LidarCommand(command) => write!(f, "Command({command})"),
             Packet::LidarParamData(raw) => write!(f, "ParamData({})", raw.len()),
             Packet::LidarWorkMode(mode) => write!(f, "WorkMode({mode})"),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn all_packet_types_are_listed() {
+        let known = (0..=u16::MAX).filter_map(|value| PacketType::try_from(u32::from(value)).ok());
+        assert!(
+            known.eq(PacketType::ALL),
+            "every packet type should be listed once"
+        );
+
+        for (index, packet_type) in PacketType::ALL.iter().enumerate() {
+            assert!(
+                PacketType::ALL
+                    .iter()
+                    .skip(index + 1)
+                    .all(|other| other.name() != packet_type.name()),
+                "{packet_type:?} should have a unique name"
+            );
+        }
+    }
+}
@@ -1,12 +1,24 @@
-use std::fmt::{self, Display};
+use core::{
+    fmt::{self, Display},
+    time::Duration,
+};
 
-use anyhow::{Result, bail};
-use bytes::Buf;
+#[cfg(feature = "std")]
+use bytes::BufMut;
 
-use crate::info::DataInfo;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    buf::Buf,
+    error::{ProtocolError, Result},
+    info::DataInfo,
+};
 
 // @note 56 bytes
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[repr(C)]
+#[derive(Debug, Clone, Copy)]
 pub struct LidarImuData {
     info: DataInfo,
     /// Quaternion Array.
@@ -20,13 +32,42 @@ pub struct LidarImuData {
 impl LidarImuData {
     pub(crate) const LEN: usize = size_of::<Self>();
 
+    /// packet sequence id, consecutively increasing
+    #[must_use]
+    pub fn seq(&self) -> u32 {
+        self.info.seq
+    }
+
+    /// timestamp of the measurement, as reported by the lidar
+    #[must_use]
+    pub fn stamp(&self) -> Duration {
+        self.info.stamp()
+    }
+
+    /// orientation as quaternion in the order `[x, y, z, w]`
+    #[must_use]
+    pub fn quaternion(&self) -> [f32; 4] {
+        self.quaternion
+    }
+
+    /// angular velocity around the x, y and z axes [rad/s]
+    #[must_use]
+    pub fn angular_velocity(&self) -> [f32; 3] {
+        self.angular_velocity
+    }
+
+    /// acceleration along the x, y and z axes, including gravity [m/s²]
+    #[must_use]
+    pub fn linear_acceleration(&self) -> [f32; 3] {
+        self.linear_acceleration
+    }
+
     pub(crate) fn parse(bytes: &[u8]) -> Result<(Self, &[u8])> {
         let Some((bytes, remainder)) = bytes.split_at_checked(Self::LEN) else {
-            bail!(
-                "expected a minimum of {} bytes but got {}",
-                Self::LEN,
-                bytes.len()
-            );
+            return Err(ProtocolError::Truncated {
+                expected: Self::LEN,
+                actual: bytes.len(),
+            });
         };
 
         let (info, mut bytes) = DataInfo::parse(bytes)?;
@@ -54,6 +95,19 @@ impl LidarImuData {
             remainder,
         ))
     }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn write(&self, buf: &mut impl BufMut) {
+        self.info.write(buf);
+        for value in self
+            .quaternion
+            .iter()
+            .chain(&self.angular_velocity)
+            .chain(&self.linear_acceleration)
+        {
+            buf.put_f32_le(*value);
+        }
+    }
 }
 
 impl Display for LidarImuData {
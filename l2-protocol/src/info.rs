@@ -1,14 +1,26 @@
-use std::fmt::{self, Display};
+use core::{
+    fmt::{self, Display},
+    time::Duration,
+};
 
-use anyhow::{Result, bail};
-use bytes::Buf;
+#[cfg(feature = "std")]
+use bytes::BufMut;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    buf::Buf,
+    error::{ProtocolError, Result},
+};
 
 /**
  * @brief Time stamp
  * @note 8 bytes
  */
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub(crate) struct TimeStamp {
     /// time stamp of second
     sec: u32,
@@ -21,11 +33,10 @@ impl TimeStamp {
 
     pub(crate) fn parse(bytes: &[u8]) -> Result<(Self, &[u8])> {
         let Some((mut bytes, remainder)) = bytes.split_at_checked(Self::LEN) else {
-            bail!(
-                "expected a minimum of {} bytes but got {}",
-                Self::LEN,
-                bytes.len()
-            );
+            return Err(ProtocolError::Truncated {
+                expected: Self::LEN,
+                actual: bytes.len(),
+            });
         };
 
         let sec = bytes.get_u32_le();
@@ -33,7 +44,20 @@ impl TimeStamp {
 
         Ok((Self { sec, nsec }, remainder))
     }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn write(self, buf: &mut impl BufMut) {
+        buf.put_u32_le(self.sec);
+        buf.put_u32_le(self.nsec);
+    }
+}
+
+impl From<TimeStamp> for Duration {
+    fn from(value: TimeStamp) -> Self {
+        Duration::new(value.sec.into(), value.nsec)
+    }
 }
+
 impl Display for TimeStamp {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}.{:09}", self.sec, self.nsec)
@@ -44,11 +68,12 @@ impl Display for TimeStamp {
  * @brief Data Info
  * @note 16 bytes
  */
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub(crate) struct DataInfo {
     /// packet sequence id, consecutively increasing
-    seq: u32,
+    pub(crate) seq: u32,
     /// Packet Size
     payload_size: u32,
     /// timestamp
@@ -60,11 +85,10 @@ impl DataInfo {
 
     pub(crate) fn parse(bytes: &[u8]) -> Result<(Self, &[u8])> {
         let Some((mut bytes, remainder)) = bytes.split_at_checked(Self::LEN) else {
-            bail!(
-                "expected a minimum of {} bytes but got {}",
-                Self::LEN,
-                bytes.len()
-            );
+            return Err(ProtocolError::Truncated {
+                expected: Self::LEN,
+                actual: bytes.len(),
+            });
         };
 
         let seq = bytes.get_u32_le();
@@ -84,6 +108,18 @@ impl DataInfo {
             remainder,
         ))
     }
+
+    /// timestamp of the measurement, as reported by the lidar
+    pub(crate) fn stamp(&self) -> Duration {
+        self.stamp.into()
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn write(&self, buf: &mut impl BufMut) {
+        buf.put_u32_le(self.seq);
+        buf.put_u32_le(self.payload_size);
+        self.stamp.write(buf);
+    }
 }
 
 impl Display for DataInfo {
@@ -1,16 +1,57 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![allow(unused_crate_dependencies, reason = "used in examples")]
 
 mod ack;
+#[cfg(feature = "tokio")]
+mod bringup;
+mod buf;
+mod capabilities;
+#[cfg(feature = "tokio")]
+mod codec;
 mod command;
+mod error;
 mod frame;
 mod imu;
 mod info;
+mod network;
+mod packet_ref;
 mod point_data;
+#[cfg(feature = "serial")]
+mod serial;
+#[cfg(feature = "tokio")]
+mod udp;
 mod user_ctrl_cmd;
 mod version;
 mod work_mode;
 
+pub use ack::{Ack, AckStatus};
+#[cfg(feature = "tokio")]
+pub use bringup::{BringUpConfig, BringUpError, BringUpStep, bring_up};
+pub use capabilities::Capabilities;
+#[cfg(feature = "std")]
+pub use capabilities::{CapabilityTable, FirmwareCapabilities};
+#[cfg(feature = "tokio")]
+pub use codec::{CodecStats, PacketCodec};
+pub use command::Command;
+pub use error::ProtocolError;
+#[cfg(feature = "std")]
 pub use frame::Packet;
+pub use frame::PacketType;
+pub use imu::LidarImuData;
+pub use network::{IpAddressConfig, MacAddressConfig};
+pub use packet_ref::PacketRef;
+pub use point_data::{
+    Lidar2DPointData, LidarCalibParam, LidarInsideState, LidarPointData, LidarPointDataRef,
+};
+#[cfg(feature = "serial")]
+pub use serial::{DEFAULT_BAUD_RATE, SerialSource};
+#[cfg(feature = "tokio")]
+pub use udp::{DEFAULT_LIDAR_ADDR, DEFAULT_USER_ADDR, UdpSource};
+pub use user_ctrl_cmd::{StandbyType, UserCmd};
+#[cfg(feature = "std")]
+pub use version::Version;
+pub use version::{CompileDate, VersionNumber, VersionRef};
+pub use work_mode::WorkMode;
 
 /// compile-time check to ensure we're not running on a 16-bit system
 const _CHECK32: () = assert!(usize::BITS >= u32::BITS, "16 bit platforms are unsupported");
@@ -25,33 +66,3 @@ impl ToUsize for u32 {
         usize::try_from(self).unwrap_or_else(|error| unreachable!("failed to convert to usize despite being on a system with sufficient word width: {error}"))
     }
 }
-
-/**
- * @brief Lidar IP Config
- * @note 20 bytes
- */
-#[repr(C)]
-pub(crate) struct LidarIpAddressConfig {
-    /// UDP local ip
-    lidar_ip: [u8; 4],
-    /// UDP remote ip
-    user_ip: [u8; 4],
-    /// Gate way
-    gateway: [u8; 4],
-    /// Subnet mask
-    subnet_mask: [u8; 4],
-    /// UDP local port
-    lidar_port: u16,
-    /// UDP remote port
-    user_port: u16,
-}
-
-/**
- * @brief Lidar MAC address Config
- * @note 8 bytes
- */
-#[repr(C)]
-pub(crate) struct LidarMacAddressConfig {
-    mac: [u8; 6],
-    reserve: [u8; 2],
-}
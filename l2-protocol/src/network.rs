@@ -0,0 +1,207 @@
+use core::{
+    fmt::{self, Display},
+    net::Ipv4Addr,
+};
+
+#[cfg(feature = "std")]
+use bytes::BufMut;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    buf::Buf,
+    error::{ProtocolError, Result},
+};
+
+/// Network configuration of the lidar's ethernet interface
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpAddressConfig {
+    pub lidar_ip: Ipv4Addr,
+    /// address the lidar sends its data to
+    pub user_ip: Ipv4Addr,
+    pub gateway: Ipv4Addr,
+    pub subnet_mask: Ipv4Addr,
+    pub lidar_port: u16,
+    /// port the lidar sends its data to
+    pub user_port: u16,
+}
+
+impl From<LidarIpAddressConfig> for IpAddressConfig {
+    fn from(value: LidarIpAddressConfig) -> Self {
+        Self {
+            lidar_ip: value.lidar_ip.into(),
+            user_ip: value.user_ip.into(),
+            gateway: value.gateway.into(),
+            subnet_mask: value.subnet_mask.into(),
+            lidar_port: value.lidar_port,
+            user_port: value.user_port,
+        }
+    }
+}
+
+impl From<&IpAddressConfig> for LidarIpAddressConfig {
+    fn from(value: &IpAddressConfig) -> Self {
+        Self {
+            lidar_ip: value.lidar_ip.octets(),
+            user_ip: value.user_ip.octets(),
+            gateway: value.gateway.octets(),
+            subnet_mask: value.subnet_mask.octets(),
+            lidar_port: value.lidar_port,
+            user_port: value.user_port,
+        }
+    }
+}
+
+impl Display for IpAddressConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "lidar:{}:{}, user:{}:{}, gateway:{}, mask:{}",
+            self.lidar_ip,
+            self.lidar_port,
+            self.user_ip,
+            self.user_port,
+            self.gateway,
+            self.subnet_mask
+        )
+    }
+}
+
+/// MAC address of the lidar's ethernet interface
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MacAddressConfig {
+    pub mac: [u8; 6],
+}
+
+impl From<LidarMacAddressConfig> for MacAddressConfig {
+    fn from(value: LidarMacAddressConfig) -> Self {
+        Self { mac: value.mac }
+    }
+}
+
+impl From<&MacAddressConfig> for LidarMacAddressConfig {
+    fn from(value: &MacAddressConfig) -> Self {
+        Self {
+            mac: value.mac,
+            reserve: [0; 2],
+        }
+    }
+}
+
+impl Display for MacAddressConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [first, rest @ ..] = self.mac;
+        write!(f, "{first:02x}")?;
+        for byte in rest {
+            write!(f, ":{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+/**
+ * @brief Lidar IP Config
+ * @note 20 bytes
+ */
+#[repr(C)]
+pub(crate) struct LidarIpAddressConfig {
+    /// UDP local ip
+    lidar_ip: [u8; 4],
+    /// UDP remote ip
+    user_ip: [u8; 4],
+    /// Gate way
+    gateway: [u8; 4],
+    /// Subnet mask
+    subnet_mask: [u8; 4],
+    /// UDP local port
+    lidar_port: u16,
+    /// UDP remote port
+    user_port: u16,
+}
+
+impl LidarIpAddressConfig {
+    pub(crate) const LEN: usize = size_of::<Self>();
+
+    pub(crate) fn parse(bytes: &[u8]) -> Result<(Self, &[u8])> {
+        let Some((mut bytes, remainder)) = bytes.split_at_checked(Self::LEN) else {
+            return Err(ProtocolError::Truncated {
+                expected: Self::LEN,
+                actual: bytes.len(),
+            });
+        };
+
+        let lidar_ip = *bytes.get_chunk();
+        let user_ip = *bytes.get_chunk();
+        let gateway = *bytes.get_chunk();
+        let subnet_mask = *bytes.get_chunk();
+        let lidar_port = bytes.get_u16_le();
+        let user_port = bytes.get_u16_le();
+
+        if !bytes.is_empty() {
+            unreachable!("bytes should've been completely consumed");
+        }
+
+        Ok((
+            Self {
+                lidar_ip,
+                user_ip,
+                gateway,
+                subnet_mask,
+                lidar_port,
+                user_port,
+            },
+            remainder,
+        ))
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn write(&self, buf: &mut impl BufMut) {
+        buf.put_slice(&self.lidar_ip);
+        buf.put_slice(&self.user_ip);
+        buf.put_slice(&self.gateway);
+        buf.put_slice(&self.subnet_mask);
+        buf.put_u16_le(self.lidar_port);
+        buf.put_u16_le(self.user_port);
+    }
+}
+
+/**
+ * @brief Lidar MAC address Config
+ * @note 8 bytes
+ */
+#[repr(C)]
+pub(crate) struct LidarMacAddressConfig {
+    mac: [u8; 6],
+    reserve: [u8; 2],
+}
+
+impl LidarMacAddressConfig {
+    pub(crate) const LEN: usize = size_of::<Self>();
+
+    pub(crate) fn parse(bytes: &[u8]) -> Result<(Self, &[u8])> {
+        let Some((mut bytes, remainder)) = bytes.split_at_checked(Self::LEN) else {
+            return Err(ProtocolError::Truncated {
+                expected: Self::LEN,
+                actual: bytes.len(),
+            });
+        };
+
+        let mac = *bytes.get_chunk();
+        let reserve = *bytes.get_chunk();
+
+        if !bytes.is_empty() {
+            unreachable!("bytes should've been completely consumed");
+        }
+
+        Ok((Self { mac, reserve }, remainder))
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn write(&self, buf: &mut impl BufMut) {
+        buf.put_slice(&self.mac);
+        buf.put_slice(&self.reserve);
+    }
+}
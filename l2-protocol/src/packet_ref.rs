@@ -0,0 +1,143 @@
+use core::fmt::{self, Display};
+
+#[cfg(feature = "std")]
+use crate::Packet;
+use crate::{
+    ack::{Ack, LidarAckData},
+    command::{Command, LidarCommand},
+    error::Result,
+    frame::{PacketType, parse_frame},
+    imu::LidarImuData,
+    network::{IpAddressConfig, LidarIpAddressConfig, LidarMacAddressConfig, MacAddressConfig},
+    point_data::LidarPointDataRef,
+    user_ctrl_cmd::{LidarUserCtrlCmd, UserCmd},
+    version::VersionRef,
+    work_mode::{LidarWorkModeConfig, WorkMode},
+};
+
+/// Borrowed counterpart of [`Packet`].
+///
+/// Parsing doesn't allocate: point data and unknown payloads refer to the input buffer. This also
+/// works with [`bytes::Bytes`] by parsing from its dereferenced slice.
+pub enum PacketRef<'buf> {
+    LidarUserCmd(UserCmd),
+    LidarAckData(Ack),
+    LidarPointData(LidarPointDataRef<'buf>),
+    Lidar2DPointData(&'buf [u8]),
+    LidarImuData(LidarImuData),
+    LidarVersion(VersionRef<'buf>),
+    LidarTimeStamp(&'buf [u8]),
+    LidarWorkModeConfig(WorkMode),
+    LidarIpAddressConfig(IpAddressConfig),
+    LidarMacAddressConfig(MacAddressConfig),
+    LidarCommand(Command),
+    LidarParamData(&'buf [u8]),
+    LidarWorkMode(WorkMode),
+}
+
+impl<'buf> PacketRef<'buf> {
+    /// Deserializes a packet from the given input without copying its payload.
+    ///
+    /// Returns the parsed packet along with the remaining non-consumed bytes.
+    ///
+    /// # Errors
+    ///
+    /// Errors if
+    ///
+    /// - the provided buffer doesn't start with a valid packet
+    /// - doesn't contain enough bytes is otherwise
+    /// - has a CRC mismatch
+    /// - contains illegal values
+    pub fn parse(input: &'buf [u8]) -> Result<(Self, &'buf [u8])> {
+        let (packet_type, payload_bytes, remainder) = parse_frame(input)?;
+
+        let packet = match PacketType::try_from(packet_type)? {
+            PacketType::LidarUserCmd => {
+                let (cmd, _) = LidarUserCtrlCmd::parse(payload_bytes)?;
+                Self::LidarUserCmd(cmd.try_into()?)
+            }
+            PacketType::LidarAckData => {
+                let (data, _) = LidarAckData::parse(payload_bytes)?;
+                Self::LidarAckData(data.try_into()?)
+            }
+            PacketType::LidarPointData => {
+                let (data, _) = LidarPointDataRef::parse(payload_bytes)?;
+                Self::LidarPointData(data)
+            }
+            PacketType::Lidar2DPointData => Self::Lidar2DPointData(payload_bytes),
+            PacketType::LidarImuData => {
+                let (data, _) = LidarImuData::parse(payload_bytes)?;
+                Self::LidarImuData(data)
+            }
+            PacketType::LidarVersion => {
+                let (data, _) = VersionRef::parse(payload_bytes)?;
+                Self::LidarVersion(data)
+            }
+            PacketType::LidarTimeStamp => Self::LidarTimeStamp(payload_bytes),
+            PacketType::LidarWorkModeConfig => {
+                let (config, _) = LidarWorkModeConfig::parse(payload_bytes)?;
+                Self::LidarWorkModeConfig(config.try_into()?)
+            }
+            PacketType::LidarIpAddressConfig => {
+                let (config, _) = LidarIpAddressConfig::parse(payload_bytes)?;
+                Self::LidarIpAddressConfig(config.into())
+            }
+            PacketType::LidarMacAddressConfig => {
+                let (config, _) = LidarMacAddressConfig::parse(payload_bytes)?;
+                Self::LidarMacAddressConfig(config.into())
+            }
+            PacketType::LidarCommand => {
+                let (command, _) = LidarCommand::parse(payload_bytes)?;
+                Self::LidarCommand(command.try_into()?)
+            }
+            PacketType::LidarParamData => Self::LidarParamData(payload_bytes),
+            PacketType::LidarWorkMode => {
+                let (config, _) = LidarWorkModeConfig::parse(payload_bytes)?;
+                Self::LidarWorkMode(config.try_into()?)
+            }
+        };
+
+        Ok((packet, remainder))
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<PacketRef<'_>> for Packet {
+    fn from(value: PacketRef<'_>) -> Self {
+        match value {
+            PacketRef::LidarUserCmd(cmd) => Self::LidarUserCmd(cmd),
+            PacketRef::LidarAckData(ack) => Self::LidarAckData(ack),
+            PacketRef::LidarPointData(data) => Self::LidarPointData(Box::new((&data).into())),
+            PacketRef::Lidar2DPointData(raw) => Self::Lidar2DPointData(raw.to_vec()),
+            PacketRef::LidarImuData(data) => Self::LidarImuData(data),
+            PacketRef::LidarVersion(version) => Self::LidarVersion((&version).into()),
+            PacketRef::LidarTimeStamp(raw) => Self::LidarTimeStamp(raw.to_vec()),
+            PacketRef::LidarWorkModeConfig(config) => Self::LidarWorkModeConfig(config),
+            PacketRef::LidarIpAddressConfig(config) => Self::LidarIpAddressConfig(config),
+            PacketRef::LidarMacAddressConfig(config) => Self::LidarMacAddressConfig(config),
+            PacketRef::LidarCommand(command) => Self::LidarCommand(command),
+            PacketRef::LidarParamData(raw) => Self::LidarParamData(raw.to_vec()),
+            PacketRef::LidarWorkMode(mode) => Self::LidarWorkMode(mode),
+        }
+    }
+}
+
+impl Display for PacketRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PacketRef::LidarUserCmd(cmd) => write!(f, "UserCmd({cmd})"),
+            PacketRef::LidarAckData(ack) => write!(f, "AckData({ack})"),
+            PacketRef::LidarPointData(data) => write!(f, "PointData({data})"),
+            PacketRef::Lidar2DPointData(raw) => write!(f, "2DPointData({})", raw.len()),
+            PacketRef::LidarImuData(data) => write!(f, "ImuData({data})"),
+            PacketRef::LidarVersion(version) => write!(f, "Version({version})"),
+            PacketRef::LidarTimeStamp(raw) => write!(f, "TimeStamp({})", raw.len()),
+            PacketRef::LidarWorkModeConfig(config) => write!(f, "WorkModeConfig({config})"),
+            PacketRef::LidarIpAddressConfig(config) => write!(f, "IpAddressConfig({config})"),
+            PacketRef::LidarMacAddressConfig(config) => write!(f, "MacAddressConfig({config})"),
+            PacketRef::LidarCommand(command) => write!(f, "Command({command})"),
+            PacketRef::LidarParamData(raw) => write!(f, "ParamData({})", raw.len()),
+            PacketRef::LidarWorkMode(mode) => write!(f, "WorkMode({mode})"),
+        }
+    }
+}
@@ -1,20 +1,29 @@
-use std::{
-    array,
+use core::{
     fmt::{self, Display},
+    time::Duration,
 };
 
-use anyhow::{Result, bail};
-use bytes::Buf;
+#[cfg(feature = "std")]
+use bytes::BufMut;
 
-use crate::info::DataInfo;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ToUsize,
+    buf::Buf,
+    error::{ProtocolError, Result},
+    info::DataInfo,
+};
 
 /**
  * @brief Lidar calib param
  * @note 32 bytes
  */
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[repr(C)]
-#[derive(Debug)]
-pub(crate) struct LidarCalibParam {
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LidarCalibParam {
     /// unit: m
     a_axis_dist: f32,
     /// unit: m
@@ -36,13 +45,59 @@ pub(crate) struct LidarCalibParam {
 impl LidarCalibParam {
     pub(crate) const LEN: usize = size_of::<Self>();
 
+    /// vertical offset of the measurement center [m]
+    #[must_use]
+    pub fn a_axis_dist(&self) -> f32 {
+        self.a_axis_dist
+    }
+
+    /// horizontal offset of the measurement center from the rotation axis [m]
+    #[must_use]
+    pub fn b_axis_dist(&self) -> f32 {
+        self.b_axis_dist
+    }
+
+    /// offset of the horizontal angle [rad]
+    #[must_use]
+    pub fn theta_angle_bias(&self) -> f32 {
+        self.theta_angle_bias
+    }
+
+    /// offset of the vertical angle [rad]
+    #[must_use]
+    pub fn alpha_angle_bias(&self) -> f32 {
+        self.alpha_angle_bias
+    }
+
+    /// [rad]
+    #[must_use]
+    pub fn beta_angle(&self) -> f32 {
+        self.beta_angle
+    }
+
+    /// [rad]
+    #[must_use]
+    pub fn xi_angle(&self) -> f32 {
+        self.xi_angle
+    }
+
+    /// [mm]
+    #[must_use]
+    pub fn range_bias(&self) -> f32 {
+        self.range_bias
+    }
+
+    #[must_use]
+    pub fn range_scale(&self) -> f32 {
+        self.range_scale
+    }
+
     pub(crate) fn parse(bytes: &[u8]) -> Result<(Self, &[u8])> {
         let Some((mut bytes, remainder)) = bytes.split_at_checked(Self::LEN) else {
-            bail!(
-                "expected a minimum of {} bytes but got {}",
-                Self::LEN,
-                bytes.len()
-            );
+            return Err(ProtocolError::Truncated {
+                expected: Self::LEN,
+                actual: bytes.len(),
+            });
         };
 
         let a_axis_dist = bytes.get_f32_le();
@@ -72,6 +127,18 @@ impl LidarCalibParam {
             remainder,
         ))
     }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn write(&self, buf: &mut impl BufMut) {
+        buf.put_f32_le(self.a_axis_dist);
+        buf.put_f32_le(self.b_axis_dist);
+        buf.put_f32_le(self.theta_angle_bias);
+        buf.put_f32_le(self.alpha_angle_bias);
+        buf.put_f32_le(self.beta_angle);
+        buf.put_f32_le(self.xi_angle);
+        buf.put_f32_le(self.range_bias);
+        buf.put_f32_le(self.range_scale);
+    }
 }
 
 impl Display for LidarCalibParam {
@@ -84,9 +151,10 @@ impl Display for LidarCalibParam {
  * @brief Lidar Inside State
  * @note 36 bytes
  */
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[repr(C)]
-#[derive(Debug)]
-pub(crate) struct LidarInsideState {
+#[derive(Debug, Clone, Copy)]
+pub struct LidarInsideState {
     /// The speed of the horizontal low-speed motor, in revolutions per minute (r/min).
     /// Up motor rotation period
     sys_rotation_period: u32,
@@ -116,13 +184,66 @@ pub(crate) struct LidarInsideState {
 impl LidarInsideState {
     pub(crate) const LEN: usize = size_of::<Self>();
 
+    /// The speed of the horizontal low-speed motor, in revolutions per minute (r/min).
+    #[must_use]
+    pub fn sys_rotation_period(&self) -> u32 {
+        self.sys_rotation_period
+    }
+
+    /// The speed of the vertical high-speed motor, in revolutions per minute (r/min).
+    #[must_use]
+    pub fn com_rotation_period(&self) -> u32 {
+        self.com_rotation_period
+    }
+
+    /// The index of dirt on the radar's optical surface.
+    #[must_use]
+    pub fn dirty_index(&self) -> f32 {
+        self.dirty_index
+    }
+
+    /// The packet loss rate of the upper board of the radar.
+    #[must_use]
+    pub fn packet_lost_up(&self) -> f32 {
+        self.packet_lost_up
+    }
+
+    /// The packet loss rate of the lower board of the radar.
+    #[must_use]
+    pub fn packet_lost_down(&self) -> f32 {
+        self.packet_lost_down
+    }
+
+    /// The temperature of the APD, in degrees Celsius (℃).
+    #[must_use]
+    pub fn apd_temperature(&self) -> f32 {
+        self.apd_temperature
+    }
+
+    /// The voltage of the APD, in Volts (V).
+    #[must_use]
+    pub fn apd_voltage(&self) -> f32 {
+        self.apd_voltage
+    }
+
+    /// The voltage of the laser emitter, in Volts (V).
+    #[must_use]
+    pub fn laser_voltage(&self) -> f32 {
+        self.laser_voltage
+    }
+
+    /// The temperature of the IMU
+    #[must_use]
+    pub fn imu_temperature(&self) -> f32 {
+        self.imu_temperature
+    }
+
     pub(crate) fn parse(bytes: &[u8]) -> Result<(Self, &[u8])> {
         let Some((mut bytes, remainder)) = bytes.split_at_checked(Self::LEN) else {
-            bail!(
-                "expected a minimum of {} bytes but got {}",
-                Self::LEN,
-                bytes.len()
-            );
+            return Err(ProtocolError::Truncated {
+                expected: Self::LEN,
+                actual: bytes.len(),
+            });
         };
 
         let sys_rotation_period = bytes.get_u32_le();
@@ -154,6 +275,19 @@ impl LidarInsideState {
             remainder,
         ))
     }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn write(&self, buf: &mut impl BufMut) {
+        buf.put_u32_le(self.sys_rotation_period);
+        buf.put_u32_le(self.com_rotation_period);
+        buf.put_f32_le(self.dirty_index);
+        buf.put_f32_le(self.packet_lost_up);
+        buf.put_f32_le(self.packet_lost_down);
+        buf.put_f32_le(self.apd_temperature);
+        buf.put_f32_le(self.apd_voltage);
+        buf.put_f32_le(self.laser_voltage);
+        buf.put_f32_le(self.imu_temperature);
+    }
 }
 
 impl Display for LidarInsideState {
@@ -166,9 +300,13 @@ impl Display for LidarInsideState {
  * @brief Lidar Point Data
  * @note 1020 bytes
  */
+#[cfg_attr(
+    all(feature = "serde", feature = "std"),
+    derive(Serialize, Deserialize),
+    serde(into = "PointDataSchema", try_from = "PointDataSchema")
+)]
 #[repr(C)]
-#[derive(Debug)]
-
+#[derive(Debug, Clone)]
 pub struct LidarPointData {
     /// Packet Info
     info: DataInfo,
@@ -207,13 +345,177 @@ pub struct LidarPointData {
 impl LidarPointData {
     pub(crate) const LEN: usize = size_of::<Self>();
 
+    /// maximum number of points per packet
+    pub(crate) const POINTS: usize = 300;
+
+    /// packet sequence id, consecutively increasing
+    #[must_use]
+    pub fn seq(&self) -> u32 {
+        self.info.seq
+    }
+
+    /// timestamp of the measurement, as reported by the lidar
+    #[must_use]
+    pub fn stamp(&self) -> Duration {
+        self.info.stamp()
+    }
+
+    /// state of the lidar at the time of the measurement
+    #[must_use]
+    pub fn state(&self) -> LidarInsideState {
+        self.state
+    }
+
+    /// calibration of the lidar, needed to compute the position of the points
+    #[must_use]
+    pub fn param(&self) -> LidarCalibParam {
+        self.param
+    }
+
+    /// Horizontal Start Angle
+    #[must_use]
+    pub fn com_horizontal_angle_start(&self) -> f32 {
+        self.com_horizontal_angle_start
+    }
+
+    /// Horizontal Angle Step
+    #[must_use]
+    pub fn com_horizontal_angle_step(&self) -> f32 {
+        self.com_horizontal_angle_step
+    }
+
+    /// Scan period [second]
+    #[must_use]
+    pub fn scan_period(&self) -> f32 {
+        self.scan_period
+    }
+
+    /// Minimum range value [m]
+    #[must_use]
+    pub fn range_min(&self) -> f32 {
+        self.range_min
+    }
+
+    /// Maximum range value [m]
+    #[must_use]
+    pub fn range_max(&self) -> f32 {
+        self.range_max
+    }
+
+    /// First Angle [rad]
+    #[must_use]
+    pub fn angle_min(&self) -> f32 {
+        self.angle_min
+    }
+
+    /// Angle Step [rad]
+    #[must_use]
+    pub fn angle_increment(&self) -> f32 {
+        self.angle_increment
+    }
+
+    /// Time step [second]
+    #[must_use]
+    pub fn time_increment(&self) -> f32 {
+        self.time_increment
+    }
+
+    /// Point Distance [mm] of all valid points
+    #[must_use]
+    pub fn ranges(&self) -> &[u16] {
+        self.ranges.split_at(point_count(self.point_num)).0
+    }
+
+    /// Point Reflect [0-255] of all valid points
+    #[must_use]
+    pub fn intensities(&self) -> &[u8] {
+        self.intensities.split_at(point_count(self.point_num)).0
+    }
+
+    #[cfg(feature = "std")]
     pub(crate) fn parse(bytes: &[u8]) -> Result<(Self, &[u8])> {
-        let Some((bytes, remainder)) = bytes.split_at_checked(Self::LEN) else {
-            bail!(
-                "expected a minimum of {} bytes but got {}",
-                Self::LEN,
-                bytes.len()
-            );
+        let (data, remainder) = LidarPointDataRef::parse(bytes)?;
+        Ok(((&data).into(), remainder))
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn write(&self, buf: &mut impl BufMut) {
+        self.info.write(buf);
+        self.state.write(buf);
+        self.param.write(buf);
+        buf.put_f32_le(self.com_horizontal_angle_start);
+        buf.put_f32_le(self.com_horizontal_angle_step);
+        buf.put_f32_le(self.scan_period);
+        buf.put_f32_le(self.range_min);
+        buf.put_f32_le(self.range_max);
+        buf.put_f32_le(self.angle_min);
+        buf.put_f32_le(self.angle_increment);
+        buf.put_f32_le(self.time_increment);
+        buf.put_u32_le(self.point_num);
+        for range in self.ranges {
+            buf.put_u16_le(range);
+        }
+        buf.put_slice(&self.intensities);
+    }
+}
+
+impl Display for LidarPointData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "info:{}, points:{}",
+            self.info,
+            point_count(self.point_num)
+        )
+    }
+}
+
+/// Borrowed view of a [`LidarPointData`] packet.
+///
+/// The ranges and intensities are not copied but read lazily from the underlying buffer.
+#[derive(Debug, Clone, Copy)]
+pub struct LidarPointDataRef<'buf> {
+    /// Packet Info
+    info: DataInfo,
+
+    /// Lidar inside state
+    state: LidarInsideState,
+
+    /// Lidar calib param
+    param: LidarCalibParam,
+
+    // Line info
+    /// Horizontal Start Angle
+    com_horizontal_angle_start: f32,
+    /// Horizontal Angle Step
+    com_horizontal_angle_step: f32,
+    /// Scan period [second]
+    scan_period: f32,
+    /// Minimum range value [m]
+    range_min: f32,
+    /// Maximum range value [m]
+    range_max: f32,
+    /// First Angle [rad]
+    angle_min: f32,
+    /// Angle Step [rad]
+    angle_increment: f32,
+    /// Time step [second]
+    time_increment: f32,
+    /// Point Number
+    point_num: u32,
+    /// Point Distance [mm] (little endian)
+    ranges: &'buf [[u8; 2]],
+    /// Point Reflect [0-255]
+    intensities: &'buf [u8],
+}
+
+impl<'buf> LidarPointDataRef<'buf> {
+    pub(crate) fn parse(bytes: &'buf [u8]) -> Result<(Self, &'buf [u8])> {
+        let Some((bytes, remainder)) = bytes.split_at_checked(LidarPointData::LEN) else {
+            return Err(ProtocolError::Truncated {
+                expected: LidarPointData::LEN,
+                actual: bytes.len(),
+            });
         };
 
         let (info, bytes) = DataInfo::parse(bytes)?;
@@ -229,10 +531,11 @@ impl LidarPointData {
         let angle_increment = bytes.get_f32_le();
         let time_increment = bytes.get_f32_le();
         let point_num = bytes.get_u32_le();
-        let ranges = array::from_fn(|_| bytes.get_u16_le());
-        let intensities = array::from_fn(|_| bytes.get_u8());
 
-        if !bytes.is_empty() {
+        let (ranges, intensities) = bytes.split_at(LidarPointData::POINTS * size_of::<u16>());
+        let (ranges, _) = ranges.as_chunks();
+
+        if intensities.len() != LidarPointData::POINTS {
             unreachable!("bytes should've been completely consumed");
         }
 
@@ -256,20 +559,250 @@ impl LidarPointData {
             remainder,
         ))
     }
+
+    /// packet sequence id, consecutively increasing
+    #[must_use]
+    pub fn seq(&self) -> u32 {
+        self.info.seq
+    }
+
+    /// timestamp of the measurement, as reported by the lidar
+    #[must_use]
+    pub fn stamp(&self) -> Duration {
+        self.info.stamp()
+    }
+
+    /// state of the lidar at the time of the measurement
+    #[must_use]
+    pub fn state(&self) -> LidarInsideState {
+        self.state
+    }
+
+    /// calibration of the lidar, needed to compute the position of the points
+    #[must_use]
+    pub fn param(&self) -> LidarCalibParam {
+        self.param
+    }
+
+    /// Horizontal Start Angle
+    #[must_use]
+    pub fn com_horizontal_angle_start(&self) -> f32 {
+        self.com_horizontal_angle_start
+    }
+
+    /// Horizontal Angle Step
+    #[must_use]
+    pub fn com_horizontal_angle_step(&self) -> f32 {
+        self.com_horizontal_angle_step
+    }
+
+    /// Scan period [second]
+    #[must_use]
+    pub fn scan_period(&self) -> f32 {
+        self.scan_period
+    }
+
+    /// Minimum range value [m]
+    #[must_use]
+    pub fn range_min(&self) -> f32 {
+        self.range_min
+    }
+
+    /// Maximum range value [m]
+    #[must_use]
+    pub fn range_max(&self) -> f32 {
+        self.range_max
+    }
+
+    /// First Angle [rad]
+    #[must_use]
+    pub fn angle_min(&self) -> f32 {
+        self.angle_min
+    }
+
+    /// Angle Step [rad]
+    #[must_use]
+    pub fn angle_increment(&self) -> f32 {
+        self.angle_increment
+    }
+
+    /// Time step [second]
+    #[must_use]
+    pub fn time_increment(&self) -> f32 {
+        self.time_increment
+    }
+
+    /// Number of valid points
+    #[must_use]
+    pub fn len(&self) -> usize {
+        point_count(self.point_num)
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Point Distance [mm] of the point at `index`
+    #[must_use]
+    pub fn range(&self, index: usize) -> Option<u16> {
+        self.ranges
+            .get(index)
+            .filter(|_| index < self.len())
+            .map(|range| u16::from_le_bytes(*range))
+    }
+
+    /// Point Distance [mm] of all valid points
+    #[must_use]
+    pub fn ranges(&self) -> impl ExactSizeIterator<Item = u16> + use<'buf> {
+        self.ranges
+            .iter()
+            .take(self.len())
+            .map(|range| u16::from_le_bytes(*range))
+    }
+
+    /// Point Reflect [0-255] of all valid points
+    #[must_use]
+    pub fn intensities(&self) -> &'buf [u8] {
+        self.intensities.split_at(self.len()).0
+    }
 }
 
-impl Display for LidarPointData {
+impl From<&LidarPointDataRef<'_>> for LidarPointData {
+    fn from(value: &LidarPointDataRef<'_>) -> Self {
+        let mut ranges = [0; LidarPointData::POINTS];
+        for (range, bytes) in ranges.iter_mut().zip(value.ranges) {
+            *range = u16::from_le_bytes(*bytes);
+        }
+
+        let mut intensities = [0; LidarPointData::POINTS];
+        intensities.copy_from_slice(value.intensities);
+
+        Self {
+            info: value.info,
+            state: value.state,
+            param: value.param,
+            com_horizontal_angle_start: value.com_horizontal_angle_start,
+            com_horizontal_angle_step: value.com_horizontal_angle_step,
+            scan_period: value.scan_period,
+            range_min: value.range_min,
+            range_max: value.range_max,
+            angle_min: value.angle_min,
+            angle_increment: value.angle_increment,
+            time_increment: value.time_increment,
+            point_num: value.point_num,
+            ranges,
+            intensities,
+        }
+    }
+}
+
+/// Serialized form of [`LidarPointData`] which only contains the valid points
+#[cfg(all(feature = "serde", feature = "std"))]
+#[derive(Serialize, Deserialize)]
+struct PointDataSchema {
+    info: DataInfo,
+    state: LidarInsideState,
+    param: LidarCalibParam,
+    com_horizontal_angle_start: f32,
+    com_horizontal_angle_step: f32,
+    scan_period: f32,
+    range_min: f32,
+    range_max: f32,
+    angle_min: f32,
+    angle_increment: f32,
+    time_increment: f32,
+    ranges: Vec<u16>,
+    intensities: Vec<u8>,
+}
+
+#[cfg(all(feature = "serde", feature = "std"))]
+impl From<LidarPointData> for PointDataSchema {
+    fn from(value: LidarPointData) -> Self {
+        Self {
+            ranges: value.ranges().to_vec(),
+            intensities: value.intensities().to_vec(),
+            info: value.info,
+            state: value.state,
+            param: value.param,
+            com_horizontal_angle_start: value.com_horizontal_angle_start,
+            com_horizontal_angle_step: value.com_horizontal_angle_step,
+            scan_period: value.scan_period,
+            range_min: value.range_min,
+            range_max: value.range_max,
+            angle_min: value.angle_min,
+            angle_increment: value.angle_increment,
+            time_increment: value.time_increment,
+        }
+    }
+}
+
+#[cfg(all(feature = "serde", feature = "std"))]
+impl TryFrom<PointDataSchema> for LidarPointData {
+    type Error = ProtocolError;
+
+    fn try_from(value: PointDataSchema) -> Result<Self, Self::Error> {
+        let point_num = value.ranges.len();
+        if point_num != value.intensities.len() || point_num > LidarPointData::POINTS {
+            return Err(ProtocolError::InvalidPointCount {
+                ranges: point_num,
+                intensities: value.intensities.len(),
+            });
+        }
+
+        let mut ranges = [0; LidarPointData::POINTS];
+        for (range, source) in ranges.iter_mut().zip(&value.ranges) {
+            *range = *source;
+        }
+
+        let mut intensities = [0; LidarPointData::POINTS];
+        for (intensity, source) in intensities.iter_mut().zip(&value.intensities) {
+            *intensity = *source;
+        }
+
+        Ok(Self {
+            info: value.info,
+            state: value.state,
+            param: value.param,
+            com_horizontal_angle_start: value.com_horizontal_angle_start,
+            com_horizontal_angle_step: value.com_horizontal_angle_step,
+            scan_period: value.scan_period,
+            range_min: value.range_min,
+            range_max: value.range_max,
+            angle_min: value.angle_min,
+            angle_increment: value.angle_increment,
+            time_increment: value.time_increment,
+            point_num: u32::try_from(point_num)
+                .unwrap_or_else(|_overflow| unreachable!("point count should've been checked")),
+            ranges,
+            intensities,
+        })
+    }
+}
+
+impl Display for LidarPointDataRef<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{self:?}")
+        write!(
+            f,
+            "info:{}, points:{}",
+            self.info,
+            point_count(self.point_num)
+        )
     }
 }
 
+/// Number of valid points, limited to the capacity of a packet
+fn point_count(point_num: u32) -> usize {
+    point_num.to_usize().min(LidarPointData::POINTS)
+}
+
 /**
  * @brief Lidar 2D Point Data
  * @note 5512 bytes
  */
 #[repr(C)]
-pub(crate) struct Lidar2DPointData {
+#[derive(Debug, Clone)]
+pub struct Lidar2DPointData {
     /// Packet Info
     info: DataInfo,
 
@@ -299,3 +832,151 @@ pub(crate) struct Lidar2DPointData {
     /// Point Reflect Data
     intensities: [u8; 1800],
 }
+
+impl Lidar2DPointData {
+    pub(crate) const LEN: usize = size_of::<Self>();
+
+    /// maximum number of points per packet
+    pub(crate) const POINTS: usize = 1800;
+
+    /// packet sequence id, consecutively increasing
+    #[must_use]
+    pub fn seq(&self) -> u32 {
+        self.info.seq
+    }
+
+    /// timestamp of the measurement, as reported by the lidar
+    #[must_use]
+    pub fn stamp(&self) -> Duration {
+        self.info.stamp()
+    }
+
+    /// state of the lidar at the time of the measurement
+    #[must_use]
+    pub fn state(&self) -> LidarInsideState {
+        self.state
+    }
+
+    /// calibration of the lidar
+    #[must_use]
+    pub fn param(&self) -> LidarCalibParam {
+        self.param
+    }
+
+    /// Scan period [second]
+    #[must_use]
+    pub fn scan_period(&self) -> f32 {
+        self.scan_period
+    }
+
+    /// Minimum range value [m]
+    #[must_use]
+    pub fn range_min(&self) -> f32 {
+        self.range_min
+    }
+
+    /// Maximum range value [m]
+    #[must_use]
+    pub fn range_max(&self) -> f32 {
+        self.range_max
+    }
+
+    /// First Angle [rad]
+    #[must_use]
+    pub fn angle_min(&self) -> f32 {
+        self.angle_min
+    }
+
+    /// Angle Step [rad]
+    #[must_use]
+    pub fn angle_increment(&self) -> f32 {
+        self.angle_increment
+    }
+
+    /// Time step [second]
+    #[must_use]
+    pub fn time_increment(&self) -> f32 {
+        self.time_increment
+    }
+
+    /// Point Distance [mm] of all valid points
+    #[must_use]
+    pub fn ranges(&self) -> &[u16] {
+        self.ranges.split_at(point_count_2d(self.point_num)).0
+    }
+
+    /// Point Reflect [0-255] of all valid points
+    #[must_use]
+    pub fn intensities(&self) -> &[u8] {
+        self.intensities.split_at(point_count_2d(self.point_num)).0
+    }
+
+    pub(crate) fn parse(bytes: &[u8]) -> Result<(Self, &[u8])> {
+        let Some((bytes, remainder)) = bytes.split_at_checked(Self::LEN) else {
+            return Err(ProtocolError::Truncated {
+                expected: Self::LEN,
+                actual: bytes.len(),
+            });
+        };
+
+        let (info, bytes) = DataInfo::parse(bytes)?;
+        let (state, bytes) = LidarInsideState::parse(bytes)?;
+        let (param, mut bytes) = LidarCalibParam::parse(bytes)?;
+
+        let scan_period = bytes.get_f32_le();
+        let range_min = bytes.get_f32_le();
+        let range_max = bytes.get_f32_le();
+        let angle_min = bytes.get_f32_le();
+        let angle_increment = bytes.get_f32_le();
+        let time_increment = bytes.get_f32_le();
+        let point_num = bytes.get_u32_le();
+
+        let mut ranges = [0; Self::POINTS];
+        for range in &mut ranges {
+            *range = bytes.get_u16_le();
+        }
+        let intensities = *bytes.get_chunk::<{ Self::POINTS }>();
+
+        if !bytes.is_empty() {
+            unreachable!("bytes should've been completely consumed");
+        }
+
+        Ok((
+            Self {
+                info,
+                state,
+                param,
+                scan_period,
+                range_min,
+                range_max,
+                angle_min,
+                angle_increment,
+                time_increment,
+                point_num,
+                ranges,
+                intensities,
+            },
+            remainder,
+        ))
+    }
+}
+
+impl TryFrom<&[u8]> for Lidar2DPointData {
+    type Error = ProtocolError;
+
+    /// Decodes the payload of a `Packet::Lidar2DPointData`, which is kept undecoded.
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        Self::parse(value).map(|(data, _)| data)
+    }
+}
+
+impl Display for Lidar2DPointData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// Number of valid points of a 2D packet, limited to the capacity of a packet
+fn point_count_2d(point_num: u32) -> usize {
+    point_num.to_usize().min(Lidar2DPointData::POINTS)
+}
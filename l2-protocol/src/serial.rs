@@ -0,0 +1,76 @@
+use std::{
+    pin::Pin,
+    task::{Context as TaskContext, Poll},
+};
+
+use anyhow::{Context, Result};
+use futures_core::Stream;
+use futures_sink::Sink;
+use tokio_serial::{SerialPortBuilderExt, SerialStream};
+use tokio_util::codec::Framed;
+
+use crate::{
+    Packet,
+    codec::{CodecStats, PacketCodec},
+};
+
+/// Baud rate used by the lidar's USB serial interface
+pub const DEFAULT_BAUD_RATE: u32 = 4_000_000;
+
+/// Receives packets from and sends packets to a lidar connected via its serial interface.
+///
+/// Incoming packets are provided through [`Stream`], commands can be sent through [`Sink`].
+#[derive(Debug)]
+pub struct SerialSource {
+    framed: Framed<SerialStream, PacketCodec>,
+}
+
+impl SerialSource {
+    /// Opens the serial port at `path`, e.g. `/dev/ttyACM0`.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the port cannot be opened.
+    pub fn open(path: &str, baud_rate: u32) -> Result<Self> {
+        let port = tokio_serial::new(path, baud_rate)
+            .open_native_async()
+            .with_context(|| format!("failed to open serial port {path}"))?;
+
+        Ok(Self {
+            framed: Framed::new(port, PacketCodec::new()),
+        })
+    }
+
+    #[must_use]
+    pub fn stats(&self) -> &CodecStats {
+        self.framed.codec().stats()
+    }
+}
+
+impl Stream for SerialSource {
+    type Item = Result<Packet>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.framed).poll_next(cx)
+    }
+}
+
+impl Sink<Packet> for SerialSource {
+    type Error = anyhow::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Result<()>> {
+        Pin::new(&mut self.framed).poll_ready(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Packet) -> Result<()> {
+        Pin::new(&mut self.framed).start_send(item)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Result<()>> {
+        Pin::new(&mut self.framed).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Result<()>> {
+        Pin::new(&mut self.framed).poll_close(cx)
+    }
+}
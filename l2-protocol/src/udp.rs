@@ -0,0 +1,98 @@
+use std::{
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    pin::Pin,
+    task::{Context as TaskContext, Poll},
+};
+
+use anyhow::{Context, Result};
+use futures_core::Stream;
+use futures_sink::Sink;
+use tokio::net::UdpSocket;
+use tokio_util::udp::UdpFramed;
+
+use crate::{
+    Packet,
+    codec::{CodecStats, PacketCodec},
+};
+
+/// Factory default address of the lidar
+pub const DEFAULT_LIDAR_ADDR: SocketAddr =
+    SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 62), 6101));
+
+/// Factory default address the lidar sends its data to
+pub const DEFAULT_USER_ADDR: SocketAddr =
+    SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 2), 6201));
+
+/// Receives packets from and sends packets to a lidar connected via ethernet.
+///
+/// Incoming packets are provided through [`Stream`], commands can be sent through [`Sink`].
+#[derive(Debug)]
+pub struct UdpSource {
+    framed: UdpFramed<PacketCodec>,
+    lidar_addr: SocketAddr,
+}
+
+impl UdpSource {
+    /// Binds to `local_addr` and exchanges packets with the lidar at `lidar_addr`.
+    ///
+    /// Datagrams from other peers are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the socket cannot be bound or connected.
+    pub async fn bind(local_addr: SocketAddr, lidar_addr: SocketAddr) -> Result<Self> {
+        let socket = UdpSocket::bind(local_addr)
+            .await
+            .with_context(|| format!("failed to bind to {local_addr}"))?;
+        socket
+            .connect(lidar_addr)
+            .await
+            .with_context(|| format!("failed to connect to {lidar_addr}"))?;
+
+        Ok(Self {
+            framed: UdpFramed::new(socket, PacketCodec::new()),
+            lidar_addr,
+        })
+    }
+
+    #[must_use]
+    pub fn lidar_addr(&self) -> SocketAddr {
+        self.lidar_addr
+    }
+
+    #[must_use]
+    pub fn stats(&self) -> &CodecStats {
+        self.framed.codec().stats()
+    }
+}
+
+impl Stream for UdpSource {
+    type Item = Result<Packet>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.framed)
+            .poll_next(cx)
+            .map(|item| item.map(|result| result.map(|(packet, _)| packet)))
+    }
+}
+
+impl Sink<Packet> for UdpSource {
+    type Error = anyhow::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Result<()>> {
+        Pin::new(&mut self.framed).poll_ready(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Packet) -> Result<()> {
+        let lidar_addr = self.lidar_addr;
+        Pin::new(&mut self.framed).start_send((item, lidar_addr))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Result<()>> {
+        Pin::new(&mut self.framed).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Result<()>> {
+        Pin::new(&mut self.framed).poll_close(cx)
+    }
+}
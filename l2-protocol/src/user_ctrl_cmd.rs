@@ -1,8 +1,22 @@
-use std::fmt::{self, Display};
+use core::fmt::{self, Display};
 
-use anyhow::{Result, bail};
-use bytes::Buf;
+#[cfg(feature = "std")]
+use bytes::BufMut;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    buf::Buf,
+    error::{ProtocolError, Result},
+};
+
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UserCmd {
     ResetType(u32),
     StandbyType(StandbyType),
@@ -24,7 +38,7 @@ impl UserCmd {
 }
 
 impl TryFrom<LidarUserCtrlCmd> for UserCmd {
-    type Error = anyhow::Error;
+    type Error = ProtocolError;
 
     fn try_from(cmd: LidarUserCtrlCmd) -> Result<Self, Self::Error> {
         (cmd.cmd_type, cmd.cmd_value).try_into()
@@ -32,7 +46,7 @@ impl TryFrom<LidarUserCtrlCmd> for UserCmd {
 }
 
 impl TryFrom<(u32, u32)> for UserCmd {
-    type Error = anyhow::Error;
+    type Error = ProtocolError;
 
     fn try_from((typ, value): (u32, u32)) -> Result<Self, Self::Error> {
         match typ {
@@ -43,7 +57,26 @@ impl TryFrom<(u32, u32)> for UserCmd {
             Self::CONFIG_RESET => Ok(Self::ConfigReset(value)),
             Self::CONFIG_GET => Ok(Self::ConfigGet(value)),
             Self::CONFIG_AUTO_STANDBY => Ok(Self::ConfigAutoStandby(value)),
-            unknown => bail!("unknown command type: {unknown}"),
+            unknown => Err(ProtocolError::UnknownCommandType(unknown)),
+        }
+    }
+}
+
+impl From<&UserCmd> for LidarUserCtrlCmd {
+    fn from(cmd: &UserCmd) -> Self {
+        let (cmd_type, cmd_value) = match cmd {
+            UserCmd::ResetType(value) => (UserCmd::RESET_TYPE, *value),
+            UserCmd::StandbyType(value) => (UserCmd::STANDBY_TYPE, value.into()),
+            UserCmd::VersionGet(value) => (UserCmd::VERSION_GET, *value),
+            UserCmd::LatencyType(value) => (UserCmd::LATENCY_TYPE, *value),
+            UserCmd::ConfigReset(value) => (UserCmd::CONFIG_RESET, *value),
+            UserCmd::ConfigGet(value) => (UserCmd::CONFIG_GET, *value),
+            UserCmd::ConfigAutoStandby(value) => (UserCmd::CONFIG_AUTO_STANDBY, *value),
+        };
+
+        Self {
+            cmd_type,
+            cmd_value,
         }
     }
 }
@@ -62,19 +95,34 @@ impl Display for UserCmd {
     }
 }
 
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StandbyType {
     Start = 0,
     Standby = 1,
 }
 
 impl TryFrom<u32> for StandbyType {
-    type Error = anyhow::Error;
+    type Error = ProtocolError;
 
     fn try_from(value: u32) -> Result<Self, Self::Error> {
         match value {
             0 => Ok(Self::Start),
             1 => Ok(Self::Standby),
-            unknown => bail!("unknown standby mode: {unknown}"),
+            unknown => Err(ProtocolError::UnknownStandbyMode(unknown)),
+        }
+    }
+}
+
+impl From<&StandbyType> for u32 {
+    fn from(value: &StandbyType) -> Self {
+        match value {
+            StandbyType::Start => 0,
+            StandbyType::Standby => 1,
         }
     }
 }
@@ -96,8 +144,8 @@ impl Display for StandbyType {
 #[repr(C)]
 pub struct LidarUserCtrlCmd {
     ///   0:null, 1:standby
-    cmd_type: u32,
-    cmd_value: u32,
+    pub(crate) cmd_type: u32,
+    pub(crate) cmd_value: u32,
 }
 
 impl LidarUserCtrlCmd {
@@ -105,11 +153,10 @@ impl LidarUserCtrlCmd {
 
     pub(crate) fn parse(bytes: &[u8]) -> Result<(Self, &[u8])> {
         let Some((mut bytes, remainder)) = bytes.split_at_checked(Self::LEN) else {
-            bail!(
-                "expected a minimum of {} bytes but got {}",
-                Self::LEN,
-                bytes.len()
-            );
+            return Err(ProtocolError::Truncated {
+                expected: Self::LEN,
+                actual: bytes.len(),
+            });
         };
 
         let cmd_type = bytes.get_u32_le();
@@ -127,6 +174,12 @@ impl LidarUserCtrlCmd {
             remainder,
         ))
     }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn write(&self, buf: &mut impl BufMut) {
+        buf.put_u32_le(self.cmd_type);
+        buf.put_u32_le(self.cmd_value);
+    }
 }
 
 impl Display for LidarUserCtrlCmd {
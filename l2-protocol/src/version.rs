@@ -1,23 +1,235 @@
-use std::{
+use core::{
     fmt::{self, Display},
-    io::Read,
+    str::FromStr,
 };
 
-use anyhow::{Context, Ok, Result, bail};
+#[cfg(feature = "std")]
+use bytes::BufMut;
 
+#[cfg(all(feature = "serde", feature = "std"))]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    buf::Buf,
+    error::{ProtocolError, Result},
+};
+
+/// A hardware or software version like `1.2.0.3`, ordered component by component
+#[cfg_attr(
+    all(feature = "serde", feature = "std"),
+    derive(Serialize, Deserialize),
+    serde(transparent)
+)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VersionNumber(pub [u8; 4]);
+
+impl VersionNumber {
+    #[must_use]
+    pub const fn new(major: u8, minor: u8, patch: u8, build: u8) -> Self {
+        Self([major, minor, patch, build])
+    }
+}
+
+impl Display for VersionNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [major, minor, patch, build] = self.0;
+        write!(f, "{major}.{minor}.{patch}.{build}")
+    }
+}
+
+/// Parses up to 4 dot-separated components; missing ones are 0, e.g. `1.2` is `1.2.0.0`
+impl FromStr for VersionNumber {
+    type Err = ProtocolError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut version = [0; 4];
+        let mut parts = value.split('.');
+        for (index, component) in version.iter_mut().enumerate() {
+            match parts.next() {
+                Some(part) => {
+                    *component = part
+                        .parse()
+                        .map_err(|_error| ProtocolError::InvalidVersion)?;
+                }
+                None if index > 0 => break,
+                None => return Err(ProtocolError::InvalidVersion),
+            }
+        }
+        if parts.next().is_some() {
+            return Err(ProtocolError::InvalidVersion);
+        }
+        Ok(Self(version))
+    }
+}
+
+/// The date the firmware has been compiled on.
+///
+/// The lidar reports it as the ASCII digits `YYMMDD` of a year in the 2000s.
+#[cfg_attr(
+    all(feature = "serde", feature = "std"),
+    derive(Serialize, Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CompileDate {
+    year: u16,
+    month: u8,
+    day: u8,
+}
+
+impl CompileDate {
+    /// Fails for dates that don't exist or can't be reported by the lidar.
+    ///
+    /// # Errors
+    ///
+    /// [`ProtocolError::InvalidDate`] if the year isn't in `2000..=2099`, the month isn't in
+    /// `1..=12` or the day doesn't exist in the month.
+    pub fn new(year: u16, month: u8, day: u8) -> Result<Self> {
+        let leap =
+            year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+        let days = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if leap => 29,
+            2 => 28,
+            _ => return Err(ProtocolError::InvalidDate),
+        };
+        if !(2000..=2099).contains(&year) || !(1..=days).contains(&day) {
+            return Err(ProtocolError::InvalidDate);
+        }
+        Ok(Self { year, month, day })
+    }
+
+    #[must_use]
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    #[must_use]
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    #[must_use]
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    /// Parses the `YYMMDD` digits the lidar reports; the remaining bytes are ignored
+    fn parse(date: [u8; 8]) -> Result<Self> {
+        let [y1, y2, m1, m2, d1, d2, ..] = date;
+        let number = |tens: u8, ones: u8| {
+            if tens.is_ascii_digit() && ones.is_ascii_digit() {
+                Ok((tens - b'0') * 10 + (ones - b'0'))
+            } else {
+                Err(ProtocolError::InvalidDate)
+            }
+        };
+        Self::new(
+            2000 + u16::from(number(y1, y2)?),
+            number(m1, m2)?,
+            number(d1, d2)?,
+        )
+    }
+
+    /// The `YYMMDD` digits the lidar reports, padded with zeros
+    #[cfg(feature = "std")]
+    fn to_bytes(self) -> [u8; 8] {
+        let digits = |value: u16| {
+            let value = (value % 100) as u8;
+            [b'0' + value / 10, b'0' + value % 10]
+        };
+        let [y1, y2] = digits(self.year);
+        let [m1, m2] = digits(self.month.into());
+        let [d1, d2] = digits(self.day.into());
+        [y1, y2, m1, m2, d1, d2, 0, 0]
+    }
+}
+
+impl Display for CompileDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// Parses `YYYY-MM-DD`
+impl FromStr for CompileDate {
+    type Err = ProtocolError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut parts = value.splitn(3, '-');
+        let mut next = || parts.next().ok_or(ProtocolError::InvalidDate);
+        let year = next()?
+            .parse()
+            .map_err(|_error| ProtocolError::InvalidDate)?;
+        let month = next()?
+            .parse()
+            .map_err(|_error| ProtocolError::InvalidDate)?;
+        let day = next()?
+            .parse()
+            .map_err(|_error| ProtocolError::InvalidDate)?;
+        Self::new(year, month, day)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<CompileDate> for String {
+    fn from(value: CompileDate) -> Self {
+        value.to_string()
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<String> for CompileDate {
+    type Error = ProtocolError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Version {
     /// hardware version
-    hardware: [u8; 4],
+    hardware: VersionNumber,
     /// software version
-    software: [u8; 4],
+    software: VersionNumber,
     /// device name
     name: String,
-    /// device compile date
-    date: String,
+    /// device compile date; `None` if the lidar doesn't report it as `YYMMDD`
+    date: Option<CompileDate>,
 }
 
+#[cfg(feature = "std")]
+impl Version {
+    #[must_use]
+    pub fn hardware(&self) -> VersionNumber {
+        self.hardware
+    }
+
+    /// version of the firmware
+    #[must_use]
+    pub fn software(&self) -> VersionNumber {
+        self.software
+    }
+
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// `None` if the lidar reports a date that isn't `YYMMDD`
+    #[must_use]
+    pub fn date(&self) -> Option<CompileDate> {
+        self.date
+    }
+}
+
+#[cfg(feature = "std")]
 impl TryFrom<LidarVersionData> for Version {
-    type Error = anyhow::Error;
+    type Error = ProtocolError;
 
     fn try_from(value: LidarVersionData) -> Result<Self, Self::Error> {
         let LidarVersionData {
@@ -28,63 +240,155 @@ impl TryFrom<LidarVersionData> for Version {
             reserve: _,
         } = value;
 
-        // let hardware = format!(
-        //     "{}.{}.{}.{}",
-        //     hw_version[0], hw_version[1], hw_version[2], hw_version[3]
-        // );
+        Ok(Self {
+            hardware: VersionNumber(hw_version),
+            software: VersionNumber(sw_version),
+            name: parse_name(&name)?.to_owned(),
+            date: CompileDate::parse(date).ok(),
+        })
+    }
+}
 
-        // let software = format!(
-        //     "{}.{}.{}.{}",
-        //     sw_version[0], sw_version[1], sw_version[2], sw_version[3]
-        // );
+#[cfg(feature = "std")]
+impl From<&VersionRef<'_>> for Version {
+    fn from(value: &VersionRef<'_>) -> Self {
+        Self {
+            hardware: value.hardware,
+            software: value.software,
+            name: value.name.to_owned(),
+            date: value.date,
+        }
+    }
+}
 
-        let mut name = name.as_slice();
-        while let [rest @ .., last] = name {
-            if *last == 0 {
-                name = rest;
-            } else {
-                break;
-            }
+/// Borrowed view of a [`Version`] packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VersionRef<'buf> {
+    /// hardware version
+    hardware: VersionNumber,
+    /// software version
+    software: VersionNumber,
+    /// device name
+    name: &'buf str,
+    /// device compile date; `None` if the lidar doesn't report it as `YYMMDD`
+    date: Option<CompileDate>,
+}
+
+impl<'buf> VersionRef<'buf> {
+    pub(crate) fn parse(bytes: &'buf [u8]) -> Result<(Self, &'buf [u8])> {
+        let Some((mut bytes, remainder)) = bytes.split_at_checked(LidarVersionData::LEN) else {
+            return Err(ProtocolError::Truncated {
+                expected: LidarVersionData::LEN,
+                actual: bytes.len(),
+            });
+        };
+
+        let hardware = bytes.get_chunk::<4>();
+        let software = bytes.get_chunk::<4>();
+        let name = bytes.get_chunk::<24>();
+        let date = bytes.get_chunk::<8>();
+
+        Ok((
+            Self {
+                hardware: VersionNumber(*hardware),
+                software: VersionNumber(*software),
+                name: parse_name(name)?,
+                date: CompileDate::parse(*date).ok(),
+            },
+            remainder,
+        ))
+    }
+
+    #[must_use]
+    pub fn hardware(&self) -> VersionNumber {
+        self.hardware
+    }
+
+    /// version of the firmware
+    #[must_use]
+    pub fn software(&self) -> VersionNumber {
+        self.software
+    }
+
+    #[must_use]
+    pub fn name(&self) -> &'buf str {
+        self.name
+    }
+
+    /// `None` if the lidar reports a date that isn't `YYMMDD`
+    #[must_use]
+    pub fn date(&self) -> Option<CompileDate> {
+        self.date
+    }
+}
+
+impl Display for VersionRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "hw:{}, sw:{}, name:'{}', compiled:",
+            self.hardware, self.software, self.name
+        )?;
+        write_date(f, self.date)
+    }
+}
+
+/// Writes the compile date in quotes or `unknown`
+fn write_date(formatter: &mut fmt::Formatter<'_>, date: Option<CompileDate>) -> fmt::Result {
+    match date {
+        Some(date) => write!(formatter, "'{date}'"),
+        None => write!(formatter, "unknown"),
+    }
+}
+
+/// Strips the zero-padding from the device name
+fn parse_name(mut name: &[u8]) -> Result<&str> {
+    while let [rest @ .., last] = name {
+        if *last == 0 {
+            name = rest;
+        } else {
+            break;
         }
-        let name =
-            String::from_utf8(name.to_vec()).context("device name contained invalid utf-8")?;
+    }
+    str::from_utf8(name).map_err(ProtocolError::InvalidDeviceName)
+}
 
-        // TODO add some sanity checks
-        let date = format!(
-            "20{}{}-{}{}-{}{}",
-            char::from(date[0]),
-            char::from(date[1]),
-            char::from(date[2]),
-            char::from(date[3]),
-            char::from(date[4]),
-            char::from(date[5])
-        );
+#[cfg(feature = "std")]
+impl TryFrom<&Version> for LidarVersionData {
+    type Error = ProtocolError;
+
+    fn try_from(value: &Version) -> Result<Self, Self::Error> {
+        let Version {
+            hardware,
+            software,
+            name: name_str,
+            date,
+        } = value;
+
+        let mut name = [0; 24];
+        name.get_mut(..name_str.len())
+            .ok_or(ProtocolError::DeviceNameTooLong)?
+            .copy_from_slice(name_str.as_bytes());
 
         Ok(Self {
-            hardware: hw_version,
-            software: sw_version,
+            hw_version: hardware.0,
+            sw_version: software.0,
             name,
-            date,
+            date: date.map_or([0; 8], CompileDate::to_bytes),
+            reserve: [0; 40],
         })
     }
 }
 
+#[cfg(feature = "std")]
 impl Display for Version {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "hw:{}.{}.{}.{}, sw:{}.{}.{}.{}, name:'{}', compiled:'{}'",
-            self.hardware[0],
-            self.hardware[1],
-            self.hardware[2],
-            self.hardware[3],
-            self.software[0],
-            self.software[1],
-            self.software[2],
-            self.software[3],
-            self.name,
-            self.date
-        )
+            "hw:{}, sw:{}, name:'{}', compiled:",
+            self.hardware, self.software, self.name
+        )?;
+        write_date(f, self.date)
     }
 }
 
@@ -94,7 +398,7 @@ impl Display for Version {
  */
 #[repr(C)]
 #[derive(Debug)]
-pub struct LidarVersionData {
+pub(crate) struct LidarVersionData {
     /// hardware version
     hw_version: [u8; 4],
     /// software version
@@ -109,35 +413,20 @@ pub struct LidarVersionData {
 impl LidarVersionData {
     pub(crate) const LEN: usize = size_of::<Self>();
 
+    #[cfg(feature = "std")]
     pub(crate) fn parse(bytes: &[u8]) -> Result<(Self, &[u8])> {
         let Some((mut bytes, remainder)) = bytes.split_at_checked(Self::LEN) else {
-            bail!(
-                "expected a minimum of {} bytes but got {}",
-                Self::LEN,
-                bytes.len()
-            );
+            return Err(ProtocolError::Truncated {
+                expected: Self::LEN,
+                actual: bytes.len(),
+            });
         };
 
-        let mut hw_version = [0; 4];
-        bytes
-            .read_exact(&mut hw_version)
-            .context("failed to read hw_version")?;
-
-        let mut sw_version = [0; 4];
-        bytes
-            .read_exact(&mut sw_version)
-            .context("failed to read sw_version")?;
-
-        let mut name = [0; 24];
-        bytes.read_exact(&mut name).context("failed to read name")?;
-
-        let mut date = [0; 8];
-        bytes.read_exact(&mut date).context("failed to read date")?;
-
-        let mut reserve = [0; 40];
-        bytes
-            .read_exact(&mut reserve)
-            .context("failed to read reserve")?;
+        let hw_version = *bytes.get_chunk();
+        let sw_version = *bytes.get_chunk();
+        let name = *bytes.get_chunk();
+        let date = *bytes.get_chunk();
+        let reserve = *bytes.get_chunk();
 
         if !bytes.is_empty() {
             unreachable!("bytes should've been completely consumed");
@@ -154,6 +443,15 @@ impl LidarVersionData {
             remainder,
         ))
     }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn write(&self, buf: &mut impl BufMut) {
+        buf.put_slice(&self.hw_version);
+        buf.put_slice(&self.sw_version);
+        buf.put_slice(&self.name);
+        buf.put_slice(&self.date);
+        buf.put_slice(&self.reserve);
+    }
 }
 
 impl Display for LidarVersionData {
@@ -161,3 +459,137 @@ impl Display for LidarVersionData {
         write!(f, "{self:?}")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compile_date_leap_years() {
+        assert!(CompileDate::new(2024, 2, 29).is_ok(), "2024 is a leap year");
+        assert!(CompileDate::new(2000, 2, 29).is_ok(), "2000 is a leap year");
+        assert_eq!(
+            CompileDate::new(2023, 2, 29),
+            Err(ProtocolError::InvalidDate),
+            "2023 isn't a leap year"
+        );
+        assert_eq!(
+            CompileDate::parse(*b"230229\0\0"),
+            Err(ProtocolError::InvalidDate),
+            "2023 isn't a leap year"
+        );
+        assert_eq!(
+            CompileDate::parse(*b"240229\0\0"),
+            CompileDate::new(2024, 2, 29),
+            "2024 is a leap year"
+        );
+    }
+
+    #[test]
+    fn compile_date_invalid() {
+        assert_eq!(
+            CompileDate::new(2024, 13, 1),
+            Err(ProtocolError::InvalidDate),
+            "there is no 13th month"
+        );
+        assert_eq!(
+            CompileDate::parse(*b"241301\0\0"),
+            Err(ProtocolError::InvalidDate),
+            "there is no 13th month"
+        );
+        assert_eq!(
+            CompileDate::new(2024, 4, 31),
+            Err(ProtocolError::InvalidDate),
+            "April has 30 days"
+        );
+        assert_eq!(
+            CompileDate::new(1999, 1, 1),
+            Err(ProtocolError::InvalidDate),
+            "years before 2000 can't be reported"
+        );
+        assert_eq!(
+            CompileDate::parse(*b"24-1-01\0"),
+            Err(ProtocolError::InvalidDate),
+            "dates need to consist of digits"
+        );
+        assert_eq!(
+            CompileDate::parse([0; 8]),
+            Err(ProtocolError::InvalidDate),
+            "a missing date is invalid"
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn compile_date_round_trip() {
+        let bytes = *b"250704\0\0";
+        let date = CompileDate::parse(bytes);
+        assert_eq!(
+            date,
+            CompileDate::new(2025, 7, 4),
+            "digits should be parsed"
+        );
+        assert_eq!(
+            date.clone().map(CompileDate::to_bytes),
+            Ok(bytes),
+            "digits should round-trip"
+        );
+        assert_eq!(
+            "2025-07-04".parse(),
+            date,
+            "the display format should round-trip"
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn version_keeps_numbers_of_invalid_date() {
+        let data = LidarVersionData {
+            hw_version: [1, 2, 3, 4],
+            sw_version: [5, 6, 7, 8],
+            name: [0; 24],
+            date: *b"v1.0\0\0\0\0",
+            reserve: [0; 40],
+        };
+        let version = Version::try_from(data);
+        assert_eq!(
+            version.as_ref().map(Version::software),
+            Ok(VersionNumber::new(5, 6, 7, 8)),
+            "an invalid date shouldn't discard the version"
+        );
+        assert_eq!(
+            version.map(|version| version.date()),
+            Ok(None),
+            "an invalid date should be unknown"
+        );
+    }
+
+    #[test]
+    fn version_number_from_str() {
+        assert_eq!(
+            "1.2".parse(),
+            Ok(VersionNumber::new(1, 2, 0, 0)),
+            "missing components should be 0"
+        );
+        assert_eq!(
+            "1.2.3.4".parse(),
+            Ok(VersionNumber::new(1, 2, 3, 4)),
+            "all components should be parsed"
+        );
+        assert_eq!(
+            "1.2.3.4.5".parse::<VersionNumber>(),
+            Err(ProtocolError::InvalidVersion),
+            "more than 4 components are invalid"
+        );
+        assert_eq!(
+            "".parse::<VersionNumber>(),
+            Err(ProtocolError::InvalidVersion),
+            "an empty version is invalid"
+        );
+        assert_eq!(
+            "256.0".parse::<VersionNumber>(),
+            Err(ProtocolError::InvalidVersion),
+            "components need to fit into a byte"
+        );
+    }
+}
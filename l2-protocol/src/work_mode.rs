@@ -1,22 +1,36 @@
-use std::fmt::{self, Display};
+use core::fmt::{self, Display};
 
-use anyhow::{Result, bail};
-use bytes::Buf;
+#[cfg(feature = "std")]
+use bytes::BufMut;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    buf::Buf,
+    error::{ProtocolError, Result},
+};
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[expect(
     clippy::struct_excessive_bools,
     reason = "this represents a configuration bit-field"
 )]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct WorkMode {
-    wide_angle: bool,
-    measure_2d: bool,
-    disable_imu: bool,
-    serial_mode: bool,
-    wait_start: bool,
+    /// wide-angle FOV (192°) instead of the standard FOV (180°)
+    pub wide_angle: bool,
+    /// 2D instead of 3D measurement
+    pub measure_2d: bool,
+    pub disable_imu: bool,
+    /// serial instead of ethernet interface
+    pub serial_mode: bool,
+    /// wait for the start command after power-on instead of starting automatically
+    pub wait_start: bool,
 }
 
 impl TryFrom<LidarWorkModeConfig> for WorkMode {
-    type Error = anyhow::Error;
+    type Error = ProtocolError;
 
     fn try_from(value: LidarWorkModeConfig) -> Result<Self, Self::Error> {
         let LidarWorkModeConfig { mode: flags } = value;
@@ -26,7 +40,7 @@ impl TryFrom<LidarWorkModeConfig> for WorkMode {
         // 5-31	Reserved	Reserved	Reserved
 
         if flags & 0b1111_1111_1111_1111_1111_1111_1110_0000 != 0 {
-            bail!("unknown mode flags: {value}")
+            return Err(ProtocolError::UnknownModeFlags(flags));
         }
 
         // Bit 0: Switch between standard FOV and wide-angle FOV
@@ -65,6 +79,26 @@ impl TryFrom<LidarWorkModeConfig> for WorkMode {
     }
 }
 
+impl From<&WorkMode> for LidarWorkModeConfig {
+    fn from(value: &WorkMode) -> Self {
+        let WorkMode {
+            wide_angle,
+            measure_2d,
+            disable_imu,
+            serial_mode,
+            wait_start,
+        } = *value;
+
+        let mode = u32::from(wide_angle)
+            | u32::from(measure_2d) << 1
+            | u32::from(disable_imu) << 2
+            | u32::from(serial_mode) << 3
+            | u32::from(wait_start) << 4;
+
+        Self { mode }
+    }
+}
+
 impl Display for WorkMode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -95,11 +129,10 @@ impl LidarWorkModeConfig {
 
     pub(crate) fn parse(bytes: &[u8]) -> Result<(Self, &[u8])> {
         let Some((mut bytes, remainder)) = bytes.split_at_checked(Self::LEN) else {
-            bail!(
-                "expected a minimum of {} bytes but got {}",
-                Self::LEN,
-                bytes.len()
-            );
+            return Err(ProtocolError::Truncated {
+                expected: Self::LEN,
+                actual: bytes.len(),
+            });
         };
 
         let flags = bytes.get_u32_le();
@@ -110,6 +143,11 @@ impl LidarWorkModeConfig {
 
         Ok((Self { mode: flags }, remainder))
     }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn write(&self, buf: &mut impl BufMut) {
+        buf.put_u32_le(self.mode);
+    }
 }
 
 impl Display for LidarWorkModeConfig {
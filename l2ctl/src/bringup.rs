@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use clap::Args;
+use l2_protocol::{BringUpConfig, BringUpError, BringUpStep, bring_up};
+
+use crate::{
+    control::{WorkModeFlags, print_capabilities},
+    device::{ConnectionArgs, parse_timeout},
+};
+
+/// Brings the lidar into the desired work mode and starts it
+#[derive(Debug, Args)]
+pub(crate) struct ConnectArgs {
+    #[command(flatten)]
+    connection: ConnectionArgs,
+    /// flags the work mode should have; the others are left as they are
+    #[command(flatten)]
+    work_mode: WorkModeFlags,
+    /// seconds to wait for point and IMU data after starting
+    #[arg(long, default_value = "10", value_parser = parse_timeout)]
+    data_timeout: Duration,
+    /// number of packets of each kind to receive before the data is considered flowing
+    #[arg(long, default_value_t = 10)]
+    min_packets: u32,
+}
+
+/// Brings the lidar up with [`bring_up`], reporting each step.
+pub(crate) async fn run(args: ConnectArgs) -> Result<()> {
+    let table = args.connection.capability_table()?;
+    let config = BringUpConfig {
+        response_timeout: args.connection.timeout(),
+        data_timeout: args.data_timeout,
+        min_packets: args.min_packets,
+    };
+
+    let mut transport = args
+        .connection
+        .open()
+        .await
+        .context("failed to open the connection")?;
+    println!("connection: open");
+
+    let result = bring_up(
+        &mut transport,
+        &table,
+        |mode| args.work_mode.apply(mode),
+        &config,
+        |step| match step {
+            BringUpStep::VersionRead { version, .. } => {
+                println!("version: {version}");
+                print_capabilities(&table, version);
+            }
+            BringUpStep::WorkModeUnchanged(mode) => println!("work mode: {mode}, unchanged"),
+            BringUpStep::WorkModeApplied { previous, current } => {
+                println!("work mode: {previous} → {current}");
+            }
+            BringUpStep::Started => println!("start: acknowledged"),
+            BringUpStep::DataFlowing {
+                points,
+                imu,
+                elapsed,
+            } => println!("data: {points} point data and {imu} IMU packets after {elapsed:.1?}"),
+        },
+    )
+    .await;
+
+    match result {
+        Ok(()) => {
+            println!("ready");
+            Ok(())
+        }
+        Err(error @ BringUpError::NoVersion { .. }) => {
+            bail!("{error}; {}", args.connection.hint())
+        }
+        Err(error @ BringUpError::NoData { .. }) => {
+            bail!("{error} (see --data-timeout); {}", args.connection.hint())
+        }
+        Err(error) => Err(error.into()),
+    }
+}
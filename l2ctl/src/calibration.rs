@@ -0,0 +1,136 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use clap::{Args, Subcommand};
+use futures::StreamExt;
+use l2_processing::{Calibration, CalibrationEstimator};
+use l2_protocol::Packet;
+
+use crate::source::SourceArgs;
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum CalibrationCommand {
+    /// Compare a calibration file with the calibration stored on the lidar
+    Compare {
+        #[command(flatten)]
+        source: SourceArgs,
+        /// JSON file describing the calibration
+        calibration: PathBuf,
+    },
+    /// Refine the calibration from a recording of a room with planar walls
+    Estimate(EstimateArgs),
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct EstimateArgs {
+    #[command(flatten)]
+    source: SourceArgs,
+    /// JSON file describing the estimator's settings
+    #[arg(long)]
+    settings: Option<PathBuf>,
+    /// JSON file describing the calibration to start from instead of the lidar's
+    #[arg(long)]
+    initial: Option<PathBuf>,
+    /// use this many point data packets
+    #[arg(long, default_value_t = 2000)]
+    count: u64,
+    /// JSON file to write the calibration to
+    #[arg(long, short)]
+    output: PathBuf,
+}
+
+pub(crate) async fn calibration(command: CalibrationCommand) -> Result<()> {
+    match command {
+        CalibrationCommand::Compare {
+            source,
+            calibration,
+        } => {
+            let supplied = load_calibration(&calibration)?;
+            let mut packets = source.open().await?;
+            let onboard = loop {
+                match packets.next().await.transpose()? {
+                    Some(Packet::LidarPointData(data)) => break Calibration::from(data.param()),
+                    Some(_) => {}
+                    None => bail!("no point data received"),
+                }
+            };
+            print_comparison(&supplied, &onboard)?;
+        }
+        CalibrationCommand::Estimate(args) => estimate(args).await?,
+    }
+    Ok(())
+}
+
+async fn estimate(args: EstimateArgs) -> Result<()> {
+    let estimator = match &args.settings {
+        Some(path) => {
+            let file =
+                File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+            serde_json::from_reader(BufReader::new(file))
+                .with_context(|| format!("failed to parse {}", path.display()))?
+        }
+        None => CalibrationEstimator::default(),
+    };
+    let initial = args.initial.as_deref().map(load_calibration).transpose()?;
+
+    let mut packets = args.source.open().await?;
+    let mut recorded = Vec::new();
+    while u64::try_from(recorded.len()).is_ok_and(|count| count < args.count)
+        && let Some(packet) = packets.next().await
+    {
+        if let Packet::LidarPointData(data) = packet? {
+            recorded.push(*data);
+        }
+    }
+    let Some(onboard) = recorded.first().map(|data| Calibration::from(data.param())) else {
+        bail!("no point data received");
+    };
+
+    let Some(estimate) = estimator.estimate(&recorded, &initial.unwrap_or(onboard)) else {
+        bail!("no planes found to estimate the calibration from");
+    };
+    let mut writer = BufWriter::new(
+        File::create(&args.output)
+            .with_context(|| format!("failed to create {}", args.output.display()))?,
+    );
+    serde_json::to_writer_pretty(&mut writer, &estimate.calibration)?;
+    writeln!(writer)?;
+    writer.flush()?;
+
+    println!(
+        "fit {} planes to {} points, rms distance {:.2} mm → {:.2} mm",
+        estimate.planes,
+        estimate.samples,
+        estimate.initial_rmse * 1000.0,
+        estimate.rmse * 1000.0
+    );
+    print_comparison(&estimate.calibration, &onboard)
+}
+
+pub(crate) fn load_calibration(path: &Path) -> Result<Calibration> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    serde_json::from_reader(BufReader::new(file))
+        .with_context(|| format!("failed to parse {}", path.display()))
+}
+
+fn print_comparison(calibration: &Calibration, onboard: &Calibration) -> Result<()> {
+    println!(
+        "{:<18} {:>12} {:>12} {:>12}",
+        "parameter", "onboard", "calibration", "difference"
+    );
+    for (parameter, difference) in calibration.compare(onboard) {
+        let name = serde_json::to_value(parameter)?;
+        println!(
+            "{:<18} {:>12.6} {:>12.6} {:>+12.6}",
+            name.as_str().unwrap_or_default(),
+            onboard.get(parameter),
+            calibration.get(parameter),
+            difference
+        );
+    }
+    Ok(())
+}
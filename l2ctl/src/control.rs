@@ -0,0 +1,374 @@
+use std::{fs, net::Ipv4Addr, path::PathBuf, time::Instant};
+
+use anyhow::{Context, Result, bail};
+use clap::{Args, Subcommand};
+use l2_protocol::{
+    Capabilities, CapabilityTable, Command, IpAddressConfig, MacAddressConfig, Packet, StandbyType,
+    UserCmd, Version, WorkMode,
+};
+
+use crate::device::{ConnectionArgs, Device};
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum WorkModeCommand {
+    /// Print the current work mode
+    Get(ConnectionArgs),
+    /// Change individual flags of the work mode
+    Set {
+        #[command(flatten)]
+        connection: ConnectionArgs,
+        #[command(flatten)]
+        flags: WorkModeFlags,
+    },
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct WorkModeFlags {
+    /// wide-angle FOV (192°) instead of the standard FOV (180°)
+    #[arg(long)]
+    wide_angle: Option<bool>,
+    /// 2D instead of 3D measurement
+    #[arg(long)]
+    measure_2d: Option<bool>,
+    #[arg(long)]
+    disable_imu: Option<bool>,
+    /// serial instead of ethernet interface
+    #[arg(long)]
+    serial_mode: Option<bool>,
+    /// wait for the start command after power-on instead of starting automatically
+    #[arg(long)]
+    wait_start: Option<bool>,
+}
+
+impl WorkModeFlags {
+    /// The work mode with the given flags changed
+    pub(crate) fn apply(&self, mut mode: WorkMode) -> WorkMode {
+        let Self {
+            wide_angle,
+            measure_2d,
+            disable_imu,
+            serial_mode,
+            wait_start,
+        } = *self;
+        mode.wide_angle = wide_angle.unwrap_or(mode.wide_angle);
+        mode.measure_2d = measure_2d.unwrap_or(mode.measure_2d);
+        mode.disable_imu = disable_imu.unwrap_or(mode.disable_imu);
+        mode.serial_mode = serial_mode.unwrap_or(mode.serial_mode);
+        mode.wait_start = wait_start.unwrap_or(mode.wait_start);
+        mode
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum NetworkCommand {
+    /// Print the current network configuration
+    Get(ConnectionArgs),
+    /// Change individual settings of the network configuration
+    Set {
+        #[command(flatten)]
+        connection: ConnectionArgs,
+        #[command(flatten)]
+        settings: NetworkSettings,
+    },
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct NetworkSettings {
+    #[arg(long)]
+    lidar_ip: Option<Ipv4Addr>,
+    /// address the lidar sends its data to
+    #[arg(long)]
+    user_ip: Option<Ipv4Addr>,
+    #[arg(long)]
+    gateway: Option<Ipv4Addr>,
+    #[arg(long)]
+    subnet_mask: Option<Ipv4Addr>,
+    #[arg(long)]
+    lidar_port: Option<u16>,
+    /// port the lidar sends its data to
+    #[arg(long)]
+    user_port: Option<u16>,
+    /// MAC address, e.g. `02:00:00:00:00:01`
+    #[arg(long, value_parser = parse_mac)]
+    mac: Option<[u8; 6]>,
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum ParamsCommand {
+    /// Save the lidar's parameters into a file
+    Dump {
+        #[command(flatten)]
+        connection: ConnectionArgs,
+        file: PathBuf,
+    },
+    /// Write parameters from a file back to the lidar
+    Restore {
+        #[command(flatten)]
+        connection: ConnectionArgs,
+        file: PathBuf,
+    },
+}
+
+pub(crate) async fn info(connection: &ConnectionArgs) -> Result<()> {
+    let table = connection.capability_table()?;
+    let mut device = connection.connect().await?;
+    let version = read_version(&mut device).await?;
+
+    println!("{version}");
+    print_capabilities(&table, &version);
+    Ok(())
+}
+
+pub(crate) async fn read_version(device: &mut Device) -> Result<Version> {
+    device
+        .query(Packet::LidarUserCmd(UserCmd::VersionGet(0)), |packet| {
+            Ok(match packet {
+                Packet::LidarVersion(version) => Some(version),
+                _ => None,
+            })
+        })
+        .await
+}
+
+/// Reads the version to look up the capabilities of the firmware; warns if it hasn't been tested
+pub(crate) async fn read_capabilities(
+    device: &mut Device,
+    connection: &ConnectionArgs,
+) -> Result<Capabilities> {
+    let table = connection.capability_table()?;
+    let version = read_version(device).await?;
+    warn_untested(&table, &version);
+    Ok(table.capabilities(version.software()))
+}
+
+/// Prints the capabilities of the firmware and warns if it hasn't been tested
+pub(crate) fn print_capabilities(table: &CapabilityTable, version: &Version) {
+    let capabilities = table.capabilities(version.software());
+    println!(
+        "commands:{:?}, work mode:{:?}, 2D:{}, MAC address:{}",
+        capabilities.command_packet,
+        capabilities.work_mode_packet,
+        capabilities.measure_2d,
+        capabilities.mac_address
+    );
+    warn_untested(table, version);
+}
+
+fn warn_untested(table: &CapabilityTable, version: &Version) {
+    if !table.is_tested(version.software()) {
+        eprintln!(
+            "warning: firmware {} hasn't been tested, its capabilities are assumed",
+            version.software()
+        );
+    }
+}
+
+/// The packet sending `cmd` the way the firmware expects
+fn command(capabilities: &Capabilities, cmd: UserCmd) -> Result<Packet> {
+    capabilities.command(cmd).with_context(|| {
+        format!(
+            "the capabilities name {:?}, which can't send {cmd}",
+            capabilities.command_packet
+        )
+    })
+}
+
+pub(crate) async fn standby(connection: &ConnectionArgs, standby: StandbyType) -> Result<()> {
+    let mut device = connection.connect().await?;
+    let capabilities = read_capabilities(&mut device, connection).await?;
+    device
+        .request(command(&capabilities, UserCmd::StandbyType(standby))?)
+        .await
+}
+
+pub(crate) async fn reset(connection: &ConnectionArgs) -> Result<()> {
+    let mut device = connection.connect().await?;
+    let capabilities = read_capabilities(&mut device, connection).await?;
+    device
+        .request(command(&capabilities, UserCmd::ResetType(1))?)
+        .await
+}
+
+pub(crate) async fn latency(connection: &ConnectionArgs, value: u32) -> Result<()> {
+    let mut device = connection.connect().await?;
+    let packet = command(
+        &read_capabilities(&mut device, connection).await?,
+        UserCmd::LatencyType(value),
+    )?;
+
+    let start = Instant::now();
+    device.request(packet).await?;
+    println!("acknowledged after {:?}", start.elapsed());
+
+    Ok(())
+}
+
+pub(crate) async fn work_mode(command: WorkModeCommand) -> Result<()> {
+    match command {
+        WorkModeCommand::Get(connection) => {
+            let mut device = connection.connect().await?;
+            println!("{}", read_work_mode(&mut device).await?);
+        }
+        WorkModeCommand::Set { connection, flags } => {
+            let mut device = connection.connect().await?;
+            let capabilities = read_capabilities(&mut device, &connection).await?;
+            let mode = flags.apply(read_work_mode(&mut device).await?);
+            let packet = capabilities.work_mode(mode).with_context(|| {
+                format!(
+                    "the capabilities name {:?}, which can't change the work mode",
+                    capabilities.work_mode_packet
+                )
+            })?;
+            device.request(packet).await?;
+            println!("{mode}");
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn read_work_mode(device: &mut Device) -> Result<WorkMode> {
+    device
+        .query(Packet::LidarUserCmd(UserCmd::ConfigGet(0)), |packet| {
+            Ok(match packet {
+                Packet::LidarWorkModeConfig(mode) | Packet::LidarWorkMode(mode) => Some(mode),
+                _ => None,
+            })
+        })
+        .await
+}
+
+pub(crate) async fn network(command: NetworkCommand) -> Result<()> {
+    match command {
+        NetworkCommand::Get(connection) => {
+            let mut device = connection.connect().await?;
+            let capabilities = read_capabilities(&mut device, &connection).await?;
+            let (ip_config, mac_config) = read_network(&mut device, &capabilities).await?;
+
+            println!("{ip_config}");
+            if let Some(mac_config) = mac_config {
+                println!("mac:{mac_config}");
+            }
+        }
+        NetworkCommand::Set {
+            connection,
+            settings,
+        } => {
+            let mut device = connection.connect().await?;
+            let capabilities = read_capabilities(&mut device, &connection).await?;
+            let (mut ip_config, _) = read_network(&mut device, &capabilities).await?;
+
+            let NetworkSettings {
+                lidar_ip,
+                user_ip,
+                gateway,
+                subnet_mask,
+                lidar_port,
+                user_port,
+                mac,
+            } = settings;
+            ip_config.lidar_ip = lidar_ip.unwrap_or(ip_config.lidar_ip);
+            ip_config.user_ip = user_ip.unwrap_or(ip_config.user_ip);
+            ip_config.gateway = gateway.unwrap_or(ip_config.gateway);
+            ip_config.subnet_mask = subnet_mask.unwrap_or(ip_config.subnet_mask);
+            ip_config.lidar_port = lidar_port.unwrap_or(ip_config.lidar_port);
+            ip_config.user_port = user_port.unwrap_or(ip_config.user_port);
+
+            device
+                .request(Packet::LidarIpAddressConfig(ip_config))
+                .await?;
+            println!("{ip_config}");
+
+            if let Some(mac) = mac {
+                let mac_config = MacAddressConfig { mac };
+                device
+                    .request(Packet::LidarMacAddressConfig(mac_config))
+                    .await?;
+                println!("mac:{mac_config}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Requests the network configuration.
+///
+/// Only waits for the MAC address if the firmware reports it, otherwise it's taken along if it
+/// arrives first.
+async fn read_network(
+    device: &mut Device,
+    capabilities: &Capabilities,
+) -> Result<(IpAddressConfig, Option<MacAddressConfig>)> {
+    let mut ip_config = None;
+    let mut mac_config = None;
+
+    let complete = device
+        .query(Packet::LidarUserCmd(UserCmd::ConfigGet(0)), |packet| {
+            match packet {
+                Packet::LidarIpAddressConfig(config) => ip_config = Some(config),
+                Packet::LidarMacAddressConfig(config) => mac_config = Some(config),
+                _ => {}
+            }
+            let mac_complete = mac_config.is_some() || !capabilities.mac_address;
+            Ok((ip_config.is_some() && mac_complete).then_some(()))
+        })
+        .await;
+
+    match (complete, ip_config) {
+        (_, Some(ip_config)) => Ok((ip_config, mac_config)),
+        (Err(error), None) => Err(error.context("failed to read the network configuration")),
+        (Ok(()), None) => unreachable!("query should only complete with an IP configuration"),
+    }
+}
+
+pub(crate) async fn params(command: ParamsCommand) -> Result<()> {
+    match command {
+        ParamsCommand::Dump { connection, file } => {
+            let mut device = connection.connect().await?;
+
+            let params = device
+                .query(Packet::LidarCommand(Command::ParamGet(0)), |packet| {
+                    Ok(match packet {
+                        Packet::LidarParamData(params) => Some(params),
+                        _ => None,
+                    })
+                })
+                .await?;
+
+            fs::write(&file, &params)
+                .with_context(|| format!("failed to write {}", file.display()))?;
+            println!("saved {} bytes of parameters", params.len());
+        }
+        ParamsCommand::Restore { connection, file } => {
+            let params =
+                fs::read(&file).with_context(|| format!("failed to read {}", file.display()))?;
+            if params.is_empty() {
+                bail!("{} is empty", file.display());
+            }
+
+            let mut device = connection.connect().await?;
+            let len = params.len();
+            device.request(Packet::LidarParamData(params)).await?;
+            device
+                .request(Packet::LidarCommand(Command::ParamSave(0)))
+                .await?;
+            println!("restored {len} bytes of parameters");
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_mac(value: &str) -> Result<[u8; 6]> {
+    let mut mac = [0; 6];
+    let mut parts = value.split(':');
+    for byte in &mut mac {
+        let part = parts.next().context("expected 6 bytes")?;
+        *byte = u8::from_str_radix(part, 16)?;
+    }
+    if parts.next().is_some() {
+        bail!("expected 6 bytes");
+    }
+    Ok(mac)
+}
@@ -0,0 +1,176 @@
+use std::{fs::File, io::BufReader, net::SocketAddr, path::PathBuf, pin::Pin, time::Duration};
+
+use anyhow::{Context, Result, anyhow, bail};
+use clap::Args;
+use futures::{Sink, SinkExt, Stream, StreamExt};
+use l2_protocol::{
+    AckStatus, CapabilityTable, DEFAULT_BAUD_RATE, DEFAULT_USER_ADDR, Packet, SerialSource,
+    UdpSource,
+};
+use tokio::time;
+
+/// How to connect to the lidar
+#[derive(Debug, Args)]
+pub(crate) struct ConnectionArgs {
+    #[command(flatten)]
+    kind: ConnectionKind,
+    /// baud rate of the serial port
+    #[arg(long, default_value_t = DEFAULT_BAUD_RATE)]
+    baud: u32,
+    /// local address to receive UDP packets on
+    #[arg(long, default_value_t = DEFAULT_USER_ADDR)]
+    bind: SocketAddr,
+    /// seconds to wait for a response of the lidar
+    #[arg(long, default_value = "2", value_parser = parse_timeout)]
+    timeout: Duration,
+    /// JSON file describing the capabilities of firmware versions and which have been tested
+    #[arg(long)]
+    capabilities: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+#[group(required = true, multiple = false)]
+struct ConnectionKind {
+    /// serial port the lidar is connected to, e.g. `/dev/ttyACM0`
+    #[arg(long)]
+    serial: Option<String>,
+    /// address of the lidar, e.g. `192.168.1.62:6101`
+    #[arg(long)]
+    udp: Option<SocketAddr>,
+}
+
+impl ConnectionArgs {
+    pub(crate) async fn connect(&self) -> Result<Device> {
+        Ok(Device {
+            transport: self.open().await?,
+            timeout: self.timeout,
+        })
+    }
+
+    /// Opens the serial port or UDP socket without wrapping it into a [`Device`]
+    pub(crate) async fn open(&self) -> Result<Pin<Box<dyn Transport>>> {
+        let ConnectionKind { serial, udp } = &self.kind;
+
+        if let Some(path) = serial {
+            Ok(Box::pin(SerialSource::open(path, self.baud)?))
+        } else if let Some(lidar_addr) = udp {
+            Ok(Box::pin(UdpSource::bind(self.bind, *lidar_addr).await?))
+        } else {
+            unreachable!("clap should've required a connection")
+        }
+    }
+
+    /// How long to wait for a response of the lidar
+    pub(crate) fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Reads the capability table from `--capabilities`; the default table without one
+    pub(crate) fn capability_table(&self) -> Result<CapabilityTable> {
+        let Some(path) = &self.capabilities else {
+            return Ok(CapabilityTable::default());
+        };
+        let file =
+            File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+        serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("failed to parse {}", path.display()))
+    }
+
+    /// What to check if the lidar doesn't respond
+    pub(crate) fn hint(&self) -> String {
+        let ConnectionKind { serial, udp } = &self.kind;
+        match (serial, udp) {
+            (Some(path), _) => format!(
+                "check that the lidar is connected to {path}, uses {} baud and is in serial mode",
+                self.baud
+            ),
+            (None, Some(lidar_addr)) => format!(
+                "check that the lidar is reachable at {lidar_addr}, sends to {} and is in ethernet \
+                 mode",
+                self.bind
+            ),
+            (None, None) => unreachable!("clap should've required a connection"),
+        }
+    }
+}
+
+pub(crate) fn parse_timeout(value: &str) -> Result<Duration> {
+    Ok(Duration::try_from_secs_f64(value.parse()?)?)
+}
+
+pub(crate) trait Transport:
+    Stream<Item = Result<Packet>> + Sink<Packet, Error = anyhow::Error> + Send
+{
+}
+
+impl<T> Transport for T where
+    T: Stream<Item = Result<Packet>> + Sink<Packet, Error = anyhow::Error> + Send
+{
+}
+
+/// Request/response style access to a lidar
+pub(crate) struct Device {
+    transport: Pin<Box<dyn Transport>>,
+    timeout: Duration,
+}
+
+impl Device {
+    pub(crate) async fn send(&mut self, packet: Packet) -> Result<()> {
+        self.transport.send(packet).await
+    }
+
+    /// Waits for the first packet `select` returns a value for.
+    ///
+    /// Fails if the lidar doesn't send such a packet within the timeout.
+    pub(crate) async fn receive<T>(
+        &mut self,
+        mut select: impl FnMut(Packet) -> Result<Option<T>>,
+    ) -> Result<T> {
+        let timeout = self.timeout;
+        let transport = &mut self.transport;
+
+        time::timeout(timeout, async {
+            while let Some(packet) = transport.next().await {
+                if let Some(value) = select(packet?)? {
+                    return Ok(value);
+                }
+            }
+            bail!("connection closed")
+        })
+        .await
+        .map_err(|_elapsed| anyhow!("no response from the lidar within {timeout:?}"))?
+    }
+
+    /// Sends a packet and waits until the lidar has acknowledged it.
+    pub(crate) async fn request(&mut self, packet: Packet) -> Result<()> {
+        self.query(packet, |response| match response {
+            Packet::LidarAckData(_) => Ok(Some(())),
+            _ => Ok(None),
+        })
+        .await
+    }
+
+    /// Sends a packet and waits for the response `select` returns a value for.
+    ///
+    /// Fails early if the lidar rejects the packet.
+    pub(crate) async fn query<T>(
+        &mut self,
+        packet: Packet,
+        mut select: impl FnMut(Packet) -> Result<Option<T>>,
+    ) -> Result<T> {
+        self.send(packet.clone()).await?;
+
+        self.receive(|response| match &response {
+            Packet::LidarAckData(ack) if ack.acknowledges(&packet) => {
+                if ack.status() != AckStatus::Success {
+                    bail!("the lidar rejected {packet}: {}", ack.status());
+                }
+                select(response)
+            }
+            // acks of earlier requests
+            Packet::LidarAckData(_) => Ok(None),
+            _ => select(response),
+        })
+        .await
+    }
+}
@@ -0,0 +1,212 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result, bail};
+use clap::{Args, ValueEnum, builder::PossibleValuesParser};
+use futures::StreamExt;
+use l2_protocol::{Packet, PacketType};
+use serde_json::{Map, Value};
+
+use crate::source::SourceArgs;
+
+/// Writes one JSON object or CSV row per packet
+#[derive(Debug, Args)]
+pub(crate) struct DumpArgs {
+    #[command(flatten)]
+    source: SourceArgs,
+    /// output format
+    #[arg(long, value_enum, default_value_t = Format::Json)]
+    format: Format,
+    /// only dump packets of these types
+    #[arg(
+        long = "type",
+        value_delimiter = ',',
+        value_parser = PossibleValuesParser::new(PacketType::ALL.map(PacketType::name))
+    )]
+    types: Vec<String>,
+    /// only dump these fields, given as dot-separated paths, e.g. `info.stamp,quaternion`
+    #[arg(long, value_delimiter = ',')]
+    fields: Vec<String>,
+    /// stop after this many packets have been written
+    #[arg(long)]
+    count: Option<u64>,
+    /// file to write to instead of stdout
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    /// JSON Lines; nested values are kept as they are
+    Json,
+    /// CSV with a header row; nested objects are flattened into one column per field and arrays
+    /// are written space-separated into a single cell
+    Csv,
+}
+
+pub(crate) async fn run(args: DumpArgs) -> Result<()> {
+    let output: Box<dyn Write> = match &args.output {
+        Some(path) => {
+            Box::new(BufWriter::new(File::create(path).with_context(|| {
+                format!("failed to create {}", path.display())
+            })?))
+        }
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut writer = match args.format {
+        Format::Json => RecordWriter::Json(output),
+        Format::Csv => RecordWriter::Csv {
+            writer: Box::new(csv::Writer::from_writer(output)),
+            header: None,
+        },
+    };
+
+    let mut packets = args.source.open().await?;
+    let mut written = 0;
+    while args.count.is_none_or(|count| written < count)
+        && let Some(packet) = packets.next().await
+    {
+        let Some(record) = record(&packet?, &args.types, &args.fields)? else {
+            continue;
+        };
+        writer.write(record)?;
+        written += 1;
+    }
+
+    writer.flush()
+}
+
+/// Selects the requested fields of a packet, starting with its type.
+///
+/// Returns `None` if the packet type hasn't been selected.
+fn record(
+    packet: &Packet,
+    types: &[String],
+    fields: &[String],
+) -> Result<Option<Vec<(String, Value)>>> {
+    let Value::Object(mut packet) = serde_json::to_value(packet)? else {
+        unreachable!("packets should serialize as objects");
+    };
+    let packet_type = packet.remove("type").unwrap_or_default();
+    let data = packet.remove("data").unwrap_or_default();
+
+    if !types.is_empty() && !types.iter().any(|name| packet_type == name.as_str()) {
+        return Ok(None);
+    }
+
+    let mut record = vec![("type".to_owned(), packet_type)];
+    if fields.is_empty() {
+        match data {
+            Value::Object(map) => record.extend(map),
+            other => record.push(("data".to_owned(), other)),
+        }
+    } else {
+        for field in fields {
+            let pointer = format!("/{}", field.replace('.', "/"));
+            let value = data.pointer(&pointer).cloned().unwrap_or_default();
+            record.push((field.clone(), value));
+        }
+    }
+
+    Ok(Some(record))
+}
+
+enum RecordWriter {
+    Json(Box<dyn Write>),
+    Csv {
+        writer: Box<csv::Writer<Box<dyn Write>>>,
+        /// column names of the first row; all further rows need to match
+        header: Option<Vec<String>>,
+    },
+}
+
+impl RecordWriter {
+    fn write(&mut self, record: Vec<(String, Value)>) -> Result<()> {
+        match self {
+            Self::Json(writer) => {
+                serde_json::to_writer(&mut *writer, &record.into_iter().collect::<Map<_, _>>())?;
+                writer.write_all(b"\n")?;
+            }
+            Self::Csv { writer, header } => {
+                let mut columns = Vec::new();
+                for (name, value) in record {
+                    flatten(name, value, &mut columns);
+                }
+                let (names, cells): (Vec<_>, Vec<_>) = columns.into_iter().unzip();
+
+                match header {
+                    Some(header) if *header != names => {
+                        let missing = difference(header, &names);
+                        let added = difference(&names, header);
+                        bail!(
+                            "CSV rows need identical columns, but this row lacks [{missing}] and \
+                             adds [{added}]; select a single packet type or fields"
+                        );
+                    }
+                    Some(_) => {}
+                    None => {
+                        writer.write_record(&names)?;
+                        *header = Some(names);
+                    }
+                }
+                writer.write_record(&cells)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        match self {
+            Self::Json(writer) => writer.flush()?,
+            Self::Csv { writer, .. } => writer.flush()?,
+        }
+        Ok(())
+    }
+}
+
+/// Comma-separated names of the columns in `columns` that are missing from `other`
+fn difference(columns: &[String], other: &[String]) -> String {
+    columns
+        .iter()
+        .filter(|name| !other.contains(name))
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Flattens nested objects into columns named by their dot-separated path.
+///
+/// Arrays become a single space-separated cell as their length may vary between packets, e.g. the
+/// ranges of point data which only holds valid points.
+fn flatten(name: String, value: Value, columns: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(map) => {
+            for (key, nested) in map {
+                flatten(format!("{name}.{key}"), nested, columns);
+            }
+        }
+        other => columns.push((name, cell(other))),
+    }
+}
+
+/// Formats a value that isn't flattened any further
+fn cell(value: Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(value) => value,
+        Value::Bool(value) => value.to_string(),
+        Value::Number(value) => value.to_string(),
+        Value::Array(values) => values
+            .into_iter()
+            .map(|nested| match nested {
+                Value::Object(_) | Value::Array(_) => nested.to_string(),
+                scalar => cell(scalar),
+            })
+            .collect::<Vec<_>>()
+            .join(" "),
+        Value::Object(_) => value.to_string(),
+    }
+}
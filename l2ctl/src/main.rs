@@ -0,0 +1,95 @@
+//! Command line tool for the Unitree L2 lidar
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use l2_protocol::StandbyType;
+
+use crate::{
+    bringup::ConnectArgs,
+    calibration::CalibrationCommand,
+    control::{NetworkCommand, ParamsCommand, WorkModeCommand},
+    device::ConnectionArgs,
+    dump::DumpArgs,
+    map::MapArgs,
+    points::PointsArgs,
+    reflectivity::CalibrateIntensityArgs,
+};
+
+mod bringup;
+mod calibration;
+mod control;
+mod device;
+mod dump;
+mod map;
+mod points;
+mod reflectivity;
+mod source;
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Print the hardware and software version and the capabilities of the firmware
+    Info(ConnectionArgs),
+    /// Apply a work mode, start the lidar and wait until it streams data
+    Connect(ConnectArgs),
+    /// Start rotating and measuring
+    Start(ConnectionArgs),
+    /// Stop rotating and measuring
+    Standby(ConnectionArgs),
+    /// Reboot the lidar
+    Reset(ConnectionArgs),
+    /// Read or change the work mode
+    #[command(subcommand)]
+    WorkMode(WorkModeCommand),
+    /// Read or change the network configuration
+    #[command(subcommand)]
+    Network(NetworkCommand),
+    /// Back up or restore the lidar's parameters
+    #[command(subcommand)]
+    Params(ParamsCommand),
+    /// Set the latency type
+    Latency {
+        #[command(flatten)]
+        connection: ConnectionArgs,
+        value: u32,
+    },
+    /// Dump packets as JSON Lines or CSV
+    Dump(DumpArgs),
+    /// Convert point data into filtered points in CSV
+    Points(PointsArgs),
+    /// Merge the scans of a recording into one point cloud in PCD or PLY
+    Map(MapArgs),
+    /// Fit a reflectivity model from a recording of a known target
+    CalibrateIntensity(CalibrateIntensityArgs),
+    /// Compare or estimate the intrinsic calibration
+    #[command(subcommand)]
+    Calibration(CalibrationCommand),
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let Cli { command } = Cli::parse();
+
+    match command {
+        Command::Info(connection) => control::info(&connection).await,
+        Command::Connect(args) => bringup::run(args).await,
+        Command::Start(connection) => control::standby(&connection, StandbyType::Start).await,
+        Command::Standby(connection) => control::standby(&connection, StandbyType::Standby).await,
+        Command::Reset(connection) => control::reset(&connection).await,
+        Command::WorkMode(command) => control::work_mode(command).await,
+        Command::Network(command) => control::network(command).await,
+        Command::Params(command) => control::params(command).await,
+        Command::Latency { connection, value } => control::latency(&connection, value).await,
+        Command::Dump(args) => dump::run(args).await,
+        Command::Points(args) => points::run(args).await,
+        Command::Map(args) => map::run(args).await,
+        Command::CalibrateIntensity(args) => reflectivity::calibrate(args).await,
+        Command::Calibration(command) => calibration::calibration(command).await,
+    }
+}
@@ -0,0 +1,236 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context, Result, bail};
+use clap::Args;
+use futures::StreamExt;
+use l2_processing::{
+    FilterPipeline, Frames, IcpOdometry, ImuSample, MapAccumulator, MapConfig, Point,
+    PointToPlaneIcp, Scan, ScanAssembler, VoxelGrid, VoxelMode,
+};
+use l2_protocol::Packet;
+use nalgebra::{Isometry3, Quaternion, Translation3, UnitQuaternion};
+
+use crate::{
+    calibration::load_calibration, device::parse_timeout, points::load_pipeline, source::SourceArgs,
+};
+
+/// Merges the scans of a recording into one point cloud
+#[derive(Debug, Args)]
+pub(crate) struct MapArgs {
+    #[command(flatten)]
+    source: SourceArgs,
+    /// JSON file describing the filter pipeline
+    #[arg(long)]
+    filters: Option<PathBuf>,
+    /// JSON file describing a calibration to use instead of the lidar's
+    #[arg(long)]
+    calibration: Option<PathBuf>,
+    /// CSV trajectory with the columns `stamp,x,y,z,qx,qy,qz,qw` to use instead of the odometry
+    #[arg(long)]
+    poses: Option<PathBuf>,
+    /// duration of the scans registered and merged at once [s]; defaults to the scan period
+    /// reported by the lidar
+    #[arg(long, value_parser = parse_timeout)]
+    scan_period: Option<Duration>,
+    /// edge length of the voxels the map is merged in [m]
+    #[arg(long, default_value_t = 0.05)]
+    leaf_size: f32,
+    /// edge length of the voxels scans are downsampled to for the odometry [m]
+    #[arg(long, default_value_t = 0.1)]
+    odometry_leaf_size: f32,
+    /// distance the lidar has to move before the next scan is merged [m]
+    #[arg(long, default_value_t = 0.5)]
+    keyframe_distance: f32,
+    /// angle the lidar has to rotate before the next scan is merged [deg]
+    #[arg(long, default_value_t = 15.0)]
+    keyframe_angle: f32,
+    /// file to write the poses of the merged scans to, as CSV in the format of `--poses`
+    #[arg(long)]
+    trajectory: Option<PathBuf>,
+    /// stop after this many point data packets
+    #[arg(long)]
+    count: Option<u64>,
+    /// file to write the merged cloud to, PCD or PLY depending on the extension
+    #[arg(long, short)]
+    output: PathBuf,
+}
+
+/// Where the poses of the scans come from
+enum PoseSource {
+    Odometry {
+        odometry: Box<IcpOdometry>,
+        downsampling: VoxelGrid,
+    },
+    Trajectory(Vec<(Duration, Isometry3<f32>)>),
+}
+
+impl PoseSource {
+    fn pose(&mut self, stamp: Duration, scan: &[Point]) -> Option<Isometry3<f32>> {
+        match self {
+            Self::Odometry {
+                odometry,
+                downsampling,
+            } => odometry
+                .update_scan(stamp, &downsampling.downsample(scan))
+                .map(|estimate| estimate.pose),
+            Self::Trajectory(poses) => interpolate(poses, stamp),
+        }
+    }
+}
+
+pub(crate) async fn run(args: MapArgs) -> Result<()> {
+    // fail before processing the whole recording
+    let extension = args
+        .output
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    if !matches!(extension.as_deref(), Some("pcd" | "ply")) {
+        bail!("output should end in .pcd or .ply");
+    }
+
+    let pipeline = match &args.filters {
+        Some(path) => load_pipeline(path)?,
+        None => FilterPipeline::default(),
+    };
+    let frames = Frames::default();
+    let mut converter = pipeline.point_converter(&frames);
+    if let Some(path) = &args.calibration {
+        converter = converter.with_calibration(load_calibration(path)?);
+    }
+    let mut poses = match &args.poses {
+        Some(path) => PoseSource::Trajectory(load_poses(path)?),
+        None => PoseSource::Odometry {
+            odometry: Box::new(IcpOdometry::new(PointToPlaneIcp::default())),
+            downsampling: VoxelGrid::new(args.odometry_leaf_size),
+        },
+    };
+    let mut map = MapAccumulator::new(MapConfig {
+        keyframe_distance: args.keyframe_distance,
+        keyframe_angle: args.keyframe_angle.to_radians(),
+        leaf_size: args.leaf_size,
+        mode: VoxelMode::Centroid,
+    });
+
+    let mut packets = args.source.open().await?;
+    let mut packet_count = 0;
+    let mut points = Vec::new();
+    let mut scans = ScanAssembler::new();
+    if let Some(period) = args.scan_period {
+        scans = scans.with_period(period);
+    }
+    let mut skipped = 0_usize;
+    while args.count.is_none_or(|count| packet_count < count)
+        && let Some(packet) = packets.next().await
+    {
+        let data = match packet? {
+            Packet::LidarImuData(data) => {
+                if let PoseSource::Odometry { odometry, .. } = &mut poses {
+                    odometry.update_imu(&frames.imu_sample_to_base(&ImuSample::from(&data)));
+                }
+                continue;
+            }
+            Packet::LidarPointData(data) => data,
+            _ => continue,
+        };
+        packet_count += 1;
+
+        points.clear();
+        converter.convert_into(&data, &mut points);
+        pipeline.apply(&mut points);
+        if let Some(scan) = scans.push(&data, &points)
+            && !merge(&mut poses, &mut map, &scan)
+        {
+            skipped += 1;
+        }
+    }
+    if let Some(scan) = scans.finish()
+        && !merge(&mut poses, &mut map, &scan)
+    {
+        skipped += 1;
+    }
+
+    if map.is_empty() {
+        bail!("no scans could be merged");
+    }
+    map.save(&args.output)
+        .with_context(|| format!("failed to write {}", args.output.display()))?;
+    if let Some(path) = &args.trajectory {
+        save_poses(path, &map)?;
+    }
+    println!(
+        "merged {} scans into {} points, skipped {skipped} scans without a pose",
+        map.keyframes().len(),
+        map.len()
+    );
+    Ok(())
+}
+
+/// Merges a scan into the map if it's a keyframe; `false` if there is no pose for it
+fn merge(poses: &mut PoseSource, map: &mut MapAccumulator, scan: &Scan) -> bool {
+    let Some(pose) = poses.pose(scan.stamp, &scan.points) else {
+        return false;
+    };
+    map.insert(scan.stamp, &pose, &scan.points);
+    true
+}
+
+/// Reads a trajectory sorted by timestamp
+fn load_poses(path: &Path) -> Result<Vec<(Duration, Isometry3<f32>)>> {
+    let mut reader = csv::Reader::from_path(path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    let mut poses = Vec::new();
+    for record in reader.deserialize() {
+        let (stamp, x, y, z, qx, qy, qz, qw): (f64, f32, f32, f32, f32, f32, f32, f32) =
+            record.with_context(|| format!("failed to parse {}", path.display()))?;
+        let stamp = Duration::try_from_secs_f64(stamp)
+            .with_context(|| format!("invalid timestamp {stamp} in {}", path.display()))?;
+        poses.push((
+            stamp,
+            Isometry3::from_parts(
+                Translation3::new(x, y, z),
+                UnitQuaternion::from_quaternion(Quaternion::new(qw, qx, qy, qz)),
+            ),
+        ));
+    }
+    poses.sort_by_key(|(stamp, _)| *stamp);
+    Ok(poses)
+}
+
+fn save_poses(path: &Path, map: &MapAccumulator) -> Result<()> {
+    let mut writer = csv::Writer::from_path(path)
+        .with_context(|| format!("failed to create {}", path.display()))?;
+    writer.write_record(["stamp", "x", "y", "z", "qx", "qy", "qz", "qw"])?;
+    for keyframe in map.keyframes() {
+        let translation = keyframe.pose.translation.vector;
+        let rotation = keyframe.pose.rotation.coords;
+        writer.serialize((
+            keyframe.stamp.as_secs_f64(),
+            translation.x,
+            translation.y,
+            translation.z,
+            rotation.x,
+            rotation.y,
+            rotation.z,
+            rotation.w,
+        ))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Pose at `stamp` interpolated between the surrounding poses; `None` outside of the trajectory
+fn interpolate(poses: &[(Duration, Isometry3<f32>)], stamp: Duration) -> Option<Isometry3<f32>> {
+    let next = poses.partition_point(|(pose_stamp, _)| *pose_stamp < stamp);
+    let (after_stamp, after) = poses.get(next)?;
+    if *after_stamp == stamp {
+        return Some(*after);
+    }
+    let (before_stamp, before) = poses.get(next.checked_sub(1)?)?;
+    let fraction = stamp.saturating_sub(*before_stamp).as_secs_f32()
+        / after_stamp.saturating_sub(*before_stamp).as_secs_f32();
+    Some(before.lerp_slerp(after, fraction))
+}
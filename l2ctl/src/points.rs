@@ -0,0 +1,148 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use clap::Args;
+use futures::StreamExt;
+use l2_processing::{FilterPipeline, Frames, ReflectivityModel, Scan, ScanAssembler};
+use l2_protocol::Packet;
+use nalgebra::Point3;
+
+use crate::{
+    calibration::load_calibration, device::parse_timeout, reflectivity::load_model,
+    source::SourceArgs,
+};
+
+/// Writes the filtered points of point data packets as CSV
+#[derive(Debug, Args)]
+pub(crate) struct PointsArgs {
+    #[command(flatten)]
+    source: SourceArgs,
+    /// JSON file describing the filter pipeline
+    #[arg(long)]
+    filters: Option<PathBuf>,
+    /// JSON file describing a calibration to use instead of the lidar's
+    #[arg(long)]
+    calibration: Option<PathBuf>,
+    /// JSON file describing a reflectivity model; replaces the intensities with reflectivities
+    #[arg(long)]
+    reflectivity: Option<PathBuf>,
+    /// duration of the scans the reflectivity model is applied to, which need enough points to
+    /// estimate the surface normals [s]; defaults to the scan period reported by the lidar
+    #[arg(long, value_parser = parse_timeout)]
+    scan_period: Option<Duration>,
+    /// stop after this many point data packets
+    #[arg(long)]
+    count: Option<u64>,
+    /// file to write to instead of stdout
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+}
+
+pub(crate) async fn run(args: PointsArgs) -> Result<()> {
+    let pipeline = match &args.filters {
+        Some(path) => load_pipeline(path)?,
+        None => FilterPipeline::default(),
+    };
+    let reflectivity = args.reflectivity.as_deref().map(load_model).transpose()?;
+    let frames = Frames::default();
+    let mut converter = pipeline.point_converter(&frames);
+    if let Some(path) = &args.calibration {
+        converter = converter.with_calibration(load_calibration(path)?);
+    }
+    let origin = Point3::from(frames.lidar_to_base().translation.vector);
+
+    let output: Box<dyn Write> = match &args.output {
+        Some(path) => {
+            Box::new(BufWriter::new(File::create(path).with_context(|| {
+                format!("failed to create {}", path.display())
+            })?))
+        }
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut writer = csv::Writer::from_writer(output);
+    writer.write_record([
+        "stamp",
+        "seq",
+        "x",
+        "y",
+        "z",
+        "range",
+        "intensity",
+        "time",
+        "returned",
+    ])?;
+
+    let mut packets = args.source.open().await?;
+    let mut packet_count = 0;
+    let mut points = Vec::new();
+    let mut scans = ScanAssembler::new();
+    if let Some(period) = args.scan_period {
+        scans = scans.with_period(period);
+    }
+    while args.count.is_none_or(|count| packet_count < count)
+        && let Some(packet) = packets.next().await
+    {
+        let Packet::LidarPointData(data) = packet? else {
+            continue;
+        };
+        packet_count += 1;
+
+        points.clear();
+        converter.convert_into(&data, &mut points);
+        pipeline.apply(&mut points);
+        if let Some(scan) = scans.push(&data, &points) {
+            write_scan(&mut writer, scan, reflectivity.as_ref(), &origin)?;
+        }
+    }
+    if let Some(scan) = scans.finish() {
+        write_scan(&mut writer, scan, reflectivity.as_ref(), &origin)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes the points of a scan labelled with their packets.
+///
+/// The reflectivity model is applied to whole scans as it needs enough points to estimate the
+/// surface normals.
+fn write_scan<W: Write>(
+    writer: &mut csv::Writer<W>,
+    mut scan: Scan,
+    reflectivity: Option<&ReflectivityModel>,
+    origin: &Point3<f32>,
+) -> Result<()> {
+    if let Some(model) = reflectivity {
+        model.apply(&mut scan.points, origin);
+    }
+
+    for (packet, points) in scan.packets() {
+        let stamp = packet.stamp.as_secs_f64().to_string();
+        let seq = packet.seq.to_string();
+        for point in points {
+            writer.write_record([
+                stamp.clone(),
+                seq.clone(),
+                point.position.x.to_string(),
+                point.position.y.to_string(),
+                point.position.z.to_string(),
+                point.range.to_string(),
+                point.intensity.to_string(),
+                point.time.to_string(),
+                point.returned.to_string(),
+            ])?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn load_pipeline(path: &Path) -> Result<FilterPipeline> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    serde_json::from_reader(BufReader::new(file))
+        .with_context(|| format!("failed to parse {}", path.display()))
+}
@@ -0,0 +1,139 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context, Result, bail};
+use clap::Args;
+use futures::StreamExt;
+use l2_processing::{
+    FilterPipeline, Frames, PointFilter, ReflectivityCalibration, ReflectivityModel, ScanAssembler,
+};
+use l2_protocol::Packet;
+use nalgebra::Point3;
+
+use crate::{device::parse_timeout, points::load_pipeline, source::SourceArgs};
+
+/// Fits a reflectivity model from a recording of a flat target with known reflectivity
+#[derive(Debug, Args)]
+pub(crate) struct CalibrateIntensityArgs {
+    #[command(flatten)]
+    source: SourceArgs,
+    /// JSON file describing the filter pipeline
+    #[arg(long)]
+    filters: Option<PathBuf>,
+    /// corner of the box containing the target with the smallest coordinates, e.g. `1,-0.5,0` [m]
+    #[arg(long, value_parser = parse_position)]
+    target_min: [f32; 3],
+    /// corner of the box containing the target with the largest coordinates [m]
+    #[arg(long, value_parser = parse_position)]
+    target_max: [f32; 3],
+    /// reflectivity of the target, between 0 and 1
+    #[arg(long)]
+    reflectivity: f32,
+    /// duration of the scans the target's plane is fit to [s]; defaults to the scan period
+    /// reported by the lidar
+    #[arg(long, value_parser = parse_timeout)]
+    scan_period: Option<Duration>,
+    /// width of the range bins one gain is fit for [m]
+    #[arg(long, default_value_t = 0.5)]
+    bin_width: f32,
+    /// bins with fewer samples are skipped
+    #[arg(long, default_value_t = 20)]
+    min_samples: usize,
+    /// stop after this many point data packets
+    #[arg(long)]
+    count: Option<u64>,
+    /// JSON file to write the model to
+    #[arg(long, short)]
+    output: PathBuf,
+}
+
+pub(crate) async fn calibrate(args: CalibrateIntensityArgs) -> Result<()> {
+    if !(args.reflectivity > 0.0 && args.reflectivity <= 1.0) {
+        bail!("reflectivity should be in (0, 1]");
+    }
+    let pipeline = match &args.filters {
+        Some(path) => load_pipeline(path)?,
+        None => FilterPipeline::default(),
+    };
+    let frames = Frames::default();
+    let converter = pipeline.point_converter(&frames);
+    let origin = Point3::from(frames.lidar_to_base().translation.vector);
+    // keeps the points the body box would remove
+    let target = PointFilter::BodyBox {
+        min: args.target_min,
+        max: args.target_max,
+    };
+    let mut calibration = ReflectivityCalibration::new(args.reflectivity)
+        .with_bin_width(args.bin_width)
+        .with_min_samples(args.min_samples);
+
+    let mut packets = args.source.open().await?;
+    let mut packet_count = 0;
+    let mut points = Vec::new();
+    let mut scans = ScanAssembler::new();
+    if let Some(period) = args.scan_period {
+        scans = scans.with_period(period);
+    }
+    while args.count.is_none_or(|count| packet_count < count)
+        && let Some(packet) = packets.next().await
+    {
+        let Packet::LidarPointData(data) = packet? else {
+            continue;
+        };
+        packet_count += 1;
+
+        points.clear();
+        converter.convert_into(&data, &mut points);
+        pipeline.apply(&mut points);
+        points.retain(|point| !target.keeps(point));
+        if let Some(scan) = scans.push(&data, &points) {
+            calibration.add_scan(&scan.points, &origin);
+        }
+    }
+    if let Some(scan) = scans.finish() {
+        calibration.add_scan(&scan.points, &origin);
+    }
+
+    let Some(model) = calibration.fit() else {
+        bail!(
+            "no range bin has enough samples of the target among {}",
+            calibration.sample_count()
+        );
+    };
+    let mut writer = BufWriter::new(
+        File::create(&args.output)
+            .with_context(|| format!("failed to create {}", args.output.display()))?,
+    );
+    serde_json::to_writer_pretty(&mut writer, &model)?;
+    writeln!(writer)?;
+    writer.flush()?;
+    println!(
+        "fit {} range bins from {} samples",
+        model.ranges.len(),
+        calibration.sample_count()
+    );
+    Ok(())
+}
+
+pub(crate) fn load_model(path: &Path) -> Result<ReflectivityModel> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    serde_json::from_reader(BufReader::new(file))
+        .with_context(|| format!("failed to parse {}", path.display()))
+}
+
+fn parse_position(value: &str) -> Result<[f32; 3]> {
+    let mut position = [0.0; 3];
+    let mut parts = value.split(',');
+    for coord in &mut position {
+        let part = parts.next().context("expected 3 coordinates")?;
+        *coord = part.trim().parse()?;
+    }
+    if parts.next().is_some() {
+        bail!("expected 3 coordinates");
+    }
+    Ok(position)
+}
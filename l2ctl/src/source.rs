@@ -0,0 +1,150 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow, bail};
+use bytes::BytesMut;
+use clap::Args;
+use etherparse::{Ethernet2Slice, Ipv4Slice, UdpSlice};
+use futures::{
+    StreamExt,
+    stream::{self, BoxStream},
+};
+use l2_protocol::{
+    DEFAULT_BAUD_RATE, DEFAULT_LIDAR_ADDR, DEFAULT_USER_ADDR, Packet, PacketCodec, SerialSource,
+    UdpSource,
+};
+use pcap_parser::{Block, PcapBlockOwned, PcapError, PcapNGReader, traits::PcapReaderIterator};
+use tokio::fs;
+use tokio_util::codec::{Decoder, FramedRead};
+
+/// Where to read packets from
+#[derive(Debug, Args)]
+pub(crate) struct SourceArgs {
+    #[command(flatten)]
+    kind: SourceKind,
+    /// baud rate of the serial port
+    #[arg(long, default_value_t = DEFAULT_BAUD_RATE)]
+    baud: u32,
+    /// local address to receive UDP packets on
+    #[arg(long, default_value_t = DEFAULT_USER_ADDR)]
+    bind: SocketAddr,
+}
+
+#[derive(Debug, Args)]
+#[group(required = true, multiple = false)]
+struct SourceKind {
+    /// serial port the lidar is connected to, e.g. `/dev/ttyACM0`
+    #[arg(long)]
+    serial: Option<PathBuf>,
+    /// address of the lidar, e.g. `192.168.1.62:6101`
+    #[arg(long)]
+    udp: Option<SocketAddr>,
+    /// network capture containing the lidar's UDP traffic
+    #[arg(long)]
+    pcapng: Option<PathBuf>,
+    /// raw recording of concatenated frames
+    #[arg(long)]
+    recording: Option<PathBuf>,
+}
+
+impl SourceArgs {
+    /// Opens the selected source as a stream of packets.
+    pub(crate) async fn open(&self) -> Result<BoxStream<'static, Result<Packet>>> {
+        let SourceKind {
+            serial,
+            udp,
+            pcapng,
+            recording,
+        } = &self.kind;
+
+        if let Some(path) = serial {
+            let path = path
+                .to_str()
+                .ok_or_else(|| anyhow!("invalid serial port: {}", path.display()))?;
+            Ok(SerialSource::open(path, self.baud)?.boxed())
+        } else if let Some(lidar_addr) = udp {
+            Ok(UdpSource::bind(self.bind, *lidar_addr).await?.boxed())
+        } else if let Some(path) = pcapng {
+            Ok(stream::iter(PcapngSource::open(path)?).boxed())
+        } else if let Some(path) = recording {
+            let file = fs::File::open(path).await?;
+            Ok(FramedRead::new(file, PacketCodec::new()).boxed())
+        } else {
+            unreachable!("clap should've required a source")
+        }
+    }
+}
+
+/// Reads the packets sent by the lidar from a pcapng capture
+struct PcapngSource {
+    reader: PcapNGReader<File>,
+    codec: PacketCodec,
+    pending: VecDeque<Packet>,
+}
+
+impl PcapngSource {
+    fn open(path: &Path) -> Result<Self> {
+        let reader = PcapNGReader::new(0x0001_0000, File::open(path)?)
+            .map_err(|error| anyhow!("failed to read capture: {error:?}"))?;
+
+        Ok(Self {
+            reader,
+            codec: PacketCodec::new(),
+            pending: VecDeque::new(),
+        })
+    }
+
+    fn read_next(&mut self) -> Result<Option<Packet>> {
+        loop {
+            if let Some(packet) = self.pending.pop_front() {
+                return Ok(Some(packet));
+            }
+
+            match self.reader.next() {
+                Ok((offset, block)) => {
+                    if let PcapBlockOwned::NG(Block::EnhancedPacket(block)) = block
+                        && let Some(datagram) = lidar_datagram(block.data)
+                    {
+                        // datagrams are decoded like the UDP source does
+                        let mut buf = BytesMut::from(datagram);
+                        while let Some(packet) = self.codec.decode_eof(&mut buf)? {
+                            self.pending.push_back(packet);
+                        }
+                    }
+                    self.reader.consume(offset);
+                }
+                Err(PcapError::Eof) => return Ok(None),
+                Err(PcapError::Incomplete(_)) => {
+                    if self.reader.reader_exhausted() {
+                        bail!("capture is truncated");
+                    }
+                    self.reader
+                        .refill()
+                        .map_err(|error| anyhow!("failed to read capture: {error:?}"))?;
+                }
+                Err(error) => bail!("failed to read capture: {error:?}"),
+            }
+        }
+    }
+}
+
+impl Iterator for PcapngSource {
+    type Item = Result<Packet>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_next().transpose()
+    }
+}
+
+/// Extracts the payload of UDP datagrams sent from the lidar's port
+fn lidar_datagram(frame: &[u8]) -> Option<&[u8]> {
+    let ethernet = Ethernet2Slice::from_slice_without_fcs(frame).ok()?;
+    let ipv4 = Ipv4Slice::from_slice(ethernet.payload().payload).ok()?;
+    let udp = UdpSlice::from_slice(ipv4.payload().payload).ok()?;
+
+    (udp.source_port() == DEFAULT_LIDAR_ADDR.port()).then(|| udp.payload())
+}